@@ -0,0 +1,16 @@
+#version 330 core
+
+out vec4 color;
+in vec2 v_uv;
+
+// a vertical strip with one frame for each stage of breaking
+uniform sampler2D cracks;
+uniform int stage;
+uniform int stage_count;
+
+void main() {
+    vec2 uv = vec2(v_uv.x, (v_uv.y + float(stage)) / float(stage_count));
+    vec4 sample = texture(cracks, uv);
+    if (sample.a == 0.0) discard;
+    color = sample;
+}
@@ -0,0 +1,15 @@
+#version 330 core
+
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec2 uv;
+
+uniform mat4 projection_matrix;
+uniform mat4 view_matrix;
+uniform mat4 model_matrix;
+
+out vec2 v_uv;
+
+void main() {
+    gl_Position = projection_matrix * view_matrix * model_matrix * vec4(pos, 1.0);
+    v_uv = uv;
+}
@@ -0,0 +1,14 @@
+#version 330 core
+
+out vec4 color;
+in vec2 v_uv;
+flat in int v_layer;
+in float v_light;
+
+uniform sampler2DArray texture_map;
+
+void main() {
+    vec4 sample = texture(texture_map, vec3(v_uv, v_layer));
+    if (sample.a == 0.0) discard;
+    color = vec4(sample.rgb * v_light, sample.a);
+}
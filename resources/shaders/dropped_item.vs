@@ -0,0 +1,20 @@
+#version 330 core
+
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec2 uv;
+layout (location = 2) in int layer;
+layout (location = 3) in float light;
+
+uniform mat4 projection_matrix;
+uniform mat4 view_matrix;
+
+out vec2 v_uv;
+flat out int v_layer;
+out float v_light;
+
+void main() {
+    gl_Position = projection_matrix * view_matrix * vec4(pos, 1.0);
+    v_uv = uv;
+    v_layer = layer;
+    v_light = light;
+}
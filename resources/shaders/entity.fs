@@ -0,0 +1,22 @@
+#version 330 core
+
+out vec4 color;
+in vec2 v_uv;
+in vec3 v_normal;
+
+uniform sampler2D model_texture;
+uniform vec3 sun_direction;
+
+const float NIGHT_LIGHT = 0.25;
+const float SHADE = 0.4;
+
+void main() {
+    vec4 sample = texture(model_texture, v_uv);
+    if (sample.a == 0.0) discard;
+
+    // how much the sun is up, like the light level that mobs spawn in
+    float daylight = clamp(-sun_direction.y * 2.0, 0.0, 1.0);
+    float facing_sun = max(dot(normalize(v_normal), -sun_direction), 0.0);
+    float light = mix(NIGHT_LIGHT, 1.0, daylight) * (1.0 - SHADE + SHADE * facing_sun);
+    color = vec4(sample.rgb * light, sample.a);
+}
@@ -0,0 +1,39 @@
+#version 330 core
+
+#define MAX_PARTS 16
+
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec3 normal;
+layout (location = 2) in vec2 uv;
+layout (location = 3) in int part;
+// the columns of the instance's model matrix
+layout (location = 4) in vec4 model_x;
+layout (location = 5) in vec4 model_y;
+layout (location = 6) in vec4 model_z;
+layout (location = 7) in vec4 model_w;
+layout (location = 8) in float walk_phase;
+layout (location = 9) in float walk_amount;
+
+uniform mat4 projection_matrix;
+uniform mat4 view_matrix;
+uniform vec3 part_pivots[MAX_PARTS];
+uniform float part_swing[MAX_PARTS];
+uniform float part_phase[MAX_PARTS];
+
+out vec2 v_uv;
+out vec3 v_normal;
+
+void main() {
+    // parts swing back and forth around the X axis as the entity walks
+    float angle = part_swing[part] * sin(walk_phase + part_phase[part]) * walk_amount;
+    float c = cos(angle);
+    float s = sin(angle);
+    mat3 swing = mat3(1.0, 0.0, 0.0, 0.0, c, s, 0.0, -s, c);
+    vec3 pivot = part_pivots[part];
+    vec3 local = pivot + swing * (pos - pivot);
+
+    mat4 model = mat4(model_x, model_y, model_z, model_w);
+    gl_Position = projection_matrix * view_matrix * model * vec4(local, 1.0);
+    v_uv = uv;
+    v_normal = mat3(model) * swing * normal;
+}
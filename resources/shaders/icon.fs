@@ -0,0 +1,13 @@
+#version 330 core
+
+out vec4 color;
+in vec2 v_uv;
+flat in int v_layer;
+
+uniform sampler2DArray texture_map;
+
+void main() {
+    vec4 sample = texture(texture_map, vec3(v_uv, v_layer));
+    if (sample.a == 0.0) discard;
+    color = sample;
+}
@@ -0,0 +1,11 @@
+#version 330 core
+
+layout (location = 0) in vec3 pos;
+
+uniform mat4 model_matrix;
+uniform mat4 light_matrix;
+
+void main()
+{
+    gl_Position = light_matrix * model_matrix * vec4(pos, 1.0);
+}
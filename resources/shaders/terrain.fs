@@ -3,9 +3,22 @@
 #define MIN_AO 0.5
 #define AO_CURVE 0.8
 
+// has to match `CASCADES` in `render/shadow.rs`
+#define CASCADES 3
+// how bright a fully shadowed surface is compared to a lit one
+#define SHADOW_BRIGHTNESS 0.55
+
 uniform vec3 camera_position;
 uniform vec3 ambient_light;
 uniform sampler2DArray texture_map;
+// off while drawing translucent faces, which blend instead
+uniform bool alpha_test;
+
+uniform bool shadows_enabled;
+uniform sampler2DArrayShadow shadow_map;
+uniform mat4 light_matrices[CASCADES];
+uniform float cascade_splits[CASCADES];
+uniform vec3 sun_direction;
 
 in vec3 v_pos;
 in vec3 v_normal;
@@ -13,6 +26,7 @@ in vec3 v_face_scalar;
 in vec2 v_uv;
 flat in int v_tex_id;
 in float v_ao;
+in float v_view_depth;
 
 out vec4 color;
 
@@ -20,15 +34,50 @@ vec2 uv_wrap(vec2 uv) {
     return vec2(mod(uv.x, 1.0), mod(uv.y, 1.0));
 }
 
+// 1.0 when the fragment is fully lit, 0.0 when it is fully in shadow
+float shadow_factor() {
+    if (!shadows_enabled) return 1.0;
+
+    // faces pointing away from the sun can't be lit by it
+    if (dot(v_normal, -sun_direction) <= 0.0) return 0.0;
+
+    int cascade = -1;
+    for (int i = 0; i < CASCADES; i++) {
+        if (v_view_depth < cascade_splits[i]) {
+            cascade = i;
+            break;
+        }
+    }
+    if (cascade < 0) return 1.0;
+
+    vec4 light_pos = light_matrices[cascade] * vec4(v_pos, 1.0);
+    vec3 coords = light_pos.xyz / light_pos.w * 0.5 + 0.5;
+
+    // 3x3 PCF, where each tap is already bilinearly filtered by the comparison sampler
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0).xy);
+    float lit = 0.0;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec2 offset = vec2(x, y) * texel;
+            lit += texture(shadow_map, vec4(coords.xy + offset, float(cascade), coords.z));
+        }
+    }
+
+    return lit / 9.0;
+}
+
 void main()
 {
     float density = 0.007;
     float gradient = 5.0;
     float fog = exp(-pow(length(camera_position - v_pos) * density, gradient));
     vec4 tex_color = texture(texture_map, vec3(uv_wrap(v_uv), float(v_tex_id)));
+    // cutout textures like plants are either fully transparent or fully opaque
+    if (alpha_test && tex_color.a < 0.5) discard;
     // return ((n-start1)/(stop1-start1))*(stop2-start2)+start2;
     float ao = pow(v_ao, 1.0 / AO_CURVE) * (1.0 - MIN_AO) + MIN_AO;
-    vec4 col = vec4(v_face_scalar * ao * ambient_light, 1.0) * tex_color;
+    float shadow = mix(SHADOW_BRIGHTNESS, 1.0, shadow_factor());
+    vec4 col = vec4(v_face_scalar * ao * shadow * ambient_light, 1.0) * tex_color;
 
     color = mix(vec4(0.729411765, 0.907843137, 0.981568627, 1.0), col, fog);
 }
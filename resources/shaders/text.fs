@@ -0,0 +1,13 @@
+#version 330 core
+
+out vec4 color;
+in vec2 v_uv;
+in vec4 v_color;
+
+uniform sampler2D font;
+
+void main() {
+    vec4 sample = texture(font, v_uv);
+    if (sample.a == 0.0) discard;
+    color = sample * v_color;
+}
@@ -0,0 +1,19 @@
+#version 330 core
+
+layout (location = 0) in vec2 pos;
+layout (location = 1) in vec2 uv;
+layout (location = 2) in vec4 color;
+
+// size of the window, in pixels
+uniform vec2 resolution;
+
+out vec2 v_uv;
+out vec4 v_color;
+
+void main() {
+    vec2 ndc = pos / resolution * 2.0 - 1.0;
+    // pixel coordinates go down the screen, but NDC go up
+    gl_Position = vec4(ndc.x, -ndc.y, 0.0, 1.0);
+    v_uv = uv;
+    v_color = color;
+}
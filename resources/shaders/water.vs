@@ -7,6 +7,19 @@ layout (location = 3) in int tex_id;
 
 uniform float time;
 
+// has to match `MAX_ANIMATIONS` and `MAX_ANIMATION_FRAMES` in `render/terrain.rs`
+#define MAX_ANIMATIONS 16
+#define MAX_ANIMATION_FRAMES 128
+
+uniform int animation_count;
+// the layer of the first frame of each animation, which is what faces point at
+uniform int animation_layers[MAX_ANIMATIONS];
+// where the frames of each animation start in `frame_ends`
+uniform int animation_frames_start[MAX_ANIMATIONS];
+uniform int animation_frame_counts[MAX_ANIMATIONS];
+// the time each frame stops being shown, counted from the start of its animation
+uniform float frame_ends[MAX_ANIMATION_FRAMES];
+
 uniform mat4 model_matrix;
 uniform mat4 projection_matrix;
 uniform mat4 view_matrix;
@@ -17,13 +30,29 @@ out vec3 v_face_scalar;
 out vec2 v_uv;
 flat out int v_tex_id;
 
+// the layer to sample for a texture, which changes over time if it is animated
+int current_layer(int layer) {
+    for (int i = 0; i < animation_count; i++) {
+        if (animation_layers[i] != layer) continue;
+
+        int start = animation_frames_start[i];
+        int count = animation_frame_counts[i];
+        float t = mod(time, frame_ends[start + count - 1]);
+        for (int frame = 0; frame < count - 1; frame++) {
+            if (t < frame_ends[start + frame]) return layer + frame;
+        }
+        return layer + count - 1;
+    }
+    return layer;
+}
+
 void main()
 {
     gl_Position = projection_matrix * view_matrix * model_matrix * vec4(pos, 1.0);
     v_pos = vec3(model_matrix * vec4(pos, 1.0));
     v_normal = normal;
     v_uv = uv;
-    v_tex_id = tex_id;
+    v_tex_id = current_layer(tex_id);
 
     if (normal.y == 1.0) v_face_scalar = vec3(1.0);
     if (normal.y == -1.0) v_face_scalar = vec3(0.5);
@@ -0,0 +1,262 @@
+use engine::world::gen::GeneratorKind;
+use std::{fmt, path::PathBuf};
+
+pub const USAGE: &str = "\
+Usage: notcraft [command] [options]
+
+Commands:
+    validate-resources     Checks the block, item, model and mob definitions and
+                           textures in the resources and every pack, then exits
+                           without opening a window
+
+Options:
+    --world <dir>          Directory the world is stored in. The seed, generator and
+                           player are saved there, so the same world can be opened
+                           again
+    --seed <seed>          Seed for generating a new world
+    --generator <kind>     Terrain generator for a new world: `noise` or `flat`
+    --resources <dir>      Where to load resources from [default: resources]
+    --pack <path>          A resource pack directory or zip archive that overrides
+                           and extends the resources. Can be given more than once,
+                           and later packs win over earlier ones
+    --size <width>x<height>
+                           Size of the window, overriding the settings file
+    --log-level <level>    One of `error`, `warn`, `info`, `debug` or `trace`
+                           [default: debug]
+    --script <file>        Console commands to run once the game has started
+    --record <file>        Records input to a file
+    --replay <file>        Plays back input recorded with `--record`
+    -h, --help             Prints this message";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CliError {
+    /// `--help` was passed.
+    Help,
+    Invalid(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Help => write!(f, "help requested"),
+            CliError::Invalid(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Command {
+    Play,
+    ValidateResources,
+}
+
+/// Options passed to the game on the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    pub command: Command,
+    pub world: Option<PathBuf>,
+    pub seed: Option<u32>,
+    pub generator: Option<GeneratorKind>,
+    pub resources: PathBuf,
+    pub packs: Vec<PathBuf>,
+    /// Overrides the window size in the settings for this launch only, without
+    /// changing the settings that get saved.
+    pub window_size: Option<(u32, u32)>,
+    pub log_level: log::Level,
+    pub script: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            command: Command::Play,
+            world: None,
+            seed: None,
+            generator: None,
+            resources: PathBuf::from("resources"),
+            packs: Vec::new(),
+            window_size: None,
+            log_level: log::Level::Debug,
+            script: None,
+            record: None,
+            replay: None,
+        }
+    }
+}
+
+fn parse_size(value: &str) -> Option<(u32, u32)> {
+    let mut parts = value.splitn(2, 'x');
+    let width = parts.next()?.parse().ok()?;
+    let height = parts.next()?.parse().ok()?;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    Some((width, height))
+}
+
+fn invalid(flag: &str, value: &str, expected: &str) -> CliError {
+    CliError::Invalid(format!(
+        "invalid value `{}` for `{}`: expected {}",
+        value, flag, expected
+    ))
+}
+
+impl Options {
+    /// The base resources followed by every pack, in the order they should be
+    /// given to the `AssetManager`.
+    pub fn resource_packs(&self) -> Vec<PathBuf> {
+        let mut packs = vec![self.resources.clone()];
+        packs.extend(self.packs.iter().cloned());
+        packs
+    }
+
+    pub fn from_env() -> Result<Self, CliError> {
+        Options::parse(::std::env::args().skip(1))
+    }
+
+    /// Parses options from a list of arguments, not including the name of the
+    /// program. Values can either be passed as the next argument or after an
+    /// `=`, like `--seed=42`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, CliError> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                return Err(CliError::Help);
+            }
+
+            let (flag, inline_value) = match arg.find('=') {
+                Some(idx) if arg.starts_with("--") => {
+                    (arg[..idx].to_owned(), Some(arg[idx + 1..].to_owned()))
+                }
+                _ => (arg.clone(), None),
+            };
+
+            if !flag.starts_with("--") {
+                match arg.as_str() {
+                    "validate-resources" if options.command == Command::Play => {
+                        options.command = Command::ValidateResources;
+                        continue;
+                    }
+                    _ => return Err(CliError::Invalid(format!("unexpected argument `{}`", arg))),
+                }
+            }
+
+            let value = match inline_value.or_else(|| args.next()) {
+                Some(value) => value,
+                None => return Err(CliError::Invalid(format!("`{}` needs a value", flag))),
+            };
+
+            match flag.as_str() {
+                "--world" => options.world = Some(value.into()),
+                "--seed" => {
+                    let seed = value
+                        .parse()
+                        .map_err(|_| invalid(&flag, &value, "a number"))?;
+                    options.seed = Some(seed);
+                }
+                "--generator" => {
+                    let generator = value.parse().map_err(CliError::Invalid)?;
+                    options.generator = Some(generator);
+                }
+                "--resources" => options.resources = value.into(),
+                "--pack" => options.packs.push(value.into()),
+                "--size" => {
+                    let size = parse_size(&value)
+                        .ok_or_else(|| invalid(&flag, &value, "a size like `1280x720`"))?;
+                    options.window_size = Some(size);
+                }
+                "--log-level" => {
+                    options.log_level = value
+                        .parse()
+                        .map_err(|_| invalid(&flag, &value, "a log level like `info`"))?;
+                }
+                "--script" => options.script = Some(value.into()),
+                "--record" => options.record = Some(value.into()),
+                "--replay" => options.replay = Some(value.into()),
+                _ => return Err(CliError::Invalid(format!("unknown option `{}`", flag))),
+            }
+        }
+
+        if options.record.is_some() && options.replay.is_some() {
+            return Err(CliError::Invalid(
+                "`--record` and `--replay` can't be used together".into(),
+            ));
+        }
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, CliError> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_are_the_defaults() {
+        assert_eq!(parse(&[]), Ok(Options::default()));
+    }
+
+    #[test]
+    fn values_can_be_separate_or_inline() {
+        let options = parse(&["--seed", "42", "--generator=flat", "--size=800x600"]).unwrap();
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.generator, Some(GeneratorKind::Flat));
+        assert_eq!(options.window_size, Some((800, 600)));
+    }
+
+    #[test]
+    fn packs_keep_their_order() {
+        let options = parse(&["--pack", "a", "--resources", "base", "--pack=b.zip"]).unwrap();
+        assert_eq!(
+            options.resource_packs(),
+            [
+                PathBuf::from("base"),
+                PathBuf::from("a"),
+                PathBuf::from("b.zip")
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_resources_command() {
+        let options = parse(&["validate-resources", "--pack", "a"]).unwrap();
+        assert_eq!(options.command, Command::ValidateResources);
+        assert!(parse(&["validate-resources", "validate-resources"]).is_err());
+    }
+
+    #[test]
+    fn help() {
+        assert_eq!(parse(&["--seed", "1", "-h"]), Err(CliError::Help));
+        assert_eq!(parse(&["--help"]), Err(CliError::Help));
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let invalid = |args: &[&str], msg: &str| {
+            assert_eq!(parse(args), Err(CliError::Invalid(msg.into())));
+        };
+        invalid(&["--seed"], "`--seed` needs a value");
+        invalid(
+            &["--seed", "many"],
+            "invalid value `many` for `--seed`: expected a number",
+        );
+        invalid(
+            &["--size=0x600"],
+            "invalid value `0x600` for `--size`: expected a size like `1280x720`",
+        );
+        invalid(&["--fast", "yes"], "unknown option `--fast`");
+        invalid(&["world"], "unexpected argument `world`");
+        invalid(
+            &["--record", "a", "--replay", "b"],
+            "`--record` and `--replay` can't be used together",
+        );
+    }
+}
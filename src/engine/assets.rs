@@ -0,0 +1,396 @@
+use gl_api::{
+    context::Context,
+    shader::{
+        pipeline_from_source,
+        program::{LinkError, Program},
+        shader::ShaderError,
+        PipelineError,
+    },
+};
+use image::{Rgba, RgbaImage};
+use shrev::{EventChannel, ReaderId};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+use zip::{result::ZipError, ZipArchive};
+
+/// Sent when an asset in a pack directory was changed, added or removed.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct AssetChanged(pub String);
+
+/// The assets that changed since a system last read its `AssetChanged`
+/// reader.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ChangedAssets(Vec<String>);
+
+impl ChangedAssets {
+    pub fn read(changes: &EventChannel<AssetChanged>, reader: &mut ReaderId<AssetChanged>) -> Self {
+        ChangedAssets(
+            changes
+                .read(reader)
+                .map(|AssetChanged(asset)| asset.clone())
+                .collect(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether any asset starting with `prefix` changed, like `textures/` for
+    /// every texture or `shaders/terrain.` for both halves of a shader.
+    pub fn any(&self, prefix: &str) -> bool {
+        self.0.iter().any(|asset| asset.starts_with(prefix))
+    }
+}
+
+/// Used in place of shaders that don't compile. Every vertex ends up outside
+/// of the screen, so nothing gets drawn with it.
+const PLACEHOLDER_VERTEX_SHADER: &str = "#version 330 core
+void main() {
+    gl_Position = vec4(0.0, 0.0, 0.0, 0.0);
+}
+";
+const PLACEHOLDER_FRAGMENT_SHADER: &str = "#version 330 core
+out vec4 color;
+void main() {
+    color = vec4(1.0, 0.0, 1.0, 1.0);
+}
+";
+
+/// A place assets can be loaded from.
+enum Pack {
+    Dir(PathBuf),
+    /// A zip archive with the same layout as a pack directory. Reading from an
+    /// archive needs mutable access, so it is kept behind a lock.
+    Zip(PathBuf, Mutex<ZipArchive<File>>),
+}
+
+impl Pack {
+    fn open(path: &Path) -> io::Result<Self> {
+        if path.is_dir() {
+            return Ok(Pack::Dir(path.into()));
+        }
+
+        let archive = ZipArchive::new(File::open(path)?).map_err(zip_to_io)?;
+        Ok(Pack::Zip(path.into(), Mutex::new(archive)))
+    }
+
+    fn location(&self) -> &Path {
+        match self {
+            Pack::Dir(path) | Pack::Zip(path, _) => path,
+        }
+    }
+
+    fn read(&self, asset: &str) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        match self {
+            Pack::Dir(dir) => {
+                File::open(dir.join(asset))?.read_to_end(&mut buf)?;
+            }
+            Pack::Zip(_, archive) => {
+                let mut archive = archive.lock().unwrap();
+                archive
+                    .by_name(asset)
+                    .map_err(zip_to_io)?
+                    .read_to_end(&mut buf)?;
+            }
+        }
+        Ok(buf)
+    }
+
+    /// Every asset in the pack that is inside `dir`, at any depth.
+    fn list(&self, dir: &str, assets: &mut BTreeSet<String>) -> io::Result<()> {
+        match self {
+            Pack::Dir(root) => walk_dir(root, &root.join(dir), assets),
+            Pack::Zip(_, archive) => {
+                let prefix = format!("{}/", dir.trim_end_matches('/'));
+                let mut archive = archive.lock().unwrap();
+                for i in 0..archive.len() {
+                    let file = archive.by_index(i).map_err(zip_to_io)?;
+                    if file.name().starts_with(&prefix) && !file.name().ends_with('/') {
+                        assets.insert(file.name().into());
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn zip_to_io(err: ZipError) -> io::Error {
+    match err {
+        ZipError::Io(err) => err,
+        ZipError::FileNotFound => io::Error::new(io::ErrorKind::NotFound, err),
+        other => io::Error::new(io::ErrorKind::InvalidData, other),
+    }
+}
+
+fn walk_dir(root: &Path, dir: &Path, assets: &mut BTreeSet<String>) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk_dir(root, &path, assets)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let components: Vec<_> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            assets.insert(components.join("/"));
+        }
+    }
+
+    Ok(())
+}
+
+/// A magenta and black checkerboard, used in place of textures that couldn't
+/// be loaded so that they stand out in game.
+pub fn missing_texture(width: u32, height: u32) -> RgbaImage {
+    RgbaImage::from_fn(width, height, |x, y| {
+        let checker = (x * 2 / width.max(1) + y * 2 / height.max(1)) % 2 == 0;
+        if checker {
+            Rgba([255, 0, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    })
+}
+
+/// Loads assets by searching through an ordered list of packs. The first pack
+/// is the base game, and every pack after it can replace assets from the
+/// packs before it or add new ones.
+///
+/// Assets are named by their path inside a pack, with `/` between
+/// directories, like `textures/stone.png`.
+pub struct AssetManager {
+    packs: Vec<Pack>,
+    /// Assets that are currently broken, like shaders that don't compile,
+    /// along with what is wrong with them. They are shown in game until they
+    /// are fixed and reloaded.
+    problems: Mutex<BTreeMap<String, String>>,
+}
+
+impl AssetManager {
+    /// Opens each pack, which can either be a directory or a zip archive.
+    pub fn new<I>(packs: I) -> io::Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        let packs = packs
+            .into_iter()
+            .map(|path| {
+                let path = path.as_ref();
+                Pack::open(path).map_err(|err| {
+                    io::Error::new(
+                        err.kind(),
+                        format!("could not open pack {}: {}", path.display(), err),
+                    )
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        for pack in &packs {
+            info!("Using resource pack {}", pack.location().display());
+        }
+
+        Ok(AssetManager {
+            packs,
+            problems: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Reads an asset from the last pack that has it.
+    pub fn read(&self, asset: &str) -> io::Result<Vec<u8>> {
+        for pack in self.packs.iter().rev() {
+            match pack.read(asset) {
+                Ok(data) => return Ok(data),
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => {
+                    return Err(io::Error::new(
+                        err.kind(),
+                        format!(
+                            "could not read {} from {}: {}",
+                            asset,
+                            pack.location().display(),
+                            err
+                        ),
+                    ))
+                }
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not in any resource pack", asset),
+        ))
+    }
+
+    pub fn read_to_string(&self, asset: &str) -> io::Result<String> {
+        String::from_utf8(self.read(asset)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Reads an asset from every pack that has it, starting with the base
+    /// game. This is for files that packs extend instead of replacing, like
+    /// `blocks.json`. Each copy comes with a path to it for error messages.
+    pub fn read_all(&self, asset: &str) -> io::Result<Vec<(String, Vec<u8>)>> {
+        let mut found = Vec::new();
+        for pack in &self.packs {
+            match pack.read(asset) {
+                Ok(data) => {
+                    let location = pack.location().join(asset);
+                    found.push((location.display().to_string(), data));
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(found)
+    }
+
+    /// The names of all the assets inside `dir` in any pack.
+    pub fn list(&self, dir: &str) -> io::Result<Vec<String>> {
+        let mut assets = BTreeSet::new();
+        for pack in &self.packs {
+            pack.list(dir, &mut assets)?;
+        }
+        Ok(assets.into_iter().collect())
+    }
+
+    /// When each asset in a pack directory was last changed. Zip archives
+    /// aren't included, since they are only read when the game starts.
+    pub fn modification_times(&self) -> io::Result<HashMap<String, SystemTime>> {
+        let mut times = HashMap::new();
+        for pack in &self.packs {
+            if let Pack::Dir(root) = pack {
+                let mut assets = BTreeSet::new();
+                walk_dir(root, root, &mut assets)?;
+                for asset in assets {
+                    // later packs replace earlier ones, so their files are the ones to watch
+                    let modified = fs::metadata(root.join(&asset))?.modified()?;
+                    times.insert(asset, modified);
+                }
+            }
+        }
+        Ok(times)
+    }
+
+    /// Records that `asset` is broken, replacing anything that was recorded
+    /// for it before.
+    pub fn report_problem<S: Into<String>>(&self, asset: &str, message: S) {
+        self.problems
+            .lock()
+            .unwrap()
+            .insert(asset.into(), message.into());
+    }
+
+    /// Forgets about a problem with `asset`, once it has been fixed.
+    pub fn clear_problem(&self, asset: &str) {
+        self.problems.lock().unwrap().remove(asset);
+    }
+
+    /// Every asset that is currently broken, and what is wrong with it.
+    pub fn problems(&self) -> Vec<(String, String)> {
+        let problems = self.problems.lock().unwrap();
+        problems
+            .iter()
+            .map(|(asset, message)| (asset.clone(), message.clone()))
+            .collect()
+    }
+
+    /// Compiles a shader program from two assets, returning the compiler's
+    /// log if they don't compile. The error is also kept as a problem with
+    /// the shader until it compiles again.
+    pub fn try_load_shader(
+        &self,
+        ctx: &Context,
+        vert: &str,
+        frag: &str,
+    ) -> Result<Program, String> {
+        let name = format!("{} + {}", vert, frag);
+        let read = |asset: &str| self.read_to_string(asset).map_err(|err| err.to_string());
+        let compile = || -> Result<Program, String> {
+            let (vert_source, frag_source) = (read(vert)?, read(frag)?);
+            pipeline_from_source(ctx, &vert_source, &frag_source).map_err(|err| match err {
+                PipelineError::Shader(ShaderError::Shader(log)) => {
+                    format!("{} failed to compile:\n{}", name, log.trim_end())
+                }
+                PipelineError::Link(LinkError::Other(log)) => {
+                    format!("{} failed to link:\n{}", name, log.trim_end())
+                }
+                PipelineError::Shader(ShaderError::Io(err)) | PipelineError::Io(err) => {
+                    err.to_string()
+                }
+                other => format!("{}: {:?}", name, other),
+            })
+        };
+
+        let result = compile();
+        match &result {
+            Ok(_) => self.clear_problem(&name),
+            Err(err) => self.report_problem(&name, err.clone()),
+        }
+        result
+    }
+
+    /// Like `try_load_shader`, but for the shaders that are needed to start
+    /// the game. A shader that doesn't compile is replaced with one that draws
+    /// nothing, so that the game can keep running until it is fixed and hot
+    /// reloaded.
+    pub fn load_shader(&self, ctx: &Context, vert: &str, frag: &str) -> Program {
+        self.try_load_shader(ctx, vert, frag).unwrap_or_else(|err| {
+            error!("{}", err);
+            pipeline_from_source(ctx, PLACEHOLDER_VERTEX_SHADER, PLACEHOLDER_FRAGMENT_SHADER)
+                .expect("the placeholder shader always compiles")
+        })
+    }
+
+    pub fn load_image(&self, asset: &str) -> Result<RgbaImage, Box<Error>> {
+        Ok(image::load_from_memory(&self.read(asset)?)?.to_rgba())
+    }
+
+    /// Loads an image of any size, using a small `missing_texture` if it can't
+    /// be loaded.
+    pub fn load_image_or_missing(&self, asset: &str) -> RgbaImage {
+        self.load_image(asset).unwrap_or_else(|err| {
+            warn!("Could not load {}: {}", asset, err);
+            missing_texture(16, 16)
+        })
+    }
+
+    /// Loads an image that has to be exactly `width` by `height`, using
+    /// `missing_texture` if it can't be loaded or has the wrong size.
+    pub fn load_texture(&self, asset: &str, width: u32, height: u32) -> RgbaImage {
+        match self.load_image(asset) {
+            Ok(ref image) if image.width() != width || image.height() != height => {
+                warn!(
+                    "{} is {}x{}, but should be {}x{}",
+                    asset,
+                    image.width(),
+                    image.height(),
+                    width,
+                    height
+                );
+                missing_texture(width, height)
+            }
+            Ok(image) => image,
+            Err(err) => {
+                warn!("Could not load {}: {}", asset, err);
+                missing_texture(width, height)
+            }
+        }
+    }
+}
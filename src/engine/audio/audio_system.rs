@@ -1,9 +1,9 @@
-use engine::prelude::*;
+use engine::{assets::AssetManager, prelude::*};
 use rand::prelude::*;
 use rodio::{Decoder, Device, Sink, Source};
 use std::{
-    fs, io,
-    path::{Path, PathBuf},
+    io::{self, Cursor},
+    sync::Arc,
     time::{Duration, Instant},
 };
 
@@ -17,39 +17,25 @@ fn random_duration() -> Duration {
 struct AudioManagerInner {
     device: Device,
     music_sink: Sink,
+    assets: Arc<AssetManager>,
 }
 
-fn walk_dirs(path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
-    let dir_iter = fs::read_dir(path)?;
-
-    let mut items = vec![];
-    for entry in dir_iter {
-        let entry = entry?;
-        if entry.file_type()?.is_dir() {
-            items.extend(walk_dirs(entry.path())?);
-        } else {
-            items.push(entry.path());
-        }
-    }
-
-    Ok(items)
-}
-
-fn select_audio_file<P: AsRef<Path>>(dir: P) -> io::Result<Option<Decoder<fs::File>>> {
-    Ok(walk_dirs(dir)?
+fn select_audio_file(assets: &AssetManager) -> io::Result<Option<Decoder<Cursor<Vec<u8>>>>> {
+    Ok(assets
+        .list("audio")?
         .choose(&mut rand::thread_rng())
-        .map(|path| {
-            debug!("Trying to open {}", path.display());
-            fs::File::open(path)
+        .map(|asset| {
+            debug!("Trying to open {}", asset);
+            assets.read(asset)
         })
         .transpose()?
-        .and_then(|file| Decoder::new(file).ok()))
+        .and_then(|data| Decoder::new(Cursor::new(data)).ok()))
 }
 
 impl AudioManagerInner {
     fn try_play_music(&mut self) {
         if self.music_sink.empty() {
-            if let Some(Some(source)) = select_audio_file("resources/audio").ok() {
+            if let Some(Some(source)) = select_audio_file(&self.assets).ok() {
                 let duration = random_duration();
                 debug!("Playing music in {} seconds", duration.as_float_secs());
                 self.music_sink.append(source.delay(duration));
@@ -61,10 +47,11 @@ impl AudioManagerInner {
 pub struct AudioManager(Option<AudioManagerInner>);
 
 impl AudioManager {
-    pub fn new() -> Self {
+    pub fn new(assets: Arc<AssetManager>) -> Self {
         AudioManager(
             rodio::default_output_device().map(|device| AudioManagerInner {
                 music_sink: Sink::new(&device),
+                assets,
                 device,
             }),
         )
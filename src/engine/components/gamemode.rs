@@ -0,0 +1,66 @@
+use specs::prelude::*;
+use std::str::FromStr;
+
+/// How a player interacts with the world.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, Component)]
+#[serde(rename_all = "snake_case")]
+#[storage(HashMapStorage)]
+pub enum GameMode {
+    /// Players fall, breaking blocks takes time and drops their items, and
+    /// placing blocks uses them up.
+    Survival,
+    /// Players can fly, and blocks break straight away and can be placed
+    /// without running out.
+    Creative,
+    /// Players fly through blocks and can't change anything.
+    Spectator,
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Survival
+    }
+}
+
+impl GameMode {
+    /// The name used for the mode in console commands.
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+            GameMode::Spectator => "spectator",
+        }
+    }
+
+    /// Whether players in this mode bump into blocks.
+    pub fn collides(self) -> bool {
+        self != GameMode::Spectator
+    }
+
+    /// Whether players in this mode can be hurt.
+    pub fn takes_damage(self) -> bool {
+        self == GameMode::Survival
+    }
+
+    /// Whether players in this mode can break and place blocks and pick up
+    /// items.
+    pub fn interacts(self) -> bool {
+        self != GameMode::Spectator
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "survival" => Ok(GameMode::Survival),
+            "creative" => Ok(GameMode::Creative),
+            "spectator" => Ok(GameMode::Spectator),
+            _ => Err(format!(
+                "unknown game mode `{}`, expected `survival`, `creative` or `spectator`",
+                s
+            )),
+        }
+    }
+}
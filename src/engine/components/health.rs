@@ -0,0 +1,77 @@
+use specs::prelude::*;
+
+/// How much damage an entity can take before it dies, in half hearts.
+#[derive(Copy, Clone, Debug, PartialEq, Component)]
+#[storage(DenseVecStorage)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+    /// How long until the entity can be hurt again, in seconds. This stops
+    /// damage that happens every tick, like suffocating, from killing
+    /// straight away.
+    pub cooldown: f64,
+}
+
+impl Health {
+    pub fn new(max: u32) -> Self {
+        Health {
+            current: max,
+            max,
+            cooldown: 0.0,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+}
+
+/// How long an entity can stay under water before it starts drowning.
+#[derive(Copy, Clone, Debug, PartialEq, Component)]
+#[storage(DenseVecStorage)]
+pub struct Breath {
+    /// Seconds of air left.
+    pub remaining: f64,
+    pub max: f64,
+}
+
+impl Breath {
+    pub fn new(max: f64) -> Self {
+        Breath {
+            remaining: max,
+            max,
+        }
+    }
+}
+
+/// What hurt an entity.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DamageSource {
+    /// Hitting the ground too fast.
+    Fall,
+    /// Running out of air under water.
+    Drowning,
+    /// Having a solid block where the head is.
+    Suffocation,
+}
+
+impl DamageSource {
+    /// Says how a player died, to finish a sentence starting with their
+    /// name.
+    pub fn death_message(self) -> &'static str {
+        match self {
+            DamageSource::Fall => "hit the ground too hard",
+            DamageSource::Drowning => "drowned",
+            DamageSource::Suffocation => "suffocated in a wall",
+        }
+    }
+}
+
+/// Sent to hurt an entity. Entities without `Health` can't be hurt.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Damage {
+    pub entity: Entity,
+    /// In half hearts.
+    pub amount: u32,
+    pub source: DamageSource,
+}
@@ -0,0 +1,161 @@
+use engine::item::{ItemId, ItemRegistry};
+use specs::prelude::*;
+
+/// Number of slots in the hotbar, which are the first slots of an inventory.
+pub const HOTBAR_SIZE: usize = 9;
+
+/// Some number of the same item.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub count: u32,
+    /// How much of the item's durability has been used up. Only items with
+    /// the same damage stack together.
+    pub damage: u32,
+}
+
+impl ItemStack {
+    pub fn new(item: ItemId, count: u32) -> Self {
+        ItemStack {
+            item,
+            count,
+            damage: 0,
+        }
+    }
+}
+
+/// A stack of items lying in the world, which players pick up by walking into
+/// it.
+#[derive(Copy, Clone, Debug, PartialEq, Component)]
+#[storage(DenseVecStorage)]
+pub struct DroppedItem {
+    pub stack: ItemStack,
+    /// How long the item has been lying around, in seconds.
+    pub age: f64,
+}
+
+impl DroppedItem {
+    pub fn new(stack: ItemStack) -> Self {
+        DroppedItem { stack, age: 0.0 }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Component)]
+#[storage(HashMapStorage)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    /// The hotbar slot that is being held.
+    selected: usize,
+}
+
+impl Inventory {
+    /// An empty inventory with `size` slots, which has to be at least enough
+    /// for the hotbar.
+    pub fn new(size: usize) -> Self {
+        assert!(size >= HOTBAR_SIZE);
+        Inventory {
+            slots: vec![None; size],
+            selected: 0,
+        }
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn hotbar(&self) -> &[Option<ItemStack>] {
+        &self.slots[..HOTBAR_SIZE]
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_stack(&self) -> Option<ItemStack> {
+        self.slots[self.selected]
+    }
+
+    /// Selects a hotbar slot, ignoring slots past the end of the hotbar.
+    pub fn select(&mut self, slot: usize) {
+        if slot < HOTBAR_SIZE {
+            self.selected = slot;
+        }
+    }
+
+    /// Moves the selection by `offset` slots, wrapping around at either end
+    /// of the hotbar.
+    pub fn scroll(&mut self, offset: i32) {
+        let size = HOTBAR_SIZE as i32;
+        self.selected = ((self.selected as i32 + offset) % size + size) as usize % HOTBAR_SIZE;
+    }
+
+    /// Adds a stack of items, topping up matching stacks before using empty
+    /// slots. Returns how many items didn't fit.
+    pub fn insert(&mut self, items: &ItemRegistry, stack: ItemStack) -> u32 {
+        let max_stack = items.max_stack(stack.item);
+        let mut count = stack.count;
+
+        for other in self.slots.iter_mut().filter_map(Option::as_mut) {
+            let same = other.item == stack.item && other.damage == stack.damage;
+            if same && other.count < max_stack {
+                let moved = count.min(max_stack - other.count);
+                other.count += moved;
+                count -= moved;
+            }
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if count == 0 {
+                break;
+            }
+            let moved = count.min(max_stack);
+            *slot = Some(ItemStack {
+                count: moved,
+                ..stack
+            });
+            count -= moved;
+        }
+
+        count
+    }
+
+    /// Uses up some of the durability of the selected item, removing it if it
+    /// runs out. Returns whether the item broke. Items without durability
+    /// aren't affected.
+    pub fn wear_selected(&mut self, items: &ItemRegistry, amount: u32) -> bool {
+        let slot = &mut self.slots[self.selected];
+        let broke = match slot {
+            Some(stack) => match items.durability(stack.item) {
+                Some(durability) => {
+                    stack.damage += amount;
+                    stack.damage >= durability
+                }
+                None => false,
+            },
+            None => false,
+        };
+        if broke {
+            *slot = None;
+        }
+        broke
+    }
+
+    /// Empties the inventory, returning every stack that was in it.
+    pub fn drain(&mut self) -> Vec<ItemStack> {
+        self.slots.iter_mut().filter_map(Option::take).collect()
+    }
+
+    /// Removes one item from the selected stack and returns it.
+    pub fn take_selected(&mut self) -> Option<ItemStack> {
+        let slot = &mut self.slots[self.selected];
+        let taken = ItemStack {
+            count: 1,
+            ..(*slot)?
+        };
+        match slot {
+            Some(stack) if stack.count > 1 => stack.count -= 1,
+            _ => *slot = None,
+        }
+        Some(taken)
+    }
+}
@@ -0,0 +1,60 @@
+use cgmath::Deg;
+use engine::{
+    mob::MobId,
+    world::{path::Path, BlockPos},
+};
+use specs::prelude::*;
+use std::sync::Arc;
+
+/// An entity that moves around on its own, following the behaviours in its
+/// mob definition.
+#[derive(Copy, Clone, Debug, PartialEq, Component)]
+#[storage(DenseVecStorage)]
+pub struct Mob {
+    pub kind: MobId,
+    /// The index of the behaviour the mob is doing, or `None` if it hasn't
+    /// picked one yet.
+    pub behaviour: Option<usize>,
+    /// How long until the mob stops doing what it is doing, in seconds.
+    pub timer: f64,
+    /// Which way the mob is facing, around the Y axis.
+    pub heading: Deg<f64>,
+    /// Whether the mob is walking in the direction it is facing.
+    pub walking: bool,
+}
+
+impl Mob {
+    pub fn new(kind: MobId) -> Self {
+        Mob {
+            kind,
+            behaviour: None,
+            timer: 0.0,
+            heading: Deg(0.0),
+            walking: false,
+        }
+    }
+}
+
+/// Where an entity is trying to get to, and the way there. Paths are found in
+/// the background by the `Pathfinding` system, so one can take a few ticks to
+/// show up after the goal changes.
+#[derive(Clone, Debug, PartialEq, Default, Component)]
+#[storage(DenseVecStorage)]
+pub struct Navigation {
+    /// The block to walk to, set by whatever is controlling the entity.
+    pub goal: Option<BlockPos>,
+    /// The goal that `path` was searched for. The path is searched for again
+    /// when this isn't the same as `goal`, and the old one is kept until then.
+    pub path_goal: Option<BlockPos>,
+    /// The way to `path_goal`, or `None` if there is no way there.
+    pub path: Option<Arc<Path>>,
+    /// Index of the cell in the path to walk to next.
+    pub waypoint: usize,
+}
+
+impl Navigation {
+    /// Forgets the current path, so that a new one is searched for.
+    pub fn replan(&mut self) {
+        self.path_goal = None;
+    }
+}
@@ -1,12 +1,22 @@
 use specs::prelude::*;
 
+mod gamemode;
+mod health;
 mod input;
+mod inventory;
+mod mob;
+mod model;
 mod physics;
 mod transform;
 mod world;
 
+pub use self::gamemode::GameMode;
+pub use self::health::{Breath, Damage, DamageSource, Health};
 pub use self::input::{LookTarget, MoveDelta};
-pub use self::physics::{Collidable, RigidBody};
+pub use self::inventory::{DroppedItem, Inventory, ItemStack, HOTBAR_SIZE};
+pub use self::mob::{Mob, Navigation};
+pub use self::model::EntityModel;
+pub use self::physics::{Airborne, Collidable, Grounded, Landed, RigidBody};
 pub use self::transform::Transform;
 pub use self::world::*;
 
@@ -18,6 +28,11 @@ pub struct ClientControlled;
 #[storage(NullStorage)]
 pub struct Player;
 
+/// Creative players that are flying instead of falling. Spectators always fly.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Component)]
+#[storage(NullStorage)]
+pub struct Flying;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default, Component)]
 #[storage(NullStorage)]
 pub struct DirtyMesh;
@@ -0,0 +1,25 @@
+use engine::model::ModelId;
+use specs::prelude::*;
+
+/// Draws the entity with a box model from `models.json`, at its transform.
+#[derive(Copy, Clone, Debug, PartialEq, Component)]
+#[storage(DenseVecStorage)]
+pub struct EntityModel {
+    pub model: ModelId,
+    /// How far through its walk cycle the entity is, in radians. This goes up
+    /// faster the faster the entity moves.
+    pub walk_phase: f32,
+    /// How much the parts of the model swing, from 0 when standing still to 1
+    /// when walking.
+    pub walk_amount: f32,
+}
+
+impl EntityModel {
+    pub fn new(model: ModelId) -> Self {
+        EntityModel {
+            model,
+            walk_phase: 0.0,
+            walk_amount: 0.0,
+        }
+    }
+}
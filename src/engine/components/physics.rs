@@ -15,3 +15,24 @@ pub struct RigidBody {
 pub struct Collidable {
     pub aabb: Aabb3<f64>,
 }
+
+/// Bodies that are standing on a block, which physics keeps up to date.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Component)]
+#[storage(NullStorage)]
+pub struct Grounded;
+
+/// Bodies that aren't standing on a block, along with the highest they have
+/// been since they were last on the ground. Physics keeps this up to date.
+#[derive(Copy, Clone, Debug, PartialEq, Component)]
+#[storage(DenseVecStorage)]
+pub struct Airborne {
+    pub highest: f64,
+}
+
+/// Sent by physics when a body lands on a block.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Landed {
+    pub entity: Entity,
+    /// How far the body fell, in blocks.
+    pub height: f64,
+}
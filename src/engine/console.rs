@@ -0,0 +1,237 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs, io,
+    path::Path,
+    str::FromStr,
+};
+
+/// How many lines of output the console keeps around.
+const MAX_OUTPUT_LINES: usize = 200;
+
+/// Description of a command that can be typed into the console. Systems that
+/// handle commands register these so that the console can validate input and
+/// list them in `help`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CommandInfo {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub help: &'static str,
+}
+
+pub const HELP: CommandInfo = CommandInfo {
+    name: "help",
+    usage: "help [command]",
+    help: "Lists all commands, or shows how to use a single one",
+};
+pub const ECHO: CommandInfo = CommandInfo {
+    name: "echo",
+    usage: "echo <text...>",
+    help: "Prints some text to the console",
+};
+pub const CLEAR: CommandInfo = CommandInfo {
+    name: "clear",
+    usage: "clear",
+    help: "Clears the console output",
+};
+pub const WAIT: CommandInfo = CommandInfo {
+    name: "wait",
+    usage: "wait <frames>",
+    help: "Delays the commands after this one, mostly useful in scripts",
+};
+
+/// Commands that are handled by the console itself instead of being sent out
+/// to other systems.
+pub const BUILTIN_COMMANDS: &[CommandInfo] = &[HELP, ECHO, CLEAR, WAIT];
+
+#[derive(Clone, Debug, Default)]
+pub struct CommandRegistry {
+    commands: BTreeMap<&'static str, CommandInfo>,
+}
+
+impl CommandRegistry {
+    pub fn register(&mut self, info: CommandInfo) {
+        if self.commands.insert(info.name, info).is_some() {
+            warn!("Command `{}` was registered more than once", info.name);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CommandInfo> {
+        self.commands.get(name)
+    }
+
+    /// Iterates over all registered commands, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = &CommandInfo> {
+        self.commands.values()
+    }
+}
+
+/// A parsed console command. These are sent through an
+/// `EventChannel<ConsoleCommand>`, and systems pick out the ones they
+/// registered by looking at `name`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ConsoleCommand {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl ConsoleCommand {
+    /// Splits a line into a command name and its arguments. Returns `None` for
+    /// blank lines and comments.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return None;
+        }
+
+        let mut words = line.split_whitespace().map(String::from);
+        words.next().map(|name| ConsoleCommand {
+            name,
+            args: words.collect(),
+        })
+    }
+
+    /// Parses argument `idx`, returning `None` if it is missing or malformed.
+    pub fn arg<T: FromStr>(&self, idx: usize) -> Option<T> {
+        self.args.get(idx).and_then(|arg| arg.parse().ok())
+    }
+}
+
+/// Parses a coordinate that may be relative to `origin`, written as `~` or
+/// `~<offset>`.
+pub fn parse_coordinate(arg: &str, origin: f64) -> Option<f64> {
+    if arg.starts_with('~') {
+        match &arg[1..] {
+            "" => Some(origin),
+            offset => offset.parse::<f64>().ok().map(|offset| origin + offset),
+        }
+    } else {
+        arg.parse().ok()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    output: VecDeque<String>,
+
+    history: Vec<String>,
+    /// Index into `history` while scrolling through it with the arrow keys.
+    history_cursor: Option<usize>,
+
+    /// Lines waiting to be executed, either typed in or loaded from a script.
+    queue: VecDeque<String>,
+    wait_frames: usize,
+}
+
+impl Console {
+    pub fn print<S: Into<String>>(&mut self, line: S) {
+        let line = line.into();
+        info!("[console] {}", line);
+
+        if self.output.len() >= MAX_OUTPUT_LINES {
+            self.output.pop_front();
+        }
+        self.output.push_back(line);
+    }
+
+    pub fn print_usage(&mut self, info: &CommandInfo) {
+        self.print(format!("Usage: {}", info.usage));
+    }
+
+    pub fn clear_output(&mut self) {
+        self.output.clear();
+    }
+
+    /// The most recent `count` lines of output, oldest first.
+    pub fn recent_output(&self, count: usize) -> impl Iterator<Item = &str> {
+        let skip = self.output.len().saturating_sub(count);
+        self.output.iter().skip(skip).map(|line| &line[..])
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn push_char(&mut self, ch: char) {
+        self.input.push(ch);
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+    }
+
+    /// Queues the current input line for execution and clears it.
+    pub fn submit_input(&mut self) {
+        let line = ::std::mem::replace(&mut self.input, String::new());
+        self.history_cursor = None;
+        if line.trim().is_empty() {
+            return;
+        }
+
+        self.print(format!("> {}", line));
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        self.queue.push_back(line);
+    }
+
+    pub fn history_prev(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let idx = match self.history_cursor {
+            Some(idx) => idx.saturating_sub(1),
+            None => self.history.len() - 1,
+        };
+        self.history_cursor = Some(idx);
+        self.input = self.history[idx].clone();
+    }
+
+    pub fn history_next(&mut self) {
+        match self.history_cursor {
+            Some(idx) if idx + 1 < self.history.len() => {
+                self.history_cursor = Some(idx + 1);
+                self.input = self.history[idx + 1].clone();
+            }
+            Some(_) => {
+                self.history_cursor = None;
+                self.input.clear();
+            }
+            None => {}
+        }
+    }
+
+    /// Queues a line to be run as if it had been typed in.
+    pub fn execute<S: Into<String>>(&mut self, line: S) {
+        self.queue.push_back(line.into());
+    }
+
+    /// Queues every line of the file at `path`. Blank lines and lines starting
+    /// with `#` are skipped.
+    pub fn run_script<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let script = fs::read_to_string(path)?;
+        for line in script.lines() {
+            self.execute(line);
+        }
+        Ok(())
+    }
+
+    /// Delays the rest of the queued lines by `frames` frames.
+    pub fn wait(&mut self, frames: usize) {
+        self.wait_frames = frames;
+    }
+
+    /// Pops the next line that is ready to run, if any. This should be called
+    /// until it returns `None` once per frame, since waiting is counted in
+    /// calls that found the console waiting.
+    pub fn next_line(&mut self) -> Option<String> {
+        if self.wait_frames > 0 {
+            self.wait_frames -= 1;
+            return None;
+        }
+
+        self.queue.pop_front()
+    }
+}
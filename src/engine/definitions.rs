@@ -0,0 +1,269 @@
+use engine::assets::AssetManager;
+use serde::de::DeserializeOwned;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    error::Error,
+    fmt, io,
+};
+
+/// Something that resource packs define in a list in a JSON file, like the
+/// blocks in `blocks.json`.
+pub trait Definition: DeserializeOwned {
+    /// What the definitions are called in error messages, like `blocks`.
+    const KIND: &'static str;
+
+    /// The name that the definition is looked up by, which later packs use to
+    /// replace it.
+    fn name(&self) -> &str;
+}
+
+/// Something wrong with a single definition.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Problem {
+    /// The file the definition was loaded from.
+    pub file: String,
+    /// Where the problem is in the file, as a JSON pointer like
+    /// `/3/textures/same/texture`.
+    pub pointer: String,
+    /// The name of the definition with the problem.
+    pub name: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {} (`{}`): {}",
+            self.file, self.pointer, self.name, self.message
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum DefinitionError {
+    Io(io::Error),
+    /// A file that isn't JSON or doesn't have the right layout, along with the
+    /// error, which has the line and column.
+    Parse(String, serde_json::Error),
+    /// Every problem that was found, along with what kind of definitions they
+    /// were found in.
+    Invalid(&'static str, Vec<Problem>),
+}
+
+impl From<io::Error> for DefinitionError {
+    fn from(err: io::Error) -> Self {
+        DefinitionError::Io(err)
+    }
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DefinitionError::Io(err) => write!(f, "{}", err),
+            DefinitionError::Parse(file, err) => write!(f, "{}: {}", file, err),
+            DefinitionError::Invalid(kind, problems) => {
+                write!(f, "found {} problem(s) with the {}:", problems.len(), kind)?;
+                for problem in problems {
+                    write!(f, "\n    {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for DefinitionError {
+    fn description(&self) -> &str {
+        match self {
+            DefinitionError::Io(_) => "could not read definitions",
+            DefinitionError::Parse(..) => "could not parse definitions",
+            DefinitionError::Invalid(..) => "invalid definitions",
+        }
+    }
+}
+
+/// A definition along with where it came from.
+pub struct SourcedEntry<T> {
+    pub file: String,
+    /// The position of the definition in the file's list.
+    pub index: usize,
+    pub entry: T,
+}
+
+impl<T: Definition> SourcedEntry<T> {
+    /// A problem with the part of the definition at `path`, which is a JSON
+    /// pointer relative to the definition, or empty for all of it.
+    pub fn problem<S: Into<String>>(&self, path: &str, message: S) -> Problem {
+        Problem {
+            file: self.file.clone(),
+            pointer: format!("/{}{}", self.index, path),
+            name: self.entry.name().into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Parses a file with a list of definitions. Definitions with the same name
+/// as an earlier one in the file are left out, and a problem is added for
+/// each of them.
+pub fn parse_file<T: Definition>(
+    file: &str,
+    data: &[u8],
+    problems: &mut Vec<Problem>,
+) -> Result<Vec<SourcedEntry<T>>, DefinitionError> {
+    let parsed: Vec<T> =
+        serde_json::from_slice(data).map_err(|err| DefinitionError::Parse(file.into(), err))?;
+
+    let mut seen = HashMap::new();
+    let mut entries = Vec::new();
+    for (index, entry) in parsed.into_iter().enumerate() {
+        let sourced = SourcedEntry {
+            file: file.into(),
+            index,
+            entry,
+        };
+        match seen.entry(sourced.entry.name().to_owned()) {
+            Entry::Occupied(first) => {
+                let message = format!("`{}` is already defined at /{}", first.key(), first.get());
+                problems.push(sourced.problem("/name", message));
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(index);
+                entries.push(sourced);
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Loads the definitions in `asset` from every resource pack. A definition
+/// with the same name as one from an earlier pack replaces it without
+/// changing the order of the definitions, and new ones go at the end.
+pub fn load_all<T: Definition>(
+    assets: &AssetManager,
+    asset: &str,
+    problems: &mut Vec<Problem>,
+) -> Result<Vec<SourcedEntry<T>>, DefinitionError> {
+    let mut entries: Vec<SourcedEntry<T>> = Vec::new();
+    for (file, data) in assets.read_all(asset)? {
+        for sourced in parse_file(&file, &data, problems)? {
+            let name = sourced.entry.name();
+            match entries.iter().position(|other| other.entry.name() == name) {
+                Some(idx) => entries[idx] = sourced,
+                None => entries.push(sourced),
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, path::PathBuf, process};
+
+    #[derive(Debug, Deserialize)]
+    struct Thing {
+        name: String,
+        value: u32,
+    }
+
+    impl Definition for Thing {
+        const KIND: &'static str = "things";
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    /// Makes a pack directory with `things.json` in it, which is unique to the
+    /// test and the process.
+    fn make_pack(test: &str, pack: &str, things: &str) -> PathBuf {
+        let dir = env::temp_dir()
+            .join(format!("definitions-{}-{}", test, process::id()))
+            .join(pack);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("things.json"), things).unwrap();
+        dir
+    }
+
+    #[test]
+    fn problem_points_into_the_entry() {
+        let sourced = SourcedEntry {
+            file: "things.json".into(),
+            index: 3,
+            entry: Thing {
+                name: "rock".into(),
+                value: 1,
+            },
+        };
+        let problem = sourced.problem("/value", "too small");
+        assert_eq!(problem.pointer, "/3/value");
+        assert_eq!(problem.name, "rock");
+        assert_eq!(
+            problem.to_string(),
+            "things.json at /3/value (`rock`): too small"
+        );
+        assert_eq!(sourced.problem("", "bad").pointer, "/3");
+    }
+
+    #[test]
+    fn parse_file_skips_duplicates() {
+        let data = br#"[
+            {"name": "rock", "value": 1},
+            {"name": "sand", "value": 2},
+            {"name": "rock", "value": 3}
+        ]"#;
+        let mut problems = Vec::new();
+        let entries = parse_file::<Thing>("things.json", data, &mut problems).unwrap();
+
+        let names: Vec<_> = entries.iter().map(|sourced| sourced.entry.name()).collect();
+        assert_eq!(names, ["rock", "sand"]);
+        assert_eq!(entries[0].entry.value, 1);
+        assert_eq!(entries[1].index, 1);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].pointer, "/2/name");
+        assert_eq!(problems[0].message, "`rock` is already defined at /0");
+    }
+
+    #[test]
+    fn parse_file_reports_bad_json() {
+        let mut problems = Vec::new();
+        match parse_file::<Thing>("things.json", br#"[{"name": "rock"}]"#, &mut problems) {
+            Err(DefinitionError::Parse(file, _)) => assert_eq!(file, "things.json"),
+            other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+        }
+        assert!(problems.is_empty());
+    }
+
+    #[test]
+    fn load_all_replaces_by_name() {
+        let base = make_pack(
+            "replace",
+            "base",
+            r#"[{"name": "rock", "value": 1}, {"name": "sand", "value": 2}]"#,
+        );
+        let extra = make_pack(
+            "replace",
+            "extra",
+            r#"[{"name": "dirt", "value": 3}, {"name": "rock", "value": 4}]"#,
+        );
+        let assets = AssetManager::new(&[&base, &extra]).unwrap();
+
+        let mut problems = Vec::new();
+        let entries = load_all::<Thing>(&assets, "things.json", &mut problems).unwrap();
+        let found: Vec<_> = entries
+            .iter()
+            .map(|sourced| (sourced.entry.name(), sourced.entry.value, sourced.index))
+            .collect();
+        assert_eq!(found, [("rock", 4, 1), ("sand", 2, 1), ("dirt", 3, 0)]);
+        assert!(entries[0].file.starts_with(&extra.display().to_string()));
+        assert!(problems.is_empty());
+
+        fs::remove_dir_all(base.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,389 @@
+use engine::{
+    assets::AssetManager,
+    definitions::{self, Definition, DefinitionError, Problem, SourcedEntry},
+    world::block::{BlockId, BlockRegistry},
+};
+use std::collections::HashMap;
+
+/// How many items fit in a stack unless the item says otherwise.
+pub const DEFAULT_MAX_STACK: u32 = 64;
+
+/// Identifies a kind of item. Items declared in `items.json` come first,
+/// followed by the items for placeable blocks. Items added by reloading go
+/// after all of them, so that the items that already exist keep their IDs.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct ItemId(usize);
+
+/// What a tool is made for. Each kind goes with a `mineable/<kind>` block tag.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolKind {
+    Pickaxe,
+    Shovel,
+    Axe,
+}
+
+impl ToolKind {
+    /// The block tag for blocks that this kind of tool is made for breaking.
+    pub fn mineable_tag(self) -> &'static str {
+        match self {
+            ToolKind::Pickaxe => "mineable/pickaxe",
+            ToolKind::Shovel => "mineable/shovel",
+            ToolKind::Axe => "mineable/axe",
+        }
+    }
+}
+
+/// An item as it is written in `items.json`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ItemEntry {
+    name: String,
+    /// An image in the `textures` directory, with the same size as a block
+    /// texture.
+    texture: String,
+    /// Defaults to `DEFAULT_MAX_STACK`, or 1 for items with durability.
+    max_stack: Option<u32>,
+    tool: Option<ToolKind>,
+    /// How many times faster than by hand a tool breaks the blocks it is made
+    /// for. Defaults to 1.
+    speed: Option<f32>,
+    /// How many times the item can be used before it breaks.
+    durability: Option<u32>,
+}
+
+impl Definition for ItemEntry {
+    const KIND: &'static str = "items";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// An item definition along with where it came from.
+type SourcedItem = SourcedEntry<ItemEntry>;
+
+fn check_entry(sourced: &SourcedItem, blocks: &BlockRegistry, problems: &mut Vec<Problem>) {
+    let entry = &sourced.entry;
+
+    if blocks.get_id(&entry.name).is_some() {
+        problems.push(sourced.problem(
+            "/name",
+            format!("`{}` is already the name of a block", entry.name),
+        ));
+    }
+    if entry.max_stack == Some(0) {
+        problems.push(sourced.problem("/max_stack", "the stack size has to be at least 1"));
+    }
+    match (entry.durability, entry.max_stack) {
+        (Some(0), _) => {
+            problems.push(sourced.problem("/durability", "the durability has to be at least 1"))
+        }
+        (Some(_), Some(max_stack)) if max_stack > 1 => {
+            problems.push(sourced.problem("/max_stack", "items with durability can't be stacked"))
+        }
+        _ => {}
+    }
+    if entry.tool.is_some() && entry.durability.is_none() {
+        problems.push(sourced.problem("/durability", "tools need a durability"));
+    }
+    match (entry.speed, entry.tool) {
+        (Some(speed), _) if speed <= 0.0 => {
+            problems.push(sourced.problem("/speed", "the speed has to be more than 0"))
+        }
+        (Some(_), None) => problems.push(sourced.problem("/speed", "only tools have a speed")),
+        _ => {}
+    }
+}
+
+fn check_texture_files(
+    assets: &AssetManager,
+    entries: &[SourcedItem],
+    texture_size: u32,
+    problems: &mut Vec<Problem>,
+) {
+    for sourced in entries {
+        let file = &sourced.entry.texture;
+        let problem = match assets.load_image(&format!("textures/{}", file)) {
+            Err(err) => format!("could not load texture `{}`: {}", file, err),
+            Ok(ref image) if image.dimensions() != (texture_size, texture_size) => format!(
+                "texture `{}` is {}x{}, but has to be {}x{}",
+                file,
+                image.width(),
+                image.height(),
+                texture_size,
+                texture_size
+            ),
+            Ok(_) => continue,
+        };
+        problems.push(sourced.problem("/texture", problem));
+    }
+}
+
+/// What an item looks like in the UI.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ItemIcon {
+    /// The item is drawn like the block it places.
+    Block(BlockId),
+    /// An index into `ItemRegistry::textures`.
+    Texture(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ItemInfo {
+    name: String,
+    max_stack: u32,
+    block: Option<BlockId>,
+    tool: Option<ToolKind>,
+    speed: f32,
+    durability: Option<u32>,
+    icon: ItemIcon,
+}
+
+/// Every kind of item. Items are either declared in `items.json`, like tools
+/// and materials, or made for each block that can be placed, with the same
+/// name as the block.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct ItemRegistry {
+    items: Vec<ItemInfo>,
+    name_map: HashMap<String, ItemId>,
+    block_items: HashMap<BlockId, ItemId>,
+    textures: Vec<String>,
+}
+
+impl ItemRegistry {
+    /// Loads `items.json` from every resource pack and adds the items for the
+    /// blocks in `blocks`. Like blocks, items from later packs can replace
+    /// earlier ones with the same name.
+    pub fn load_from_assets(
+        assets: &AssetManager,
+        blocks: &BlockRegistry,
+    ) -> Result<Self, DefinitionError> {
+        let (entries, problems) = ItemRegistry::load_entries(assets, blocks)?;
+        ItemRegistry::from_entries(entries, problems, blocks)
+    }
+
+    /// Like `load_from_assets`, but also checks that every texture exists and
+    /// has the right size.
+    pub fn load_validated(
+        assets: &AssetManager,
+        blocks: &BlockRegistry,
+        texture_size: u32,
+    ) -> Result<Self, DefinitionError> {
+        let (entries, mut problems) = ItemRegistry::load_entries(assets, blocks)?;
+        check_texture_files(assets, &entries, texture_size, &mut problems);
+        ItemRegistry::from_entries(entries, problems, blocks)
+    }
+
+    fn load_entries(
+        assets: &AssetManager,
+        blocks: &BlockRegistry,
+    ) -> Result<(Vec<SourcedItem>, Vec<Problem>), DefinitionError> {
+        let mut problems = Vec::new();
+        let entries = definitions::load_all(assets, "items.json", &mut problems)?;
+        for sourced in &entries {
+            check_entry(sourced, blocks, &mut problems);
+        }
+
+        Ok((entries, problems))
+    }
+
+    fn from_entries(
+        entries: Vec<SourcedItem>,
+        problems: Vec<Problem>,
+        blocks: &BlockRegistry,
+    ) -> Result<Self, DefinitionError> {
+        if !problems.is_empty() {
+            return Err(DefinitionError::Invalid(ItemEntry::KIND, problems));
+        }
+
+        let mut registry = ItemRegistry::default();
+        for SourcedEntry { entry, .. } in entries {
+            let texture = registry.textures.len();
+            registry.textures.push(entry.texture);
+            let max_stack = entry.max_stack.unwrap_or(match entry.durability {
+                Some(_) => 1,
+                None => DEFAULT_MAX_STACK,
+            });
+
+            registry.add(ItemInfo {
+                name: entry.name,
+                max_stack,
+                block: None,
+                tool: entry.tool,
+                speed: entry.speed.unwrap_or(1.0),
+                durability: entry.durability,
+                icon: ItemIcon::Texture(texture),
+            });
+        }
+
+        // air and liquids can't be held
+        for block in blocks.blocks().filter(|&block| blocks.targetable(block)) {
+            registry.add(ItemInfo {
+                name: blocks.name(block).into(),
+                max_stack: DEFAULT_MAX_STACK,
+                block: Some(block),
+                tool: None,
+                speed: 1.0,
+                durability: None,
+                icon: ItemIcon::Block(block),
+            });
+        }
+
+        Ok(registry)
+    }
+
+    fn add(&mut self, info: ItemInfo) -> ItemId {
+        let id = ItemId(self.items.len());
+        self.name_map.insert(info.name.clone(), id);
+        if let Some(block) = info.block {
+            self.block_items.insert(block, id);
+        }
+        self.items.push(info);
+        id
+    }
+
+    /// Reorders the items so that every item in `old` keeps its ID, and new
+    /// items come after them. Inventories and dropped items store item IDs,
+    /// so this is needed to replace the registry while the game is running.
+    /// It fails if an item in `old` no longer exists.
+    pub fn keeping_ids_of(self, old: &ItemRegistry) -> Result<Self, String> {
+        let mut items: Vec<_> = self.items.into_iter().map(Some).collect();
+        let mut registry = ItemRegistry {
+            textures: self.textures,
+            ..ItemRegistry::default()
+        };
+
+        for info in &old.items {
+            let id = match self.name_map.get(&info.name) {
+                Some(id) => id,
+                None => return Err(format!("`{}` was removed", info.name)),
+            };
+            registry.add(items[id.0].take().unwrap());
+        }
+        for info in items.into_iter().flatten() {
+            registry.add(info);
+        }
+
+        Ok(registry)
+    }
+
+    /// The number of items, which is one more than the largest `ItemId`.
+    pub fn item_count(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn get_id(&self, name: &str) -> Option<ItemId> {
+        self.name_map.get(name).cloned()
+    }
+
+    pub fn name(&self, id: ItemId) -> &str {
+        &self.items[id.0].name
+    }
+
+    /// The most items of this kind that fit in one inventory slot.
+    pub fn max_stack(&self, id: ItemId) -> u32 {
+        self.items[id.0].max_stack
+    }
+
+    /// The block that is placed when using the item, which is always a
+    /// default state.
+    pub fn block(&self, id: ItemId) -> Option<BlockId> {
+        self.items[id.0].block
+    }
+
+    /// The item for a block, which has to be the block's default state.
+    pub fn block_item(&self, block: BlockId) -> Option<ItemId> {
+        self.block_items.get(&block).cloned()
+    }
+
+    pub fn tool(&self, id: ItemId) -> Option<ToolKind> {
+        self.items[id.0].tool
+    }
+
+    /// How many times faster than by hand the item breaks `block`, which is
+    /// only faster if the item is a tool that is made for the block.
+    pub fn breaking_speed(&self, id: ItemId, blocks: &BlockRegistry, block: BlockId) -> f32 {
+        let item = &self.items[id.0];
+        match item.tool {
+            Some(tool) if blocks.has_tag(block, tool.mineable_tag()) => item.speed,
+            _ => 1.0,
+        }
+    }
+
+    pub fn durability(&self, id: ItemId) -> Option<u32> {
+        self.items[id.0].durability
+    }
+
+    pub fn icon(&self, id: ItemId) -> ItemIcon {
+        self.items[id.0].icon
+    }
+
+    /// The images in the `textures` directory that item icons refer to.
+    pub fn textures(&self) -> &[String] {
+        &self.textures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(names: &[&str]) -> ItemRegistry {
+        let mut registry = ItemRegistry::default();
+        for name in names {
+            let texture = registry.textures.len();
+            registry.textures.push(format!("{}.png", name));
+            registry.add(ItemInfo {
+                name: name.to_string(),
+                max_stack: DEFAULT_MAX_STACK,
+                block: None,
+                tool: None,
+                speed: 1.0,
+                durability: None,
+                icon: ItemIcon::Texture(texture),
+            });
+        }
+        registry
+    }
+
+    fn names(registry: &ItemRegistry) -> Vec<&str> {
+        (0..registry.item_count())
+            .map(|idx| registry.name(ItemId(idx)))
+            .collect()
+    }
+
+    #[test]
+    fn keeping_ids_of_keeps_old_items_first() {
+        let old = registry(&["stick", "coal", "pickaxe"]);
+        let new = registry(&["apple", "pickaxe", "stick", "coal"])
+            .keeping_ids_of(&old)
+            .unwrap();
+
+        assert_eq!(names(&new), ["stick", "coal", "pickaxe", "apple"]);
+        for name in &["stick", "coal", "pickaxe"] {
+            assert_eq!(new.get_id(name), old.get_id(name));
+        }
+        assert_eq!(new.get_id("apple"), Some(ItemId(3)));
+    }
+
+    #[test]
+    fn keeping_ids_of_keeps_icons() {
+        let old = registry(&["stick", "coal"]);
+        let new = registry(&["coal", "stick"]).keeping_ids_of(&old).unwrap();
+
+        // the textures aren't reordered, so the icons have to follow the items
+        let stick = new.get_id("stick").unwrap();
+        match new.icon(stick) {
+            ItemIcon::Texture(idx) => assert_eq!(new.textures()[idx], "stick.png"),
+            icon => panic!("expected a texture icon, got {:?}", icon),
+        }
+    }
+
+    #[test]
+    fn keeping_ids_of_fails_on_removed_items() {
+        let old = registry(&["stick", "coal"]);
+        let result = registry(&["stick"]).keeping_ids_of(&old);
+        assert_eq!(result, Err("`coal` was removed".to_string()));
+    }
+}
@@ -106,4 +106,10 @@ impl<W: Worker> Service<W> {
     pub fn gather(&mut self) -> impl Iterator<Item = (W::Input, W::Output)> + '_ {
         self.receiver.try_iter()
     }
+
+    /// Blocks until the next request is finished. Returns `None` if all the
+    /// workers have shut down.
+    pub fn wait(&mut self) -> Option<(W::Input, W::Output)> {
+        self.receiver.recv().ok()
+    }
 }
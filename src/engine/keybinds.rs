@@ -0,0 +1,534 @@
+use glutin::{ElementState, KeyboardInput, ModifiersState, VirtualKeyCode};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs::File,
+    path::Path,
+};
+
+/// Something the player can do that is bound to a key or mouse button.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Crouch,
+    ToggleFlight,
+    Zoom,
+    BreakBlock,
+    PlaceBlock,
+    HotbarNext,
+    HotbarPrevious,
+    Hotbar1,
+    Hotbar2,
+    Hotbar3,
+    Hotbar4,
+    Hotbar5,
+    Hotbar6,
+    Hotbar7,
+    Hotbar8,
+    Hotbar9,
+
+    Exit,
+    ToggleConsole,
+    ToggleOverlay,
+    ToggleWireframe,
+    IncreaseViewDistance,
+    DecreaseViewDistance,
+    PrintPosition,
+
+    DebugChunkGrid,
+    DebugTerrainGeneration,
+    DebugMesher,
+    DebugPhysics,
+    DebugInteraction,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Input {
+    /// A key identified by its position on the keyboard, which doesn't change
+    /// with the keyboard layout.
+    Scancode(u32),
+    /// A key identified by the symbol it produces.
+    Key(VirtualKeyCode),
+    /// A mouse button, as reported by `DeviceEvent::Button`.
+    Mouse(u32),
+    /// A step of the mouse wheel, which is pressed and released straight away.
+    Scroll(ScrollDirection),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollDirection {
+    Up,
+    Down,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Binding {
+    pub input: Input,
+    /// The exact modifiers that have to be held, or `None` if it doesn't
+    /// matter which are held.
+    pub modifiers: Option<ModifiersState>,
+}
+
+const NO_MODIFIERS: ModifiersState = ModifiersState {
+    shift: false,
+    ctrl: false,
+    alt: false,
+    logo: false,
+};
+
+const CTRL_MODIFIERS: ModifiersState = ModifiersState {
+    shift: false,
+    ctrl: true,
+    alt: false,
+    logo: false,
+};
+
+const fn bind(input: Input, modifiers: Option<ModifiersState>) -> Binding {
+    Binding { input, modifiers }
+}
+
+const DEFAULT_BINDINGS: &[(Action, Binding)] = &[
+    (Action::MoveForward, bind(Input::Scancode(0x11), None)),
+    (Action::MoveBackward, bind(Input::Scancode(0x1F), None)),
+    (Action::MoveLeft, bind(Input::Scancode(0x1E), None)),
+    (Action::MoveRight, bind(Input::Scancode(0x20), None)),
+    (Action::Jump, bind(Input::Scancode(0x39), None)),
+    (Action::Crouch, bind(Input::Scancode(0x2A), None)),
+    (
+        Action::ToggleFlight,
+        bind(Input::Scancode(0x21), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::Zoom,
+        bind(Input::Scancode(0x2E), Some(NO_MODIFIERS)),
+    ),
+    (Action::BreakBlock, bind(Input::Mouse(1), None)),
+    (Action::PlaceBlock, bind(Input::Mouse(3), None)),
+    (
+        Action::HotbarNext,
+        bind(Input::Scroll(ScrollDirection::Down), None),
+    ),
+    (
+        Action::HotbarPrevious,
+        bind(Input::Scroll(ScrollDirection::Up), None),
+    ),
+    (
+        Action::Hotbar1,
+        bind(Input::Key(VirtualKeyCode::Key1), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::Hotbar2,
+        bind(Input::Key(VirtualKeyCode::Key2), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::Hotbar3,
+        bind(Input::Key(VirtualKeyCode::Key3), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::Hotbar4,
+        bind(Input::Key(VirtualKeyCode::Key4), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::Hotbar5,
+        bind(Input::Key(VirtualKeyCode::Key5), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::Hotbar6,
+        bind(Input::Key(VirtualKeyCode::Key6), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::Hotbar7,
+        bind(Input::Key(VirtualKeyCode::Key7), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::Hotbar8,
+        bind(Input::Key(VirtualKeyCode::Key8), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::Hotbar9,
+        bind(Input::Key(VirtualKeyCode::Key9), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::Exit,
+        bind(Input::Key(VirtualKeyCode::Escape), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::ToggleConsole,
+        bind(Input::Key(VirtualKeyCode::Grave), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::ToggleOverlay,
+        bind(Input::Key(VirtualKeyCode::F3), Some(NO_MODIFIERS)),
+    ),
+    (
+        Action::ToggleWireframe,
+        bind(Input::Key(VirtualKeyCode::F), Some(CTRL_MODIFIERS)),
+    ),
+    (
+        Action::IncreaseViewDistance,
+        bind(Input::Key(VirtualKeyCode::RBracket), Some(CTRL_MODIFIERS)),
+    ),
+    (
+        Action::DecreaseViewDistance,
+        bind(Input::Key(VirtualKeyCode::LBracket), Some(CTRL_MODIFIERS)),
+    ),
+    (
+        Action::PrintPosition,
+        bind(Input::Key(VirtualKeyCode::B), Some(CTRL_MODIFIERS)),
+    ),
+    (
+        Action::DebugChunkGrid,
+        bind(Input::Key(VirtualKeyCode::C), Some(CTRL_MODIFIERS)),
+    ),
+    (
+        Action::DebugTerrainGeneration,
+        bind(Input::Key(VirtualKeyCode::T), Some(CTRL_MODIFIERS)),
+    ),
+    (
+        Action::DebugMesher,
+        bind(Input::Key(VirtualKeyCode::M), Some(CTRL_MODIFIERS)),
+    ),
+    (
+        Action::DebugPhysics,
+        bind(Input::Key(VirtualKeyCode::P), Some(CTRL_MODIFIERS)),
+    ),
+    (
+        Action::DebugInteraction,
+        bind(Input::Key(VirtualKeyCode::I), Some(CTRL_MODIFIERS)),
+    ),
+];
+
+macro_rules! key_names {
+    ($($key:ident),* $(,)*) => {
+        fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+            match name {
+                $(stringify!($key) => Some(VirtualKeyCode::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+key_names! {
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Insert, Home, Delete, End, PageDown, PageUp,
+    Left, Up, Right, Down,
+    Back, Return, Space, Tab,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4,
+    Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    Apostrophe, Backslash, Comma, Equals, Grave, LBracket, Minus, Period, RBracket,
+    Semicolon, Slash,
+    LAlt, LControl, LShift, RAlt, RControl, RShift,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Modifier {
+    Shift,
+    Ctrl,
+    Alt,
+    Logo,
+}
+
+/// A single binding as it is written in the config file. Exactly one of
+/// `key`, `scancode`, `mouse` or `scroll` has to be present.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindingConfig {
+    key: Option<String>,
+    scancode: Option<u32>,
+    mouse: Option<u32>,
+    scroll: Option<ScrollDirection>,
+    modifiers: Option<Vec<Modifier>>,
+}
+
+impl BindingConfig {
+    fn to_binding(&self) -> Result<Binding, String> {
+        let input = match (&self.key, self.scancode, self.mouse, self.scroll) {
+            (Some(name), None, None, None) => {
+                Input::Key(key_from_name(name).ok_or_else(|| format!("unknown key `{}`", name))?)
+            }
+            (None, Some(scancode), None, None) => Input::Scancode(scancode),
+            (None, None, Some(button), None) => Input::Mouse(button),
+            (None, None, None, Some(direction)) => Input::Scroll(direction),
+            _ => {
+                return Err("expected exactly one of `key`, `scancode`, `mouse` or `scroll`".into())
+            }
+        };
+
+        let modifiers = self.modifiers.as_ref().map(|modifiers| {
+            let mut state = NO_MODIFIERS;
+            for modifier in modifiers {
+                match modifier {
+                    Modifier::Shift => state.shift = true,
+                    Modifier::Ctrl => state.ctrl = true,
+                    Modifier::Alt => state.alt = true,
+                    Modifier::Logo => state.logo = true,
+                }
+            }
+            state
+        });
+
+        Ok(Binding { input, modifiers })
+    }
+}
+
+/// Maps inputs to the actions they trigger.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keybinds {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        let mut bindings = HashMap::<_, Vec<_>>::new();
+        for &(action, binding) in DEFAULT_BINDINGS {
+            bindings.entry(action).or_default().push(binding);
+        }
+        Keybinds { bindings }
+    }
+}
+
+impl Keybinds {
+    /// Loads bindings from a JSON file that maps action names to lists of
+    /// bindings. Actions that aren't mentioned keep their default bindings,
+    /// and entries that can't be understood are skipped with a warning.
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<Error>> {
+        let config = serde_json::from_reader(File::open(path)?)?;
+        Ok(Keybinds::from_config(config))
+    }
+
+    /// Like `load_from_file`, but with the contents of the file.
+    pub fn load_from_str(text: &str) -> Result<Self, Box<Error>> {
+        Ok(Keybinds::from_config(serde_json::from_str(text)?))
+    }
+
+    fn from_config(config: HashMap<String, Vec<BindingConfig>>) -> Self {
+        let mut keybinds = Keybinds::default();
+
+        for (name, configs) in config {
+            let action: Action =
+                match serde_json::from_value(serde_json::Value::String(name.clone())) {
+                    Ok(action) => action,
+                    Err(_) => {
+                        warn!("Ignoring bindings for unknown action `{}`", name);
+                        continue;
+                    }
+                };
+
+            let mut bindings = Vec::with_capacity(configs.len());
+            for config in configs {
+                match config.to_binding() {
+                    Ok(binding) => bindings.push(binding),
+                    Err(err) => warn!("Ignoring binding for `{}`: {}", name, err),
+                }
+            }
+            keybinds.bindings.insert(action, bindings);
+        }
+
+        keybinds
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings
+            .get(&action)
+            .map_or(&[], |bindings| &bindings[..])
+    }
+
+    /// Whether `input` with `modifiers` held triggers `action`.
+    pub fn matches(&self, action: Action, input: Input, modifiers: ModifiersState) -> bool {
+        self.bindings(action).iter().any(|binding| {
+            binding.input == input && binding.modifiers.map_or(true, |held| held == modifiers)
+        })
+    }
+
+    /// Whether a key press triggers `action`.
+    pub fn matches_key(&self, action: Action, input: &KeyboardInput) -> bool {
+        let key = input.virtual_keycode.map(Input::Key);
+        self.matches(action, Input::Scancode(input.scancode), input.modifiers)
+            || key.map_or(false, |key| self.matches(action, key, input.modifiers))
+    }
+
+    fn actions_for<'a>(
+        &'a self,
+        input: Input,
+        modifiers: Option<ModifiersState>,
+    ) -> impl Iterator<Item = Action> + 'a {
+        self.bindings.iter().filter_map(move |(&action, bindings)| {
+            let bound = bindings.iter().any(|binding| {
+                binding.input == input
+                    && match (binding.modifiers, modifiers) {
+                        (Some(required), Some(held)) => required == held,
+                        _ => true,
+                    }
+            });
+            if bound {
+                Some(action)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Which actions are held down, and which started or stopped this frame.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+}
+
+impl ActionState {
+    /// Whether the action is currently held.
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// Whether the action started this frame.
+    pub fn was_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Whether the action stopped this frame.
+    pub fn was_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    /// Forgets which actions started or stopped, which should happen once at
+    /// the start of every frame.
+    pub fn begin_frame(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
+
+    /// Releases everything that is held.
+    pub fn release_all(&mut self) {
+        self.just_released.extend(self.pressed.drain());
+    }
+
+    /// Updates the state for an input being pressed or released. Releasing
+    /// ignores modifiers, so letting go of a modifier before the key still
+    /// ends the action.
+    pub fn handle_input(
+        &mut self,
+        keybinds: &Keybinds,
+        input: Input,
+        modifiers: ModifiersState,
+        state: ElementState,
+    ) {
+        match state {
+            ElementState::Pressed => {
+                for action in keybinds.actions_for(input, Some(modifiers)) {
+                    // key repeat sends more presses while the key is held
+                    if self.pressed.insert(action) {
+                        self.just_pressed.insert(action);
+                    }
+                }
+            }
+            ElementState::Released => {
+                for action in keybinds.actions_for(input, None) {
+                    if self.pressed.remove(&action) {
+                        self.just_released.insert(action);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHIFT_MODIFIERS: ModifiersState = ModifiersState {
+        shift: true,
+        ctrl: false,
+        alt: false,
+        logo: false,
+    };
+
+    #[test]
+    fn parses_each_kind_of_input() {
+        let keybinds = Keybinds::load_from_str(
+            r#"{
+                "jump": [{"key": "Space"}, {"scancode": 57}],
+                "break_block": [{"mouse": 2}],
+                "hotbar_next": [{"scroll": "up"}],
+                "zoom": [{"key": "Z", "modifiers": ["shift"]}]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            keybinds.bindings(Action::Jump),
+            [
+                bind(Input::Key(VirtualKeyCode::Space), None),
+                bind(Input::Scancode(57), None),
+            ]
+        );
+        assert_eq!(
+            keybinds.bindings(Action::BreakBlock),
+            [bind(Input::Mouse(2), None)]
+        );
+        assert_eq!(
+            keybinds.bindings(Action::HotbarNext),
+            [bind(Input::Scroll(ScrollDirection::Up), None)]
+        );
+        assert_eq!(
+            keybinds.bindings(Action::Zoom),
+            [bind(Input::Key(VirtualKeyCode::Z), Some(SHIFT_MODIFIERS))]
+        );
+    }
+
+    #[test]
+    fn unmentioned_actions_keep_their_defaults() {
+        let keybinds = Keybinds::load_from_str(r#"{"jump": [{"key": "J"}]}"#).unwrap();
+        let defaults = Keybinds::default();
+        assert_eq!(
+            keybinds.bindings(Action::Exit),
+            defaults.bindings(Action::Exit)
+        );
+        assert!(!keybinds.matches(Action::Jump, Input::Scancode(0x39), NO_MODIFIERS));
+    }
+
+    #[test]
+    fn skips_bad_bindings_and_actions() {
+        let keybinds = Keybinds::load_from_str(
+            r#"{
+                "jump": [{"key": "Nope"}, {"key": "J", "mouse": 1}, {"mouse": 4}],
+                "dance": [{"key": "D"}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            keybinds.bindings(Action::Jump),
+            [bind(Input::Mouse(4), None)]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(Keybinds::load_from_str(r#"{"jump": [{"button": 1}]}"#).is_err());
+    }
+
+    #[test]
+    fn modifiers_have_to_match_exactly() {
+        let keybinds = Keybinds::default();
+        let f = Input::Key(VirtualKeyCode::F);
+        assert!(keybinds.matches(Action::ToggleWireframe, f, CTRL_MODIFIERS));
+        assert!(!keybinds.matches(Action::ToggleWireframe, f, NO_MODIFIERS));
+
+        // bindings without modifiers work with any held
+        let space = Input::Scancode(0x39);
+        assert!(keybinds.matches(Action::Jump, space, SHIFT_MODIFIERS));
+    }
+}
@@ -0,0 +1,218 @@
+use engine::{
+    assets::AssetManager,
+    definitions::{self, Definition, DefinitionError, Problem, SourcedEntry},
+    model::{ModelId, ModelRegistry},
+    world::block::BlockRegistry,
+};
+use std::collections::HashMap;
+
+/// Identifies a kind of mob, in the order they are declared in `mobs.json`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct MobId(usize);
+
+/// Something a mob can spend its time doing. Each mob lists the behaviours it
+/// has from most to least important, and does the first one that applies.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Behaviour {
+    /// Runs away from players that come within `range` blocks.
+    Flee { range: f64 },
+    /// Walks towards players within `range` blocks, until it is `distance`
+    /// blocks away from them.
+    FollowPlayer { range: f64, distance: f64 },
+    /// Now and then walks off in a random direction for a few seconds.
+    /// `chance` is how likely it is to start walking each second.
+    Wander { chance: f64 },
+    /// Stands still and turns to look somewhere else every few seconds.
+    LookAround,
+}
+
+/// Where and how often a mob appears around players.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnRule {
+    /// Names of the blocks the mob can stand on when it spawns.
+    pub blocks: Vec<String>,
+    /// The range of light levels, from 0 to 15, that the mob spawns in.
+    pub min_light: u8,
+    pub max_light: u8,
+    /// How far from the closest player the mob spawns, in blocks.
+    pub min_distance: f64,
+    pub max_distance: f64,
+    /// The most of this mob that can be around at once.
+    pub max_count: usize,
+}
+
+/// A mob as it is written in `mobs.json`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MobEntry {
+    name: String,
+    /// In half hearts.
+    health: u32,
+    /// The size of the mob's collision box, in blocks.
+    width: f64,
+    height: f64,
+    /// How fast the mob walks, in blocks per second.
+    speed: f64,
+    behaviours: Vec<Behaviour>,
+    /// Mobs without spawn rules never appear on their own.
+    spawn: Option<SpawnRule>,
+    /// The name of the mob's model in `models.json`. Defaults to the name of
+    /// the mob, and mobs without a model aren't drawn.
+    model: Option<String>,
+}
+
+impl Definition for MobEntry {
+    const KIND: &'static str = "mobs";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn check_entry(
+    sourced: &SourcedEntry<MobEntry>,
+    blocks: &BlockRegistry,
+    models: &ModelRegistry,
+    problems: &mut Vec<Problem>,
+) {
+    let entry = &sourced.entry;
+    let mut problem = |path: &str, message: String| problems.push(sourced.problem(path, message));
+
+    if entry.health == 0 {
+        problem("/health", "the health has to be at least 1".into());
+    }
+    if entry.width <= 0.0 {
+        problem("/width", "the width has to be more than 0".into());
+    }
+    if entry.height <= 0.0 {
+        problem("/height", "the height has to be more than 0".into());
+    }
+    if entry.speed < 0.0 {
+        problem("/speed", "the speed can't be negative".into());
+    }
+    if let Some(model) = &entry.model {
+        if models.get_id(model).is_none() {
+            problem("/model", format!("there is no model called `{}`", model));
+        }
+    }
+
+    for (idx, behaviour) in entry.behaviours.iter().enumerate() {
+        let bad_range = match *behaviour {
+            Behaviour::Flee { range } => range <= 0.0,
+            Behaviour::FollowPlayer { range, distance } => range <= 0.0 || distance > range,
+            Behaviour::Wander { chance } => chance < 0.0,
+            Behaviour::LookAround => false,
+        };
+        if bad_range {
+            problem(
+                &format!("/behaviours/{}", idx),
+                "the ranges have to be more than 0, and the chance can't be negative".into(),
+            );
+        }
+    }
+
+    let rule = match &entry.spawn {
+        Some(rule) => rule,
+        None => return,
+    };
+    for (idx, name) in rule.blocks.iter().enumerate() {
+        if blocks.get_id(name).is_none() {
+            problem(
+                &format!("/spawn/blocks/{}", idx),
+                format!("there is no block called `{}`", name),
+            );
+        }
+    }
+    if rule.min_light > rule.max_light || rule.max_light > 15 {
+        problem(
+            "/spawn/max_light",
+            "light levels go from 0 to 15, and the minimum can't be more than the maximum".into(),
+        );
+    }
+    if rule.min_distance < 0.0 || rule.min_distance > rule.max_distance {
+        problem(
+            "/spawn/max_distance",
+            "the distances can't be negative, and the minimum can't be more than the maximum"
+                .into(),
+        );
+    }
+}
+
+/// Everything about one kind of mob.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MobInfo {
+    pub name: String,
+    pub health: u32,
+    pub width: f64,
+    pub height: f64,
+    pub speed: f64,
+    pub behaviours: Vec<Behaviour>,
+    pub spawn: Option<SpawnRule>,
+    pub model: Option<ModelId>,
+}
+
+/// Every kind of mob, declared in `mobs.json`.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct MobRegistry {
+    mobs: Vec<MobInfo>,
+    name_map: HashMap<String, MobId>,
+}
+
+impl MobRegistry {
+    /// Loads `mobs.json` from every resource pack. Like blocks and items,
+    /// mobs from later packs can replace earlier ones with the same name.
+    pub fn load_from_assets(
+        assets: &AssetManager,
+        blocks: &BlockRegistry,
+        models: &ModelRegistry,
+    ) -> Result<Self, DefinitionError> {
+        let mut problems = Vec::new();
+        let entries: Vec<SourcedEntry<MobEntry>> =
+            definitions::load_all(assets, "mobs.json", &mut problems)?;
+        for sourced in &entries {
+            check_entry(sourced, blocks, models, &mut problems);
+        }
+
+        if !problems.is_empty() {
+            return Err(DefinitionError::Invalid(MobEntry::KIND, problems));
+        }
+
+        let mut registry = MobRegistry::default();
+        for SourcedEntry { entry, .. } in entries {
+            let id = MobId(registry.mobs.len());
+            let model = models.get_id(entry.model.as_ref().unwrap_or(&entry.name));
+            registry.name_map.insert(entry.name.clone(), id);
+            registry.mobs.push(MobInfo {
+                name: entry.name,
+                health: entry.health,
+                width: entry.width,
+                height: entry.height,
+                speed: entry.speed,
+                behaviours: entry.behaviours,
+                spawn: entry.spawn,
+                model,
+            });
+        }
+
+        Ok(registry)
+    }
+
+    /// The number of mobs, which is one more than the largest `MobId`.
+    pub fn mob_count(&self) -> usize {
+        self.mobs.len()
+    }
+
+    pub fn mobs<'r>(&'r self) -> impl Iterator<Item = MobId> + 'r {
+        (0..self.mobs.len()).map(MobId)
+    }
+
+    pub fn get_id(&self, name: &str) -> Option<MobId> {
+        self.name_map.get(name).cloned()
+    }
+
+    pub fn get(&self, id: MobId) -> &MobInfo {
+        &self.mobs[id.0]
+    }
+}
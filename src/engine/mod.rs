@@ -3,12 +3,21 @@ use std::ops::Neg;
 
 pub mod world;
 
+pub mod assets;
 pub mod audio;
 pub mod camera;
 pub mod components;
+pub mod console;
+pub mod definitions;
+pub mod item;
 pub mod job;
+pub mod keybinds;
+pub mod mob;
+pub mod model;
 pub mod render;
+pub mod replay;
 pub mod resources;
+pub mod settings;
 pub mod systems;
 
 pub mod prelude {
@@ -52,6 +61,26 @@ pub enum Side {
 }
 
 impl Side {
+    pub const ALL: [Side; 6] = [
+        Side::Top,
+        Side::Bottom,
+        Side::Right,
+        Side::Left,
+        Side::Front,
+        Side::Back,
+    ];
+
+    pub fn opposite(&self) -> Side {
+        match *self {
+            Side::Top => Side::Bottom,
+            Side::Bottom => Side::Top,
+            Side::Right => Side::Left,
+            Side::Left => Side::Right,
+            Side::Front => Side::Back,
+            Side::Back => Side::Front,
+        }
+    }
+
     pub fn facing_positive(&self) -> bool {
         match self {
             Side::Top | Side::Right | Side::Front => true,
@@ -0,0 +1,237 @@
+use engine::{
+    assets::AssetManager,
+    definitions::{self, Definition, DefinitionError, Problem, SourcedEntry},
+};
+use std::collections::HashMap;
+
+/// The most parts a model can have, which is how many the entity shader has
+/// room for.
+pub const MAX_PARTS: usize = 16;
+/// How many of a model's units fit in a block. Sizes in models are in pixels
+/// of a block texture.
+pub const MODEL_UNITS_PER_BLOCK: f32 = 16.0;
+
+/// Identifies a model, in the order they are declared in `models.json`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct ModelId(usize);
+
+/// One box of a model. Positions are relative to the entity's feet, with the
+/// front of the model facing +Z.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPart {
+    pub name: String,
+    /// Opposite corners of the box.
+    pub from: [f32; 3],
+    pub to: [f32; 3],
+    /// The point the part swings around, like a shoulder or a hip.
+    #[serde(default)]
+    pub pivot: [f32; 3],
+    /// The top left corner of the part's faces in the texture. The faces are
+    /// laid out like a box that has been cut open and flattened: the top and
+    /// bottom in the first row, then the left, front, right and back sides.
+    pub uv: [u32; 2],
+    /// How far the part swings back and forth around the X axis while the
+    /// entity walks, in degrees.
+    #[serde(default)]
+    pub swing: f32,
+    /// Where in the walk cycle the part is, in degrees. Legs that move
+    /// opposite to each other are 180 degrees apart.
+    #[serde(default)]
+    pub phase: f32,
+}
+
+impl ModelPart {
+    /// The size of the box in each direction, which is also the size of its
+    /// faces in the texture.
+    pub fn size(&self) -> [u32; 3] {
+        let mut size = [0; 3];
+        for axis in 0..3 {
+            size[axis] = (self.to[axis] - self.from[axis]).abs().round() as u32;
+        }
+        size
+    }
+
+    /// The width and height in the texture that the part's faces take up.
+    pub fn texture_extent(&self) -> [u32; 2] {
+        let [w, h, d] = self.size();
+        [2 * (d + w), d + h]
+    }
+}
+
+/// A model as it is written in `models.json`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelEntry {
+    name: String,
+    /// An image in `textures/`, which can be any size.
+    texture: String,
+    /// The size the part UVs are measured in, which can be smaller than the
+    /// image to draw it in more detail.
+    texture_size: [u32; 2],
+    parts: Vec<ModelPart>,
+}
+
+impl Definition for ModelEntry {
+    const KIND: &'static str = "models";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn check_entry(sourced: &SourcedEntry<ModelEntry>, problems: &mut Vec<Problem>) {
+    let entry = &sourced.entry;
+    let mut problem = |path: &str, message: String| problems.push(sourced.problem(path, message));
+
+    let [tex_width, tex_height] = entry.texture_size;
+    if tex_width == 0 || tex_height == 0 {
+        problem(
+            "/texture_size",
+            "the texture size has to be more than 0".into(),
+        );
+    }
+    if entry.parts.is_empty() || entry.parts.len() > MAX_PARTS {
+        problem(
+            "/parts",
+            format!("models have to have from 1 to {} parts", MAX_PARTS),
+        );
+    }
+
+    let mut seen = HashMap::new();
+    for (idx, part) in entry.parts.iter().enumerate() {
+        if let Some(first) = seen.insert(&part.name, idx) {
+            problem(
+                &format!("/parts/{}/name", idx),
+                format!("`{}` is already a part at /parts/{}", part.name, first),
+            );
+        }
+
+        if part.size().iter().any(|&len| len == 0) {
+            problem(
+                &format!("/parts/{}/to", idx),
+                "parts have to be at least 1 unit in every direction".into(),
+            );
+        }
+        let is_whole = |pos: &[f32; 3]| pos.iter().all(|c| c.fract() == 0.0);
+        if !is_whole(&part.from) || !is_whole(&part.to) {
+            problem(
+                &format!("/parts/{}/from", idx),
+                "the corners of parts have to be whole numbers, so that they line up with the texture"
+                    .into(),
+            );
+        }
+
+        let [width, height] = part.texture_extent();
+        if part.uv[0] + width > tex_width || part.uv[1] + height > tex_height {
+            problem(
+                &format!("/parts/{}/uv", idx),
+                format!(
+                    "the faces of the part take up {}x{} units from {:?}, which goes past the edge of the texture",
+                    width, height, part.uv
+                ),
+            );
+        }
+    }
+}
+
+fn check_texture_files(
+    assets: &AssetManager,
+    entries: &[SourcedEntry<ModelEntry>],
+    problems: &mut Vec<Problem>,
+) {
+    for sourced in entries {
+        let file = &sourced.entry.texture;
+        if let Err(err) = assets.load_image(&format!("textures/{}", file)) {
+            let message = format!("could not load texture `{}`: {}", file, err);
+            problems.push(sourced.problem("/texture", message));
+        }
+    }
+}
+
+/// Everything about one model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelInfo {
+    pub name: String,
+    /// The path of the texture, including `textures/`.
+    pub texture: String,
+    pub texture_size: [u32; 2],
+    pub parts: Vec<ModelPart>,
+}
+
+/// Every entity model, declared in `models.json`. Models are made of boxes
+/// that can swing around a pivot while the entity walks.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct ModelRegistry {
+    models: Vec<ModelInfo>,
+    name_map: HashMap<String, ModelId>,
+}
+
+impl ModelRegistry {
+    /// Loads `models.json` from every resource pack. Models from later packs
+    /// replace earlier ones with the same name.
+    pub fn load_from_assets(assets: &AssetManager) -> Result<Self, DefinitionError> {
+        let (entries, problems) = ModelRegistry::load_entries(assets)?;
+        ModelRegistry::from_entries(entries, problems)
+    }
+
+    /// Like `load_from_assets`, but also checks that the texture of every
+    /// model can be loaded, which the renderer does separately.
+    pub fn load_validated(assets: &AssetManager) -> Result<Self, DefinitionError> {
+        let (entries, mut problems) = ModelRegistry::load_entries(assets)?;
+        check_texture_files(assets, &entries, &mut problems);
+        ModelRegistry::from_entries(entries, problems)
+    }
+
+    fn load_entries(
+        assets: &AssetManager,
+    ) -> Result<(Vec<SourcedEntry<ModelEntry>>, Vec<Problem>), DefinitionError> {
+        let mut problems = Vec::new();
+        let entries = definitions::load_all(assets, "models.json", &mut problems)?;
+        for sourced in &entries {
+            check_entry(sourced, &mut problems);
+        }
+
+        Ok((entries, problems))
+    }
+
+    fn from_entries(
+        entries: Vec<SourcedEntry<ModelEntry>>,
+        problems: Vec<Problem>,
+    ) -> Result<Self, DefinitionError> {
+        if !problems.is_empty() {
+            return Err(DefinitionError::Invalid(ModelEntry::KIND, problems));
+        }
+
+        let mut registry = ModelRegistry::default();
+        for SourcedEntry { entry, .. } in entries {
+            let id = ModelId(registry.models.len());
+            registry.name_map.insert(entry.name.clone(), id);
+            registry.models.push(ModelInfo {
+                name: entry.name,
+                texture: format!("textures/{}", entry.texture),
+                texture_size: entry.texture_size,
+                parts: entry.parts,
+            });
+        }
+
+        Ok(registry)
+    }
+
+    /// The number of models, which is one more than the largest `ModelId`.
+    pub fn model_count(&self) -> usize {
+        self.models.len()
+    }
+
+    pub fn models<'r>(&'r self) -> impl Iterator<Item = ModelId> + 'r {
+        (0..self.models.len()).map(ModelId)
+    }
+
+    pub fn get_id(&self, name: &str) -> Option<ModelId> {
+        self.name_map.get(name).cloned()
+    }
+
+    pub fn get(&self, id: ModelId) -> &ModelInfo {
+        &self.models[id.0]
+    }
+}
@@ -0,0 +1,181 @@
+use collision::Aabb3;
+use engine::{
+    assets::{AssetChanged, AssetManager, ChangedAssets},
+    camera::Camera,
+    console::Console,
+    prelude::*,
+    render::{verts::PosUv, CUBE_FACES},
+    world::block::BlockModel,
+};
+use gl_api::{
+    buffer::Buffer,
+    context::Context,
+    shader::program::Program,
+    texture::{MagFilter, MinFilter, Texture2d},
+    PrimitiveType, UsageType,
+};
+use std::sync::Arc;
+
+/// How far the overlay sticks out of the block, so that it isn't hidden by
+/// the block's own faces.
+const INFLATE: f32 = 0.002;
+
+/// Loads the crack texture, which has every stage of breaking stacked on top
+/// of each other, and returns it along with how many stages it has.
+fn load_cracks(ctx: &Context, assets: &AssetManager) -> (Texture2d, i32) {
+    let image = assets.load_image_or_missing("breaking.png");
+    let stage_count = (image.height() / image.width().max(1)).max(1) as i32;
+    let texture = Texture2d::from_image(ctx, &image);
+    texture.set_min_filter(ctx, MinFilter::Nearest);
+    texture.set_mag_filter(ctx, MagFilter::Nearest);
+    (texture, stage_count)
+}
+
+fn push_box(vertices: &mut Vec<PosUv>, bounds: &Aabb3<f32>) {
+    let min = bounds.min.to_vec() - Vector3::new(INFLATE, INFLATE, INFLATE);
+    let size = bounds.max - bounds.min + Vector3::new(2.0 * INFLATE, 2.0 * INFLATE, 2.0 * INFLATE);
+    let scale = |v: [f32; 3]| Vector3::new(v[0] * size.x, v[1] * size.y, v[2] * size.z);
+
+    for &(_, corner, u, v) in &CUBE_FACES {
+        let origin = min + scale(corner);
+        let (u, v) = (scale(u), scale(v));
+        let vertex = |pos, uv_x, uv_y| PosUv {
+            pos,
+            uv: Vector2::new(uv_x, uv_y),
+        };
+
+        let bottom_left = vertex(origin, 0.0, 1.0);
+        let top_left = vertex(origin + v, 0.0, 0.0);
+        let bottom_right = vertex(origin + u, 1.0, 1.0);
+        let top_right = vertex(origin + u + v, 1.0, 0.0);
+
+        vertices.extend(&[
+            bottom_left,
+            top_left,
+            bottom_right,
+            bottom_right,
+            top_left,
+            top_right,
+        ]);
+    }
+}
+
+/// Draws cracks over the block that the player is breaking, getting more
+/// cracked as it gets closer to breaking.
+pub struct DrawBlockBreaking {
+    ctx: Context,
+    assets: Arc<AssetManager>,
+    changes: ReaderId<AssetChanged>,
+    program: Program,
+    texture: Texture2d,
+    stage_count: i32,
+    buffer: Buffer<PosUv>,
+    vertices: Vec<PosUv>,
+}
+
+impl DrawBlockBreaking {
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> Self {
+        let (texture, stage_count) = load_cracks(ctx, &assets);
+        let program = assets.load_shader(ctx, "shaders/breaking.vs", "shaders/breaking.fs");
+
+        DrawBlockBreaking {
+            ctx: ctx.clone(),
+            assets,
+            changes: changes.register_reader(),
+            program,
+            texture,
+            stage_count,
+            buffer: Buffer::new(ctx),
+            vertices: Vec::new(),
+        }
+    }
+
+    fn reload(&mut self, changed: &ChangedAssets, console: &mut Console) {
+        if changed.any("breaking.png") {
+            let (texture, stage_count) = load_cracks(&self.ctx, &self.assets);
+            self.texture = texture;
+            self.stage_count = stage_count;
+        }
+
+        if changed.any("shaders/breaking.") {
+            match self.assets.try_load_shader(
+                &self.ctx,
+                "shaders/breaking.vs",
+                "shaders/breaking.fs",
+            ) {
+                Ok(program) => {
+                    self.program = program;
+                    console.print("Reloaded the block breaking shader");
+                }
+                Err(err) => console.print(err),
+            }
+        }
+    }
+}
+
+impl<'a> System<'a> for DrawBlockBreaking {
+    type SystemData = (
+        Read<'a, res::BlockBreaking>,
+        ReadExpect<'a, VoxelWorld>,
+        ReadExpect<'a, Camera>,
+        Read<'a, EventChannel<AssetChanged>>,
+        Write<'a, Console>,
+    );
+
+    fn run(&mut self, (breaking, world, camera, changes, mut console): Self::SystemData) {
+        let changed = ChangedAssets::read(&changes, &mut self.changes);
+        if !changed.is_empty() {
+            self.reload(&changed, &mut console);
+        }
+
+        let (pos, progress) = match breaking.0 {
+            Some(breaking) => breaking,
+            None => return,
+        };
+        let id = match world.get_block_id(pos) {
+            Some(id) => id,
+            None => return,
+        };
+
+        self.vertices.clear();
+        match world.get_registry().model(id) {
+            BlockModel::Boxes(boxes) => {
+                for bounds in boxes {
+                    push_box(&mut self.vertices, bounds);
+                }
+            }
+            _ => push_box(
+                &mut self.vertices,
+                &Aabb3::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0)),
+            ),
+        }
+        self.buffer
+            .upload(&self.ctx, &self.vertices, UsageType::DynamicDraw)
+            .unwrap();
+
+        let stage = ((progress * self.stage_count as f32) as i32).min(self.stage_count - 1);
+        let model = Matrix4::from_translation(pos.0.to_vec().cast::<f32>().unwrap());
+        let projection: Matrix4<f32> = camera.projection_matrix().cast().unwrap();
+        let view: Matrix4<f32> = camera.view_matrix().cast().unwrap();
+
+        self.program
+            .set_uniform(&self.ctx, "projection_matrix", &projection);
+        self.program.set_uniform(&self.ctx, "view_matrix", &view);
+        self.program.set_uniform(&self.ctx, "model_matrix", &model);
+        self.program.set_uniform(&self.ctx, "stage", &stage);
+        // set every frame, since the shader and the texture reload separately
+        self.program
+            .set_uniform(&self.ctx, "stage_count", &self.stage_count);
+        // other passes bind their own textures to the same unit
+        self.program.set_uniform(&self.ctx, "cracks", &self.texture);
+
+        gl_call!(assert DepthMask(gl::FALSE));
+        self.ctx
+            .draw_arrays(PrimitiveType::Triangles, &self.program, &self.buffer);
+        gl_call!(assert DepthMask(gl::TRUE));
+    }
+}
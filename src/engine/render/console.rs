@@ -0,0 +1,112 @@
+use engine::{
+    assets::{AssetChanged, AssetManager},
+    console::Console,
+    prelude::*,
+    render::text::{TextRenderer, GLYPH_ADVANCE, LINE_HEIGHT},
+};
+use gl_api::context::Context;
+use glutin::GlWindow;
+use std::sync::Arc;
+
+const TEXT_SCALE: f32 = 2.0;
+const PADDING: f32 = 6.0;
+/// How many lines of output are shown above the input line.
+const VISIBLE_LINES: usize = 12;
+
+const TEXT_COLOR: Vector4<f32> = Vector4 {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+    w: 1.0,
+};
+const INPUT_COLOR: Vector4<f32> = Vector4 {
+    x: 1.0,
+    y: 1.0,
+    z: 0.6,
+    w: 1.0,
+};
+const BACKGROUND_COLOR: Vector4<f32> = Vector4 {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+    w: 0.6,
+};
+
+/// Draws the console along the bottom of the window while it is open.
+pub struct DrawConsole {
+    text: TextRenderer,
+}
+
+impl DrawConsole {
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> Self {
+        DrawConsole {
+            text: TextRenderer::new(ctx, assets, changes),
+        }
+    }
+}
+
+impl<'a> System<'a> for DrawConsole {
+    type SystemData = (
+        Read<'a, Console>,
+        ReadExpect<'a, GlWindow>,
+        Read<'a, EventChannel<AssetChanged>>,
+    );
+
+    fn run(&mut self, (console, window, changes): Self::SystemData) {
+        self.text.reload(&changes);
+        if !console.open {
+            return;
+        }
+
+        let size = window.get_inner_size().unwrap();
+        let size: (f32, f32) = {
+            let size: (f64, f64) = size.to_physical(window.get_hidpi_factor()).into();
+            (size.0 as f32, size.1 as f32)
+        };
+
+        let line_height = LINE_HEIGHT * TEXT_SCALE;
+        let height = (VISIBLE_LINES + 1) as f32 * line_height + 2.0 * PADDING;
+        let top = size.1 - height;
+        self.text.draw_rect(
+            Point2::new(0.0, top),
+            Point2::new(size.0, size.1),
+            BACKGROUND_COLOR,
+        );
+
+        let mut output = String::new();
+        for line in console.recent_output(VISIBLE_LINES) {
+            output += line;
+            output.push('\n');
+        }
+        let shown = console.recent_output(VISIBLE_LINES).count();
+        let output_top = top + PADDING + (VISIBLE_LINES - shown) as f32 * line_height;
+        self.text.draw_text(
+            &output,
+            Point2::new(PADDING, output_top),
+            TEXT_SCALE,
+            TEXT_COLOR,
+        );
+
+        let input_pos = Point2::new(PADDING, size.1 - PADDING - line_height);
+        let input = format!("> {}", console.input());
+        self.text
+            .draw_text(&input, input_pos, TEXT_SCALE, INPUT_COLOR);
+
+        // cursor after the last character
+        let cursor_x = input_pos.x + input.chars().count() as f32 * GLYPH_ADVANCE * TEXT_SCALE;
+        self.text.draw_rect(
+            Point2::new(cursor_x, input_pos.y + line_height - 2.0 * TEXT_SCALE),
+            Point2::new(
+                cursor_x + (GLYPH_ADVANCE - 1.0) * TEXT_SCALE,
+                input_pos.y + line_height - TEXT_SCALE,
+            ),
+            INPUT_COLOR,
+        );
+
+        self.text.flush(size);
+    }
+}
@@ -1,17 +1,23 @@
 use cgmath::Deg;
 use collision::{Aabb3, Ray3};
-use engine::{camera::Camera, prelude::*, world::chunk::SIZE};
+use engine::{
+    assets::{AssetChanged, AssetManager, ChangedAssets},
+    camera::Camera,
+    console::Console,
+    prelude::*,
+    world::chunk::SIZE,
+};
 use gl_api::{
     buffer::{Buffer, UsageType},
     context::Context,
-    shader::{program::Program, simple_pipeline},
+    shader::program::Program,
     PrimitiveType,
 };
 use ordered_float::OrderedFloat;
 use specs::shred::PanicHandler;
 use std::{
     collections::{HashMap, HashSet},
-    sync::{Mutex, MutexGuard},
+    sync::{Arc, Mutex, MutexGuard},
 };
 
 vertex! {
@@ -66,6 +72,10 @@ impl DebugAccumulator {
         self.shape_buffer.get_mut().unwrap()
     }
 
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.enabled.contains(name)
+    }
+
     pub fn enable(&mut self, name: String) {
         self.enabled.insert(name);
     }
@@ -81,24 +91,27 @@ impl DebugAccumulator {
 
 pub struct DebugRenderer {
     ctx: Context,
+    assets: Arc<AssetManager>,
+    changes: ReaderId<AssetChanged>,
     program: Program,
     vbo: Buffer<DebugVertex>,
     geometry: HashMap<OrderedFloat<f64>, Vec<DebugVertex>>,
 }
 
 impl DebugRenderer {
-    pub fn new(ctx: &mut Context) -> (Self, DebugAccumulator) {
-        let program = simple_pipeline(
-            ctx,
-            "resources/shaders/debug.vs",
-            "resources/shaders/debug.fs",
-        )
-        .unwrap();
+    pub fn new(
+        ctx: &mut Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> (Self, DebugAccumulator) {
+        let program = assets.load_shader(ctx, "shaders/debug.vs", "shaders/debug.fs");
         let vbo = Buffer::new(ctx);
 
         (
             DebugRenderer {
                 ctx: ctx.clone(),
+                assets,
+                changes: changes.register_reader(),
                 geometry: HashMap::new(),
                 program,
                 vbo,
@@ -110,6 +123,21 @@ impl DebugRenderer {
         )
     }
 
+    fn reload(&mut self, changed: &ChangedAssets, console: &mut Console) {
+        if changed.any("shaders/debug.") {
+            match self
+                .assets
+                .try_load_shader(&self.ctx, "shaders/debug.vs", "shaders/debug.fs")
+            {
+                Ok(program) => {
+                    self.program = program;
+                    console.print("Reloaded the debug shader");
+                }
+                Err(err) => console.print(err),
+            }
+        }
+    }
+
     fn add_line(&mut self, start: WorldPos, end: WorldPos, color: Vector4<f64>, weight: f64) {
         self.geometry
             .entry(OrderedFloat(weight))
@@ -164,12 +192,27 @@ impl<'a> System<'a> for DebugRenderer {
         ReadStorage<'a, comp::ClientControlled>,
         ReadExpect<'a, Camera>,
         WriteExpect<'a, DebugAccumulator>,
+        Read<'a, EventChannel<AssetChanged>>,
+        Write<'a, Console>,
     );
 
     fn run(
         &mut self,
-        (transforms, player_marker, client_controlled_marker, camera, mut accumulator): Self::SystemData,
+        (
+            transforms,
+            player_marker,
+            client_controlled_marker,
+            camera,
+            mut accumulator,
+            changes,
+            mut console,
+        ): Self::SystemData,
     ) {
+        let changed = ChangedAssets::read(&changes, &mut self.changes);
+        if !changed.is_empty() {
+            self.reload(&changed, &mut console);
+        }
+
         self.geometry.clear();
         for shape in accumulator.shapes_mut().drain(..) {
             match shape {
@@ -0,0 +1,338 @@
+use cgmath::Rad;
+use engine::{
+    assets::{AssetChanged, AssetManager, ChangedAssets},
+    camera::Camera,
+    console::Console,
+    model::{ModelId, ModelInfo, ModelPart, ModelRegistry, MODEL_UNITS_PER_BLOCK},
+    prelude::*,
+    render::CUBE_FACES,
+    Side,
+};
+use gl_api::{
+    buffer::Buffer,
+    context::Context,
+    shader::program::Program,
+    texture::{MinFilter, Texture2d},
+    PrimitiveType, UsageType,
+};
+use std::{collections::HashMap, sync::Arc};
+
+vertex! {
+    vertex ModelVertex {
+        pos: Vector3<f32>,
+        normal: Vector3<f32>,
+        uv: Vector2<f32>,
+        part: i32,
+    }
+}
+
+vertex! {
+    vertex ModelInstance {
+        model_x: Vector4<f32>,
+        model_y: Vector4<f32>,
+        model_z: Vector4<f32>,
+        model_w: Vector4<f32>,
+        walk_phase: f32,
+        walk_amount: f32,
+    }
+}
+
+/// How far through the walk cycle an entity gets for each block it walks, in
+/// radians.
+const WALK_CYCLE_PER_BLOCK: f32 = 2.5;
+/// How fast an entity has to move for its parts to swing all the way, in
+/// blocks per second.
+const FULL_SWING_SPEED: f32 = 3.0;
+/// How quickly the swing of the parts catches up with the speed, so that they
+/// settle down instead of stopping dead.
+const SWING_RESPONSE: f32 = 8.0;
+
+/// Adds the faces of one part, in blocks, with the texture unwrapped around
+/// it like in `ModelPart::uv`.
+fn push_part(
+    vertices: &mut Vec<ModelVertex>,
+    part: &ModelPart,
+    index: usize,
+    texture_size: [u32; 2],
+) {
+    let min = Vector3::new(
+        part.from[0].min(part.to[0]),
+        part.from[1].min(part.to[1]),
+        part.from[2].min(part.to[2]),
+    ) / MODEL_UNITS_PER_BLOCK;
+    let size = part.size();
+    let (w, h, d) = (size[0] as f32, size[1] as f32, size[2] as f32);
+    let (u, v) = (part.uv[0] as f32, part.uv[1] as f32);
+    let (tex_width, tex_height) = (texture_size[0] as f32, texture_size[1] as f32);
+    let scale = |v: Vector3<f32>| Vector3::new(v.x * w, v.y * h, v.z * d) / MODEL_UNITS_PER_BLOCK;
+
+    for &(side, corner, u_dir, v_dir) in CUBE_FACES.iter() {
+        // the top left corner and size of the face in the texture
+        let (face_u, face_v, face_width, face_height) = match side {
+            Side::Top => (u + d, v, w, d),
+            Side::Bottom => (u + d + w, v, w, d),
+            Side::Left => (u, v + d, d, h),
+            Side::Front => (u + d, v + d, w, h),
+            Side::Right => (u + d + w, v + d, d, h),
+            Side::Back => (u + 2.0 * d + w, v + d, w, h),
+        };
+        let (corner, u_dir, v_dir) = (
+            Vector3::from(corner),
+            Vector3::from(u_dir),
+            Vector3::from(v_dir),
+        );
+        let normal = u_dir.cross(v_dir);
+
+        let vertex = |a: f32, b: f32| ModelVertex {
+            pos: min + scale(corner + u_dir * a + v_dir * b),
+            normal,
+            // the texture goes from the top down, but the V axis of the face
+            // goes up
+            uv: Vector2::new(
+                (face_u + a * face_width) / tex_width,
+                (face_v + (1.0 - b) * face_height) / tex_height,
+            ),
+            part: index as i32,
+        };
+
+        let bottom_left = vertex(0.0, 0.0);
+        let top_left = vertex(0.0, 1.0);
+        let bottom_right = vertex(1.0, 0.0);
+        let top_right = vertex(1.0, 1.0);
+
+        vertices.extend(&[
+            bottom_left,
+            top_left,
+            bottom_right,
+            bottom_right,
+            top_left,
+            top_right,
+        ]);
+    }
+}
+
+/// A model that is ready to draw, and the entities to draw it for this frame.
+struct GpuModel {
+    vertices: Buffer<ModelVertex>,
+    texture: Texture2d,
+    /// The pivot, swing and phase of each part, in blocks and radians.
+    pivots: Vec<Vector3<f32>>,
+    swings: Vec<f32>,
+    phases: Vec<f32>,
+    instances: Buffer<ModelInstance>,
+    instance_data: Vec<ModelInstance>,
+}
+
+impl GpuModel {
+    fn load(ctx: &Context, assets: &AssetManager, model: &ModelInfo) -> Self {
+        let mut vertex_data = Vec::new();
+        for (index, part) in model.parts.iter().enumerate() {
+            push_part(&mut vertex_data, part, index, model.texture_size);
+        }
+        let mut vertices = Buffer::new(ctx);
+        vertices
+            .upload(ctx, &vertex_data, UsageType::StaticDraw)
+            .unwrap();
+
+        let texture = Texture2d::from_image(ctx, &assets.load_image_or_missing(&model.texture));
+        texture.set_min_filter(ctx, MinFilter::Nearest);
+
+        GpuModel {
+            vertices,
+            texture,
+            pivots: model
+                .parts
+                .iter()
+                .map(|part| Vector3::from(part.pivot) / MODEL_UNITS_PER_BLOCK)
+                .collect(),
+            swings: model
+                .parts
+                .iter()
+                .map(|part| Rad::from(Deg(part.swing)).0)
+                .collect(),
+            phases: model
+                .parts
+                .iter()
+                .map(|part| Rad::from(Deg(part.phase)).0)
+                .collect(),
+            instances: Buffer::new(ctx),
+            instance_data: Vec::new(),
+        }
+    }
+}
+
+/// Draws entities that have an `EntityModel`, except for the player being
+/// controlled, whose model would be in the way of the camera. Every entity
+/// with the same model is drawn at once with an instanced draw call.
+pub struct DrawEntities {
+    ctx: Context,
+    assets: Arc<AssetManager>,
+    changes: ReaderId<AssetChanged>,
+    program: Program,
+    models: HashMap<ModelId, GpuModel>,
+}
+
+impl DrawEntities {
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+        models: &ModelRegistry,
+    ) -> Self {
+        let program = assets.load_shader(ctx, "shaders/entity.vs", "shaders/entity.fs");
+
+        DrawEntities {
+            ctx: ctx.clone(),
+            changes: changes.register_reader(),
+            program,
+            models: Self::load_models(ctx, &assets, models),
+            assets,
+        }
+    }
+
+    fn load_models(
+        ctx: &Context,
+        assets: &AssetManager,
+        models: &ModelRegistry,
+    ) -> HashMap<ModelId, GpuModel> {
+        models
+            .models()
+            .map(|id| (id, GpuModel::load(ctx, assets, models.get(id))))
+            .collect()
+    }
+
+    fn reload(&mut self, changed: &ChangedAssets, models: &ModelRegistry, console: &mut Console) {
+        if changed.any("textures/") {
+            self.models = Self::load_models(&self.ctx, &self.assets, models);
+        }
+
+        if changed.any("shaders/entity.") {
+            match self
+                .assets
+                .try_load_shader(&self.ctx, "shaders/entity.vs", "shaders/entity.fs")
+            {
+                Ok(program) => {
+                    self.program = program;
+                    console.print("Reloaded the entity shader");
+                }
+                Err(err) => console.print(err),
+            }
+        }
+    }
+}
+
+impl<'a> System<'a> for DrawEntities {
+    type SystemData = (
+        ReadExpect<'a, ModelRegistry>,
+        ReadExpect<'a, Camera>,
+        Read<'a, res::SunDirection>,
+        Read<'a, res::Dt>,
+        Read<'a, EventChannel<AssetChanged>>,
+        Write<'a, Console>,
+        WriteStorage<'a, comp::EntityModel>,
+        ReadStorage<'a, comp::Transform>,
+        ReadStorage<'a, comp::RigidBody>,
+        ReadStorage<'a, comp::Collidable>,
+        ReadStorage<'a, comp::ClientControlled>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            models,
+            camera,
+            sun,
+            dt,
+            changes,
+            mut console,
+            mut entity_models,
+            transforms,
+            bodies,
+            collidables,
+            client_controlled,
+        ): Self::SystemData,
+    ) {
+        let changed = ChangedAssets::read(&changes, &mut self.changes);
+        if !changed.is_empty() {
+            self.reload(&changed, &models, &mut console);
+        }
+
+        let dt = dt.as_secs() as f32;
+        for (entity_model, transform, body, collidable, _) in (
+            &mut entity_models,
+            &transforms,
+            bodies.maybe(),
+            collidables.maybe(),
+            !&client_controlled,
+        )
+            .join()
+        {
+            let speed = body.map_or(0.0, |body| {
+                let velocity = body.velocity.cast::<f32>().unwrap();
+                Vector2::new(velocity.x, velocity.z).magnitude()
+            });
+            let target = ::util::clamp(speed / FULL_SWING_SPEED, 0.0, 1.0);
+            entity_model.walk_amount +=
+                (target - entity_model.walk_amount) * (SWING_RESPONSE * dt).min(1.0);
+            entity_model.walk_phase = (entity_model.walk_phase + speed * WALK_CYCLE_PER_BLOCK * dt)
+                % (2.0 * ::std::f32::consts::PI);
+
+            let gpu_model = match self.models.get_mut(&entity_model.model) {
+                Some(model) => model,
+                None => continue,
+            };
+            // models are placed at the bottom of the collision box, which is
+            // where the feet are
+            let feet = collidable.map_or(0.0, |collidable| collidable.aabb.min.y);
+            let position = transform.position + Vector3::new(0.0, feet, 0.0);
+            let yaw = Deg(transform.orientation.y.0 as f32);
+            let model_matrix = Matrix4::from_translation(position.cast::<f32>().unwrap().to_vec())
+                * Matrix4::from_angle_y(yaw);
+
+            gpu_model.instance_data.push(ModelInstance {
+                model_x: model_matrix.x,
+                model_y: model_matrix.y,
+                model_z: model_matrix.z,
+                model_w: model_matrix.w,
+                walk_phase: entity_model.walk_phase,
+                walk_amount: entity_model.walk_amount,
+            });
+        }
+
+        let projection: Matrix4<f32> = camera.projection_matrix().cast().unwrap();
+        let view: Matrix4<f32> = camera.view_matrix().cast().unwrap();
+        let sun: Vector3<f32> = sun.0.cast().unwrap();
+        self.program
+            .set_uniform(&self.ctx, "projection_matrix", &projection);
+        self.program.set_uniform(&self.ctx, "view_matrix", &view);
+        self.program.set_uniform(&self.ctx, "sun_direction", &sun);
+
+        for model in self.models.values_mut() {
+            if model.instance_data.is_empty() {
+                continue;
+            }
+            model
+                .instances
+                .upload(&self.ctx, &model.instance_data, UsageType::DynamicDraw)
+                .unwrap();
+            model.instance_data.clear();
+
+            // other passes bind their own textures to the same unit
+            self.program
+                .set_uniform(&self.ctx, "model_texture", &model.texture);
+            self.program
+                .set_uniform(&self.ctx, "part_pivots", &model.pivots[..]);
+            self.program
+                .set_uniform(&self.ctx, "part_swing", &model.swings[..]);
+            self.program
+                .set_uniform(&self.ctx, "part_phase", &model.phases[..]);
+
+            self.ctx.draw_arrays_instanced(
+                PrimitiveType::Triangles,
+                &self.program,
+                &model.vertices,
+                &model.instances,
+            );
+        }
+    }
+}
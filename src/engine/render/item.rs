@@ -0,0 +1,223 @@
+use collision::Aabb3;
+use engine::{
+    assets::{AssetChanged, AssetManager, ChangedAssets},
+    camera::Camera,
+    console::Console,
+    item::{ItemIcon, ItemRegistry},
+    prelude::*,
+    render::{textures::SharedTextures, CUBE_FACES},
+    world::block::BlockModel,
+    Side,
+};
+use gl_api::{
+    buffer::Buffer, context::Context, shader::program::Program, PrimitiveType, UsageType,
+};
+use std::sync::Arc;
+
+vertex! {
+    vertex ItemVertex {
+        pos: Vector3<f32>,
+        uv: Vector2<f32>,
+        layer: i32,
+        light: f32,
+    }
+}
+
+/// Width of a dropped item, in blocks.
+const ITEM_SIZE: f32 = 0.25;
+/// How fast dropped items turn, in degrees per second.
+const SPIN_SPEED: f32 = 90.0;
+/// How far dropped items float up and down.
+const BOB_HEIGHT: f32 = 0.05;
+
+/// How bright each side of a dropped block is, so that its edges can be seen.
+fn side_light(side: Side) -> f32 {
+    match side {
+        Side::Top => 1.0,
+        Side::Bottom => 0.5,
+        Side::Left | Side::Right => 0.8,
+        Side::Front | Side::Back => 0.65,
+    }
+}
+
+fn setup_item_program(ctx: &Context, program: &mut Program, textures: &SharedTextures) {
+    program.set_uniform(ctx, "texture_map", textures.texture_array());
+}
+
+/// Adds the given sides of a box inside the unit cube, with the texture cut
+/// to fit like it is on the terrain.
+fn push_box<F>(
+    vertices: &mut Vec<ItemVertex>,
+    bounds: &Aabb3<f32>,
+    transform: &Matrix4<f32>,
+    sides: &[Side],
+    layer: F,
+) where
+    F: Fn(Side) -> Option<i32>,
+{
+    let size = bounds.max - bounds.min;
+    let scale = |v: [f32; 3]| Vector3::new(v[0] * size.x, v[1] * size.y, v[2] * size.z);
+
+    for &(side, corner, u_dir, v_dir) in CUBE_FACES.iter().filter(|face| sides.contains(&face.0)) {
+        let layer = match layer(side) {
+            Some(layer) => layer,
+            None => continue,
+        };
+        let light = side_light(side);
+
+        let origin = bounds.min + scale(corner);
+        let (u, v) = (scale(u_dir), scale(v_dir));
+        let vertex = |pos: Point3<f32>| {
+            // where the point is on the face of the unit cube
+            let offset = pos.to_vec() - Vector3::from(corner);
+            ItemVertex {
+                pos: transform.transform_point(pos).to_vec(),
+                uv: Vector2::new(
+                    offset.dot(Vector3::from(u_dir)),
+                    1.0 - offset.dot(Vector3::from(v_dir)),
+                ),
+                layer,
+                light,
+            }
+        };
+
+        let bottom_left = vertex(origin);
+        let top_left = vertex(origin + v);
+        let bottom_right = vertex(origin + u);
+        let top_right = vertex(origin + u + v);
+
+        vertices.extend(&[
+            bottom_left,
+            top_left,
+            bottom_right,
+            bottom_right,
+            top_left,
+            top_right,
+        ]);
+    }
+}
+
+/// Draws dropped items as small spinning blocks, or as flat sprites for items
+/// that aren't cubes.
+pub struct DrawDroppedItems {
+    ctx: Context,
+    assets: Arc<AssetManager>,
+    changes: ReaderId<AssetChanged>,
+    program: Program,
+    buffer: Buffer<ItemVertex>,
+    vertices: Vec<ItemVertex>,
+}
+
+impl DrawDroppedItems {
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> Self {
+        let program = assets.load_shader(ctx, "shaders/dropped_item.vs", "shaders/dropped_item.fs");
+
+        DrawDroppedItems {
+            ctx: ctx.clone(),
+            assets,
+            changes: changes.register_reader(),
+            program,
+            buffer: Buffer::new(ctx),
+            vertices: Vec::new(),
+        }
+    }
+
+    fn reload(&mut self, changed: &ChangedAssets, console: &mut Console) {
+        if changed.any("shaders/dropped_item.") {
+            match self.assets.try_load_shader(
+                &self.ctx,
+                "shaders/dropped_item.vs",
+                "shaders/dropped_item.fs",
+            ) {
+                Ok(program) => {
+                    self.program = program;
+                    console.print("Reloaded the dropped item shader");
+                }
+                Err(err) => console.print(err),
+            }
+        }
+    }
+}
+
+impl<'a> System<'a> for DrawDroppedItems {
+    type SystemData = (
+        ReadExpect<'a, VoxelWorld>,
+        ReadExpect<'a, ItemRegistry>,
+        ReadExpect<'a, Camera>,
+        ReadExpect<'a, SharedTextures>,
+        Read<'a, EventChannel<AssetChanged>>,
+        Write<'a, Console>,
+        ReadStorage<'a, comp::Transform>,
+        ReadStorage<'a, comp::DroppedItem>,
+    );
+
+    fn run(
+        &mut self,
+        (world, items, camera, textures, changes, mut console, transforms, dropped): Self::SystemData,
+    ) {
+        let changed = ChangedAssets::read(&changes, &mut self.changes);
+        if !changed.is_empty() {
+            self.reload(&changed, &mut console);
+        }
+        let blocks = world.get_registry();
+
+        let unit_cube = Aabb3::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        // a flat square through the middle of the cube, facing both ways
+        let sprite = Aabb3::new(Point3::new(0.0, 0.0, 0.5), Point3::new(1.0, 1.0, 0.5));
+        let sprite_sides = [Side::Front, Side::Back];
+
+        let vertices = &mut self.vertices;
+        for (transform, item) in (&transforms, &dropped).join() {
+            let age = item.age as f32;
+            let bob = BOB_HEIGHT * (1.0 + (age * 2.0).sin());
+            let center = transform.position.cast::<f32>().unwrap() + Vector3::new(0.0, bob, 0.0);
+            let transform = Matrix4::from_translation(center.to_vec())
+                * Matrix4::from_angle_y(Deg(age * SPIN_SPEED))
+                * Matrix4::from_scale(ITEM_SIZE)
+                * Matrix4::from_translation(Vector3::new(-0.5, -0.5, -0.5));
+            let layer = |side| textures.layer(blocks, &items, item.stack.item, side);
+
+            match items.icon(item.stack.item) {
+                ItemIcon::Block(block) => match blocks.model(block) {
+                    BlockModel::Cube => {
+                        push_box(vertices, &unit_cube, &transform, &Side::ALL, layer)
+                    }
+                    BlockModel::Boxes(boxes) => {
+                        for bounds in boxes {
+                            push_box(vertices, bounds, &transform, &Side::ALL, &layer);
+                        }
+                    }
+                    _ => push_box(vertices, &sprite, &transform, &sprite_sides, |_| {
+                        layer(Side::Front)
+                    }),
+                },
+                ItemIcon::Texture(_) => {
+                    push_box(vertices, &sprite, &transform, &sprite_sides, layer)
+                }
+            }
+        }
+
+        if self.vertices.is_empty() {
+            return;
+        }
+        self.buffer
+            .upload(&self.ctx, &self.vertices, UsageType::DynamicDraw)
+            .unwrap();
+        self.vertices.clear();
+
+        let projection: Matrix4<f32> = camera.projection_matrix().cast().unwrap();
+        let view: Matrix4<f32> = camera.view_matrix().cast().unwrap();
+        self.program
+            .set_uniform(&self.ctx, "projection_matrix", &projection);
+        self.program.set_uniform(&self.ctx, "view_matrix", &view);
+        // other passes bind their own textures to the same unit
+        setup_item_program(&self.ctx, &mut self.program, &textures);
+
+        self.ctx
+            .draw_arrays(PrimitiveType::Triangles, &self.program, &self.buffer);
+    }
+}
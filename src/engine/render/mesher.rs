@@ -1,4 +1,5 @@
-use cgmath::{Point2, Point3, Vector2, Vector3, Vector4};
+use cgmath::{prelude::*, Point2, Point3, Vector2, Vector3, Vector4};
+use collision::Aabb3;
 use engine::{
     components as comp,
     render::{
@@ -7,11 +8,11 @@ use engine::{
         TerrainMeshes,
     },
     world::{
-        block::{self, BlockId, BlockRegistry},
+        block::{self, BlockId, BlockModel, BlockRegistry, RenderLayer},
         chunk::{Chunk, ChunkType, PaddedChunk, SIZE},
         BlockPos, ChunkPos, VoxelWorld,
     },
-    Side,
+    Axis, Side,
 };
 use rand::prelude::*;
 use specs::prelude::*;
@@ -175,25 +176,26 @@ impl<'w> CullMesher<'w> {
         )
     }
 
-    fn is_not_occluded(&self, pos: Point3<usize>, offset: Vector3<isize>) -> bool {
-        let offset = pos.cast::<isize>().unwrap() + offset;
+    fn is_not_occluded(&self, pos: Point3<usize>, side: Side) -> bool {
+        let offset = pos.cast::<isize>().unwrap() + side.normal();
 
         let cur_solid = self.registry.opaque(self.center[pos]);
-        let other_solid = self.registry.opaque(self.center[offset]);
+        // the other block might only cover part of the face, like a slab
+        let other_covers = self.registry.occludes(self.center[offset], side.opposite());
 
         let cur_liquid = self.registry.liquid(self.center[pos]);
         let other_liquid = self.registry.liquid(self.center[offset]);
 
         if self.registry.liquid(self.center[pos]) {
-            // if the current block is liquid, we need a face when the other block is
-            // non-solid or non-liquid
-            cur_liquid && !other_solid && !other_liquid
+            // if the current block is liquid, we need a face when the other block
+            // doesn't cover it and is non-liquid
+            cur_liquid && !other_covers && !other_liquid
         } else {
             // if the current block is not liquid...
             // if the current block is not opaque, then it would never need any faces
-            // if the current block is solid, and the other is either non-opaque or a
-            // liquid, then we need a face
-            cur_solid && (!other_solid || self.registry.liquid(self.center[offset]))
+            // if the current block is solid, and the other doesn't cover the face,
+            // then we need a face. Liquids never cover anything.
+            cur_solid && !other_covers
         }
     }
 
@@ -277,7 +279,7 @@ impl<'w> CullMesher<'w> {
             for u in 0..SIZE {
                 for v in 0..SIZE {
                     let padded = make_coordinate(layer, u, v) + Vector3::new(1, 1, 1);
-                    self.slice[idx(u, v)] = if self.is_not_occluded(padded, side.normal()) {
+                    self.slice[idx(u, v)] = if self.is_not_occluded(padded, side) {
                         VoxelFace {
                             id: self.center[padded],
                             ao: self.face_ao(padded, side),
@@ -297,6 +299,67 @@ impl<'w> CullMesher<'w> {
         }
     }
 
+    /// Adds the blocks that aren't full cubes, which can't be merged with their
+    /// neighbors like cubes can. Cubes that can be seen through, like glass,
+    /// are added here too.
+    fn mesh_models(&mut self) {
+        let registry = self.registry;
+        let unit_box = [Aabb3::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 1.0),
+        )];
+
+        for x in 0..SIZE {
+            for y in 0..SIZE {
+                for z in 0..SIZE {
+                    let pos = Point3::new(x, y, z);
+                    let padded = pos + Vector3::new(1, 1, 1);
+                    let id = self.center[padded];
+
+                    let boxes: &[Aabb3<f32>] = match registry.model(id) {
+                        BlockModel::Cross => {
+                            self.mesh_constructor.add_cross(id, pos);
+                            continue;
+                        }
+                        BlockModel::Boxes(boxes) => boxes,
+                        BlockModel::Cube if !registry.opaque(id) && !registry.liquid(id) => {
+                            &unit_box
+                        }
+                        _ => continue,
+                    };
+
+                    for bounds in boxes {
+                        for &side in Side::ALL.iter() {
+                            if self.box_face_hidden(padded, bounds, side) {
+                                continue;
+                            }
+                            self.mesh_constructor.add_box_face(id, pos, bounds, side);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Faces of a box are only hidden when they lie on the edge of the block
+    /// and the neighbor on that side covers them. A neighbor in the same state
+    /// also hides them if the block culls faces between copies of itself.
+    fn box_face_hidden(&self, pos: Point3<usize>, bounds: &Aabb3<f32>, side: Side) -> bool {
+        let axis = Axis::from(side) as usize;
+        let on_edge = if side.facing_positive() {
+            bounds.max[axis] >= 1.0
+        } else {
+            bounds.min[axis] <= 0.0
+        };
+
+        let id = self.center[pos];
+        let neighbor = self.center[pos.cast::<isize>().unwrap() + side.normal()];
+        let same = neighbor == id
+            && self.registry.cull_same(id)
+            && self.registry.model(id).covers_face(side.opposite());
+        on_edge && (same || self.registry.occludes(neighbor, side.opposite()))
+    }
+
     pub fn mesh(&mut self) {
         self.mesh_slice(Side::Right, |layer, u, v| Point3::new(layer, u, v));
         self.mesh_slice(Side::Left, |layer, u, v| Point3::new(layer, u, v));
@@ -306,6 +369,8 @@ impl<'w> CullMesher<'w> {
 
         self.mesh_slice(Side::Front, |layer, u, v| Point3::new(u, v, layer));
         self.mesh_slice(Side::Back, |layer, u, v| Point3::new(u, v, layer));
+
+        self.mesh_models();
     }
 }
 
@@ -375,6 +440,124 @@ struct MeshConstructor<'w> {
 }
 
 impl<'w> MeshConstructor<'w> {
+    /// Adds a quad with its corners given in order around its edge. The
+    /// winding is fixed up so that the quad faces towards `normal`.
+    fn add_quad(
+        &mut self,
+        layer: RenderLayer,
+        corners: [Point3<f32>; 4],
+        uvs: [Vector2<f32>; 4],
+        normal: Vector3<f32>,
+        tex_id: i32,
+    ) {
+        // front faces are wound clockwise when looking at them
+        let facing = (corners[1] - corners[0])
+            .cross(corners[2] - corners[0])
+            .dot(normal);
+        let indices: &[u32] = if facing > 0.0 {
+            &[0, 2, 1, 0, 3, 2]
+        } else {
+            &[0, 1, 2, 0, 2, 3]
+        };
+
+        let mesh = match layer {
+            RenderLayer::Opaque => {
+                self.terrain_index += 4;
+                &mut self.mesh.terrain
+            }
+            RenderLayer::Cutout => &mut self.mesh.cutout,
+            RenderLayer::Translucent => {
+                let center = corners[0].midpoint(corners[2]);
+                self.mesh.translucent_centers.push(center);
+                &mut self.mesh.translucent
+            }
+        };
+
+        let index = mesh.vertices.len() as u32;
+        mesh.indices.extend(indices.iter().map(|i| i + index));
+
+        for i in 0..4 {
+            mesh.vertices.push(BlockVertex {
+                pos: corners[i],
+                uv: uvs[i],
+                ao: 1.0,
+                normal,
+                tex_id,
+            });
+        }
+    }
+
+    /// Adds one side of a box from a block model, with the texture cut to fit
+    /// the part of the block that the face covers.
+    fn add_box_face(&mut self, id: BlockId, pos: Point3<usize>, bounds: &Aabb3<f32>, side: Side) {
+        let base = pos.cast::<f32>().unwrap().to_vec();
+        let axis = Axis::from(side) as usize;
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+
+        let mut corners = [bounds.min; 4];
+        let layer = if side.facing_positive() {
+            bounds.max[axis]
+        } else {
+            bounds.min[axis]
+        };
+        let edges = [
+            (bounds.min[u], bounds.min[v]),
+            (bounds.max[u], bounds.min[v]),
+            (bounds.max[u], bounds.max[v]),
+            (bounds.min[u], bounds.max[v]),
+        ];
+        for (corner, &(cu, cv)) in corners.iter_mut().zip(edges.iter()) {
+            corner[axis] = layer;
+            corner[u] = cu;
+            corner[v] = cv;
+        }
+
+        // textures are upright on the sides of a block
+        let uv = |p: Point3<f32>| match Axis::from(side) {
+            Axis::X => Vector2::new(p.z, 1.0 - p.y),
+            Axis::Y => Vector2::new(p.x, p.z),
+            Axis::Z => Vector2::new(p.x, 1.0 - p.y),
+        };
+        let uvs = [uv(corners[0]), uv(corners[1]), uv(corners[2]), uv(corners[3])];
+
+        let face = self.registry.block_texture(id, side).unwrap();
+        let tex_id = *face.texture.select() as i32;
+        let corners = [
+            corners[0] + base,
+            corners[1] + base,
+            corners[2] + base,
+            corners[3] + base,
+        ];
+        let layer = self.registry.render_layer(id);
+        self.add_quad(layer, corners, uvs, side.normal(), tex_id);
+    }
+
+    /// Adds two diagonal planes, each visible from both sides.
+    fn add_cross(&mut self, id: BlockId, pos: Point3<usize>) {
+        let base: Point3<f32> = pos.cast().unwrap();
+        let face = self.registry.block_texture(id, Side::Front).unwrap();
+        let tex_id = *face.texture.select() as i32;
+        let layer = self.registry.render_layer(id);
+        let uvs = [
+            Vector2::new(0.0, 1.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(0.0, 0.0),
+        ];
+
+        let diagonals = [
+            (Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 1.0)),
+            (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)),
+        ];
+        for &(start, end) in diagonals.iter() {
+            let up = Vector3::new(0.0, 1.0, 0.0);
+            let corners = [base + start, base + end, base + end + up, base + start + up];
+            let normal = (end - start).cross(up).normalize();
+            self.add_quad(layer, corners, uvs, normal, tex_id);
+            self.add_quad(layer, corners, uvs, -normal, tex_id);
+        }
+    }
+
     fn add_liquid(&mut self, quad: VoxelQuad, side: Side, pos: Point3<usize>) {
         let pos: Point3<f32> = pos.cast().unwrap();
 
@@ -1,14 +1,26 @@
-use cgmath::{Vector2, Vector3};
-use engine::render::{
-    mesh::Mesh,
-    terrain::{BlockVertex, LiquidVertex},
+use cgmath::{prelude::*, Point3, Vector2, Vector3};
+use engine::{
+    render::{
+        mesh::Mesh,
+        terrain::{BlockVertex, LiquidVertex},
+    },
+    Side,
 };
 use specs::prelude::*;
 
+pub mod breaking;
+pub mod console;
 pub mod debug;
+pub mod entity;
+pub mod item;
 pub mod mesh;
 pub mod mesher;
+pub mod overlay;
+pub mod problems;
+pub mod shadow;
 pub mod terrain;
+pub mod text;
+pub mod textures;
 pub mod ui;
 
 pub mod verts {
@@ -59,9 +71,63 @@ pub mod verts {
     ];
 }
 
+/// The corner, then the directions of the texture's U and V axes, for each
+/// side of a unit cube. U cross V points out of the cube, so the faces wind
+/// clockwise when seen from outside.
+pub const CUBE_FACES: [(Side, [f32; 3], [f32; 3], [f32; 3]); 6] = [
+    (Side::Right, [1.0, 0.0, 1.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+    (Side::Left, [0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    (Side::Top, [0.0, 1.0, 1.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+    (Side::Bottom, [0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    (Side::Front, [0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    (Side::Back, [1.0, 0.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+];
+
 #[derive(Debug, Default, Component)]
 #[storage(DenseVecStorage)]
 pub struct TerrainMeshes {
     pub terrain: Mesh<BlockVertex, u32>,
     pub liquid: Mesh<LiquidVertex, u32>,
+    /// Faces with holes in their textures, drawn with alpha testing.
+    pub cutout: Mesh<BlockVertex, u32>,
+    /// Faces that blend with what is behind them. Their quads have to be kept
+    /// sorted back to front, see `sort_translucent`.
+    pub translucent: Mesh<BlockVertex, u32>,
+    /// The center of every quad in `translucent`, in the same order as the
+    /// quads.
+    pub translucent_centers: Vec<Point3<f32>>,
+    sorted_from: Option<Point3<i32>>,
+}
+
+impl TerrainMeshes {
+    /// Orders the translucent quads from farthest to nearest to `eye`, which is
+    /// relative to the chunk. Returns whether the order changed, which only
+    /// happens when the eye moves into a different block since the last sort.
+    pub fn sort_translucent(&mut self, eye: Point3<f32>) -> bool {
+        let block = eye.map(|c| c.floor() as i32);
+        if self.translucent_centers.is_empty() || self.sorted_from == Some(block) {
+            return false;
+        }
+        self.sorted_from = Some(block);
+
+        let centers = &self.translucent_centers;
+        let mut order: Vec<usize> = (0..centers.len()).collect();
+        order.sort_by(|&a, &b| {
+            let da = centers[a].distance2(eye);
+            let db = centers[b].distance2(eye);
+            db.partial_cmp(&da).unwrap_or(::std::cmp::Ordering::Equal)
+        });
+
+        // every quad has 6 indices, and only the indices are reordered
+        let indices = &self.translucent.indices;
+        let mut sorted_indices = Vec::with_capacity(indices.len());
+        let mut sorted_centers = Vec::with_capacity(centers.len());
+        for quad in order {
+            sorted_indices.extend_from_slice(&indices[quad * 6..quad * 6 + 6]);
+            sorted_centers.push(centers[quad]);
+        }
+        self.translucent.indices = sorted_indices;
+        self.translucent_centers = sorted_centers;
+        true
+    }
 }
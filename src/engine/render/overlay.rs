@@ -0,0 +1,258 @@
+use engine::{
+    assets::{AssetChanged, AssetManager},
+    camera::Camera,
+    mob::MobRegistry,
+    prelude::*,
+    render::{
+        debug::DebugSection,
+        text::{TextRenderer, LINE_HEIGHT},
+    },
+    systems::ReadClientPlayer,
+    world::spatial::{trace, EntityIndex, RayHit},
+};
+use gl_api::context::Context;
+use glutin::GlWindow;
+use std::{collections::VecDeque, sync::Arc};
+
+/// How many frames are kept around for the frame time graph and FPS counter.
+const FRAME_HISTORY: usize = 120;
+const TEXT_SCALE: f32 = 2.0;
+const MARGIN: f32 = 6.0;
+
+const GRAPH_BAR_WIDTH: f32 = 2.0;
+/// Pixels of graph height per millisecond of frame time.
+const GRAPH_SCALE: f32 = 3.0;
+const GRAPH_MAX_HEIGHT: f32 = 120.0;
+const TARGET_FRAME_MS: f64 = 1000.0 / 60.0;
+
+const TEXT_COLOR: Vector4<f32> = Vector4 {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+    w: 1.0,
+};
+const BACKGROUND_COLOR: Vector4<f32> = Vector4 {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+    w: 0.5,
+};
+
+fn frame_color(ms: f64) -> Vector4<f32> {
+    if ms <= TARGET_FRAME_MS {
+        Vector4::new(0.3, 1.0, 0.3, 0.8)
+    } else if ms <= 2.0 * TARGET_FRAME_MS {
+        Vector4::new(1.0, 1.0, 0.3, 0.8)
+    } else {
+        Vector4::new(1.0, 0.3, 0.3, 0.8)
+    }
+}
+
+/// The axis the player is mostly looking along.
+fn facing(direction: Vector3<f64>) -> &'static str {
+    if direction.x.abs() > direction.z.abs() {
+        if direction.x > 0.0 {
+            "+X"
+        } else {
+            "-X"
+        }
+    } else if direction.z > 0.0 {
+        "+Z"
+    } else {
+        "-Z"
+    }
+}
+
+/// F3-style overlay with frame timings, player position, the targeted block or
+/// entity, chunk counts and per-system timings.
+pub struct DebugOverlay {
+    text: TextRenderer,
+    frame_times: VecDeque<f64>,
+}
+
+impl DebugOverlay {
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> Self {
+        DebugOverlay {
+            text: TextRenderer::new(ctx, assets, changes),
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+        }
+    }
+
+    /// Draws a block of text with a translucent background behind it,
+    /// returning the y coordinate just below it.
+    fn draw_panel(&mut self, text: &str, pos: Point2<f32>) -> f32 {
+        let lines = text.lines().count() as f32;
+        let size = Vector2::new(
+            TextRenderer::text_width(text, TEXT_SCALE),
+            lines * LINE_HEIGHT * TEXT_SCALE,
+        );
+
+        let padding = Vector2::new(2.0, 2.0);
+        self.text
+            .draw_rect(pos - padding, pos + size + padding, BACKGROUND_COLOR);
+        self.text.draw_text(text, pos, TEXT_SCALE, TEXT_COLOR);
+        pos.y + size.y + 2.0 * padding.y
+    }
+
+    fn draw_frame_graph(&mut self, bottom_left: Point2<f32>) {
+        let width = FRAME_HISTORY as f32 * GRAPH_BAR_WIDTH;
+        self.text.draw_rect(
+            Point2::new(bottom_left.x, bottom_left.y - GRAPH_MAX_HEIGHT),
+            Point2::new(bottom_left.x + width, bottom_left.y),
+            BACKGROUND_COLOR,
+        );
+
+        for (i, &ms) in self.frame_times.iter().enumerate() {
+            let height = (ms as f32 * GRAPH_SCALE).min(GRAPH_MAX_HEIGHT);
+            let x = bottom_left.x + i as f32 * GRAPH_BAR_WIDTH;
+            self.text.draw_rect(
+                Point2::new(x, bottom_left.y - height),
+                Point2::new(x + GRAPH_BAR_WIDTH, bottom_left.y),
+                frame_color(ms),
+            );
+        }
+
+        // reference line for 60 fps
+        let target_y = bottom_left.y - TARGET_FRAME_MS as f32 * GRAPH_SCALE;
+        self.text.draw_rect(
+            Point2::new(bottom_left.x, target_y),
+            Point2::new(bottom_left.x + width, target_y + 1.0),
+            Vector4::new(1.0, 1.0, 1.0, 0.5),
+        );
+    }
+}
+
+impl<'a> System<'a> for DebugOverlay {
+    type SystemData = (
+        Read<'a, res::ShowDebugOverlay>,
+        Read<'a, res::Dt>,
+        Read<'a, res::PendingChunks>,
+        Read<'a, res::SystemTimings>,
+        ReadExpect<'a, GlWindow>,
+        ReadExpect<'a, Camera>,
+        ReadExpect<'a, VoxelWorld>,
+        ReadExpect<'a, MobRegistry>,
+        Read<'a, EntityIndex>,
+        Read<'a, EventChannel<AssetChanged>>,
+        ReadStorage<'a, comp::Mob>,
+        ReadStorage<'a, comp::DroppedItem>,
+        ReadClientPlayer<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            show,
+            dt,
+            pending,
+            timings,
+            window,
+            camera,
+            world,
+            mob_registry,
+            index,
+            changes,
+            mobs,
+            dropped,
+            player,
+        ): Self::SystemData,
+    ) {
+        self.text.reload(&changes);
+        if self.frame_times.len() >= FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(dt.as_secs() * 1000.0);
+
+        if !show.0 {
+            return;
+        }
+
+        let size = window.get_inner_size().unwrap();
+        let size: (f64, f64) = size.to_physical(window.get_hidpi_factor()).into();
+        let size = (size.0 as f32, size.1 as f32);
+
+        let average_ms =
+            self.frame_times.iter().sum::<f64>() / self.frame_times.len().max(1) as f64;
+
+        let mut info = format!(
+            "{:.0} fps ({:.2} ms)\n",
+            1000.0 / average_ms.max(::std::f64::EPSILON),
+            average_ms
+        );
+
+        if let Some(tfm) = player.get_transform() {
+            let pos = WorldPos(tfm.position);
+            let block: BlockPos = pos.into();
+            let (chunk, offset) = block.chunk_pos_offset();
+            info += &format!("XYZ: {:.3} / {:.3} / {:.3}\n", pos.0.x, pos.0.y, pos.0.z);
+            info += &format!("Block: {} {} {}\n", block.0.x, block.0.y, block.0.z);
+            info += &format!(
+                "Chunk: {} {} {} (offset {} {} {})\n",
+                chunk.0.x, chunk.0.y, chunk.0.z, offset.x, offset.y, offset.z
+            );
+        }
+
+        let ray = camera.camera_ray();
+        info += &format!(
+            "Facing: {} (yaw {:.1}, pitch {:.1})\n",
+            facing(ray.direction),
+            camera.orientation.y.0,
+            camera.orientation.x.0
+        );
+
+        // the same entities as can be reached by breaking and placing blocks
+        let ignore = player.get_entity();
+        let targetable = |entity: Entity| Some(entity) != ignore && dropped.get(entity).is_none();
+        match trace(
+            &world,
+            &index,
+            ray,
+            10.0,
+            targetable,
+            &mut DebugSection::Disabled,
+        ) {
+            Some(RayHit::Block(pos, _)) => {
+                let name = world
+                    .registry(pos)
+                    .map_or("unknown".into(), |props| props.state_name());
+                info += &format!(
+                    "Looking at: {} at {} {} {}\n",
+                    name, pos.0.x, pos.0.y, pos.0.z
+                );
+            }
+            Some(RayHit::Entity(entity)) => {
+                let name = mobs
+                    .get(entity)
+                    .map_or("entity", |mob| mob_registry.get(mob.kind).name.as_str());
+                info += &format!("Looking at: {} (entity {})\n", name, entity.id());
+            }
+            None => info += "Looking at: nothing\n",
+        }
+
+        info += &format!(
+            "Chunks: {} loaded, {} queued, {} dirty",
+            world.loaded_chunk_count(),
+            pending.0,
+            world.dirty_chunk_count()
+        );
+
+        let origin = Point2::new(MARGIN, MARGIN);
+        let bottom = self.draw_panel(&info, origin);
+
+        let timings = timings.snapshot();
+        if !timings.is_empty() {
+            let mut text = String::from("System timings:");
+            for (name, ms) in timings {
+                text += &format!("\n  {}: {:.3} ms", name, ms);
+            }
+            self.draw_panel(&text, Point2::new(MARGIN, bottom + MARGIN));
+        }
+
+        self.draw_frame_graph(Point2::new(MARGIN, size.1 - MARGIN));
+        self.text.flush(size);
+    }
+}
@@ -0,0 +1,87 @@
+use engine::{
+    assets::{AssetChanged, AssetManager},
+    prelude::*,
+    render::text::{TextRenderer, LINE_HEIGHT},
+};
+use gl_api::context::Context;
+use glutin::GlWindow;
+use std::sync::Arc;
+
+const TEXT_SCALE: f32 = 2.0;
+const MARGIN: f32 = 6.0;
+/// The most lines of each problem that are shown, since compiler logs can be
+/// long.
+const MAX_LINES: usize = 6;
+
+const TEXT_COLOR: Vector4<f32> = Vector4 {
+    x: 1.0,
+    y: 0.6,
+    z: 0.6,
+    w: 1.0,
+};
+const BACKGROUND_COLOR: Vector4<f32> = Vector4 {
+    x: 0.3,
+    y: 0.0,
+    z: 0.0,
+    w: 0.7,
+};
+
+/// Draws the assets that are broken, like shaders that don't compile, along
+/// the top of the window. Each one stays there until it is fixed, so that it
+/// can be seen without opening the console.
+pub struct DrawAssetProblems {
+    assets: Arc<AssetManager>,
+    text: TextRenderer,
+}
+
+impl DrawAssetProblems {
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> Self {
+        DrawAssetProblems {
+            text: TextRenderer::new(ctx, assets.clone(), changes),
+            assets,
+        }
+    }
+}
+
+impl<'a> System<'a> for DrawAssetProblems {
+    type SystemData = (
+        ReadExpect<'a, GlWindow>,
+        Read<'a, EventChannel<AssetChanged>>,
+    );
+
+    fn run(&mut self, (window, changes): Self::SystemData) {
+        self.text.reload(&changes);
+
+        let problems = self.assets.problems();
+        if problems.is_empty() {
+            return;
+        }
+
+        let size = window.get_inner_size().unwrap();
+        let size: (f32, f32) = {
+            let size: (f64, f64) = size.to_physical(window.get_hidpi_factor()).into();
+            (size.0 as f32, size.1 as f32)
+        };
+
+        let mut text = String::new();
+        for (_, message) in problems {
+            for line in message.lines().take(MAX_LINES) {
+                text += line;
+                text.push('\n');
+            }
+        }
+        let lines = text.lines().count() as f32;
+        self.text.draw_rect(
+            Point2::new(0.0, 0.0),
+            Point2::new(size.0, lines * LINE_HEIGHT * TEXT_SCALE + 2.0 * MARGIN),
+            BACKGROUND_COLOR,
+        );
+        self.text
+            .draw_text(&text, Point2::new(MARGIN, MARGIN), TEXT_SCALE, TEXT_COLOR);
+        self.text.flush(size);
+    }
+}
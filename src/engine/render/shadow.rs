@@ -0,0 +1,268 @@
+use cgmath::PerspectiveFov;
+use engine::{
+    assets::AssetManager,
+    camera::Camera,
+    prelude::*,
+    render::{mesh::GpuMesh, terrain::BlockVertex},
+};
+use gl_api::{
+    context::{Context, ViewportRect},
+    framebuffer::Framebuffer,
+    misc,
+    shader::program::Program,
+    texture::TextureUnit,
+    texture_array::DepthTextureArray2d,
+};
+
+/// Number of shadow cascades. This has to match `CASCADES` in `terrain.fs`.
+pub const CASCADES: usize = 3;
+
+/// View-space distances at which each cascade ends. Anything past the last
+/// split is not shadowed at all.
+const CASCADE_SPLITS: [f64; CASCADES] = [16.0, 48.0, 128.0];
+
+const PREFERRED_MAP_SIZE: usize = 2048;
+const MIN_MAP_SIZE: usize = 512;
+
+/// How far behind a cascade's bounding sphere (towards the sun) we still
+/// render shadow casters, so that tall terrain outside the view can still
+/// shadow things inside of it.
+const CASTER_MARGIN: f64 = 64.0;
+
+/// The texture unit the shadow map is bound to. Unit 0 is used by the terrain
+/// texture array.
+pub const SHADOW_TEXTURE_UNIT: u32 = 1;
+
+pub struct ShadowMaps {
+    ctx: Context,
+    program: Program,
+    framebuffer: Framebuffer,
+    depth: DepthTextureArray2d,
+    light_matrices: [Matrix4<f32>; CASCADES],
+}
+
+/// Find the largest shadow map size that the implementation supports, or
+/// `None` if we can't even fit the smallest acceptable size.
+fn select_map_size(ctx: &Context) -> Option<usize> {
+    let limits = ctx.limits();
+    if limits.array_texture_layers < CASCADES {
+        return None;
+    }
+
+    let max = limits
+        .texture_size
+        .min(limits.framebuffer_width)
+        .min(limits.framebuffer_height);
+
+    let mut size = PREFERRED_MAP_SIZE;
+    while size > max && size >= MIN_MAP_SIZE {
+        size /= 2;
+    }
+
+    if size >= MIN_MAP_SIZE {
+        Some(size)
+    } else {
+        None
+    }
+}
+
+fn to_point(vec: Vector4<f64>) -> Point3<f64> {
+    Point3::new(vec.x / vec.w, vec.y / vec.w, vec.z / vec.w)
+}
+
+/// Builds the light-space matrix for the part of the view frustum between
+/// `near` and `far`. The slice is bounded by a sphere so that the size of the
+/// projection doesn't change when the camera rotates, and the projection is
+/// snapped to texel increments so shadow edges don't shimmer while moving.
+fn cascade_matrix(
+    camera: &Camera,
+    near: f64,
+    far: f64,
+    sun: Vector3<f64>,
+    map_size: usize,
+) -> Matrix4<f64> {
+    let slice: Matrix4<f64> = PerspectiveFov {
+        near,
+        far,
+        ..camera.projection
+    }
+    .into();
+    let inverse = (slice * camera.view_matrix())
+        .invert()
+        .unwrap_or(Matrix4::identity());
+
+    let mut corners = Vec::with_capacity(8);
+    for &x in &[-1.0, 1.0] {
+        for &y in &[-1.0, 1.0] {
+            for &z in &[-1.0, 1.0] {
+                corners.push(to_point(inverse * Vector4::new(x, y, z, 1.0)));
+            }
+        }
+    }
+
+    let center = corners
+        .iter()
+        .fold(Vector3::zero(), |acc, corner| acc + ::util::to_vector(*corner))
+        / corners.len() as f64;
+    let radius = corners
+        .iter()
+        .map(|corner| (::util::to_vector(*corner) - center).magnitude())
+        .fold(0.0, f64::max)
+        .ceil();
+
+    let sun = sun.normalize();
+    let up = if sun.y.abs() > 0.99 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_y()
+    };
+    let light_view = Matrix4::look_at(Point3::origin() - sun, Point3::origin(), up);
+
+    // snap the center of the projection to whole texels
+    let texel = 2.0 * radius / map_size as f64;
+    let light_center = light_view * center.extend(1.0);
+    let cx = (light_center.x / texel).floor() * texel;
+    let cy = (light_center.y / texel).floor() * texel;
+    // things in front of the light have a negative z in view space
+    let depth = -light_center.z;
+
+    let projection = ::cgmath::ortho(
+        cx - radius,
+        cx + radius,
+        cy - radius,
+        cy + radius,
+        depth - radius - CASTER_MARGIN,
+        depth + radius,
+    );
+
+    projection * light_view
+}
+
+impl ShadowMaps {
+    /// Tries to create the shadow map resources, returning `None` when the
+    /// implementation can't support them. Terrain is rendered without shadows
+    /// in that case.
+    pub fn new(ctx: &Context, assets: &AssetManager) -> Option<Self> {
+        let size = match select_map_size(ctx) {
+            Some(size) => size,
+            None => {
+                warn!(
+                    "Shadow maps disabled: need {} array layers of at least {}x{} texels",
+                    CASCADES, MIN_MAP_SIZE, MIN_MAP_SIZE
+                );
+                return None;
+            }
+        };
+
+        if size < PREFERRED_MAP_SIZE {
+            warn!(
+                "Using {}x{} shadow maps instead of {}x{} due to implementation limits",
+                size, size, PREFERRED_MAP_SIZE, PREFERRED_MAP_SIZE
+            );
+        }
+
+        let depth = DepthTextureArray2d::new(ctx, size, size, CASCADES);
+        let framebuffer = Framebuffer::new(ctx);
+        framebuffer.disable_color(ctx);
+        framebuffer.attach_depth_layer(ctx, &depth, 0);
+
+        if let Err(err) = framebuffer.check_complete(ctx) {
+            warn!("Shadow maps disabled: framebuffer incomplete ({:?})", err);
+            return None;
+        }
+
+        let program = assets.load_shader(ctx, "shaders/shadow.vs", "shaders/shadow.fs");
+
+        Some(ShadowMaps {
+            ctx: ctx.clone(),
+            program,
+            framebuffer,
+            depth,
+            light_matrices: [Matrix4::identity(); CASCADES],
+        })
+    }
+
+    /// Recompiles the shadow shader, keeping the current one if the new one
+    /// doesn't compile.
+    pub fn reload_shader(&mut self, assets: &AssetManager) -> Result<(), String> {
+        self.program =
+            assets.try_load_shader(&self.ctx, "shaders/shadow.vs", "shaders/shadow.fs")?;
+        Ok(())
+    }
+
+    /// Renders every mesh into each of the cascades. The previously bound
+    /// viewport and the default framebuffer are restored afterwards.
+    pub fn render<'m, I>(&mut self, camera: &Camera, sun: Vector3<f64>, meshes: I)
+    where
+        I: IntoIterator<Item = (&'m GpuMesh<BlockVertex, u32>, Matrix4<f32>)>,
+    {
+        let size = self.depth.width();
+        let meshes: Vec<_> = meshes.into_iter().collect();
+
+        let previous_viewport = self.ctx.viewport();
+        self.ctx.set_viewport(ViewportRect {
+            x: 0,
+            y: 0,
+            width: size as u32,
+            height: size as u32,
+        });
+
+        // push the depth values away from the light a bit so that lit surfaces
+        // don't shadow themselves
+        gl_call!(assert Enable(gl::POLYGON_OFFSET_FILL));
+        gl_call!(assert PolygonOffset(2.0, 4.0));
+
+        self.framebuffer.bind(&self.ctx);
+        let mut near = camera.projection.near;
+        for (cascade, &far) in CASCADE_SPLITS.iter().enumerate() {
+            let light_matrix = cascade_matrix(camera, near, far, sun, size)
+                .cast::<f32>()
+                .unwrap();
+            self.light_matrices[cascade] = light_matrix;
+            near = far;
+
+            self.framebuffer
+                .attach_depth_layer(&self.ctx, &self.depth, cascade);
+            misc::clear(misc::ClearMode::Depth(1.0));
+
+            self.program
+                .set_uniform(&self.ctx, "light_matrix", &light_matrix);
+            for &(mesh, model) in &meshes {
+                self.program.set_uniform(&self.ctx, "model_matrix", &model);
+                mesh.draw_with(&mut self.ctx, &self.program);
+            }
+        }
+        Framebuffer::bind_default(&self.ctx);
+
+        gl_call!(assert Disable(gl::POLYGON_OFFSET_FILL));
+        self.ctx.set_viewport(previous_viewport);
+    }
+
+    /// Sets the uniforms that `terrain.fs` needs to sample the shadow maps.
+    pub fn bind_to(&self, ctx: &Context, program: &mut Program, sun: Vector3<f64>) {
+        for cascade in 0..CASCADES {
+            program.set_uniform(
+                ctx,
+                &format!("light_matrices[{}]", cascade),
+                &self.light_matrices[cascade],
+            );
+            program.set_uniform(
+                ctx,
+                &format!("cascade_splits[{}]", cascade),
+                &(CASCADE_SPLITS[cascade] as f32),
+            );
+        }
+
+        program.set_uniform(ctx, "shadows_enabled", &1i32);
+        program.set_uniform(
+            ctx,
+            "sun_direction",
+            &sun.normalize().cast::<f32>().unwrap(),
+        );
+        program.set_uniform(
+            ctx,
+            "shadow_map",
+            &TextureUnit(SHADOW_TEXTURE_UNIT, &self.depth),
+        );
+    }
+}
@@ -1,10 +1,17 @@
-use engine::{camera::Camera, prelude::*, render::TerrainMeshes};
-use gl_api::{
-    context::Context,
-    shader::{load_shader, program::Program},
-    texture_array::TextureArray2d,
+use engine::{
+    assets::{AssetChanged, AssetManager, ChangedAssets},
+    camera::Camera,
+    console::Console,
+    prelude::*,
+    render::{
+        shadow::{ShadowMaps, SHADOW_TEXTURE_UNIT},
+        textures::{Animation, SharedTextures},
+        TerrainMeshes,
+    },
 };
+use gl_api::{context::Context, shader::program::Program};
 use glutin::GlWindow;
+use std::{cmp::Ordering, sync::Arc};
 
 vertex! {
     vertex BlockVertex {
@@ -25,59 +32,161 @@ vertex! {
     }
 }
 
+/// Width and height of a single block texture, and of each frame of an
+/// animated one.
+pub const TEXTURE_SIZE: u32 = 16;
+
+// these have to match the defines in `terrain.vs` and `water.vs`
+const MAX_ANIMATIONS: usize = 16;
+const MAX_ANIMATION_FRAMES: usize = 128;
+
+/// Uploads the animation tables that the vertex shaders use to pick the
+/// current frame of a texture from the `time` uniform.
+fn set_animation_uniforms(ctx: &Context, program: &mut Program, animations: &[Animation]) {
+    let mut layers = Vec::new();
+    let mut starts = Vec::new();
+    let mut counts = Vec::new();
+    let mut frame_ends = Vec::new();
+
+    for animation in animations {
+        let count = animation.frame_ends.len();
+        if layers.len() == MAX_ANIMATIONS || frame_ends.len() + count > MAX_ANIMATION_FRAMES {
+            warn!(
+                "Too many animated textures, layer {} will not animate",
+                animation.first_layer
+            );
+            continue;
+        }
+
+        layers.push(animation.first_layer);
+        starts.push(frame_ends.len() as i32);
+        counts.push(count as i32);
+        frame_ends.extend_from_slice(&animation.frame_ends);
+    }
+
+    program.set_uniform(ctx, "animation_count", &(layers.len() as i32));
+    if !layers.is_empty() {
+        program.set_uniform(ctx, "animation_layers", &layers[..]);
+        program.set_uniform(ctx, "animation_frames_start", &starts[..]);
+        program.set_uniform(ctx, "animation_frame_counts", &counts[..]);
+        program.set_uniform(ctx, "frame_ends", &frame_ends[..]);
+    }
+}
+
+/// Sets the uniforms of `terrain.vs` and `terrain.fs` that only change when
+/// the program or the textures are reloaded.
+fn setup_terrain_program(ctx: &Context, program: &mut Program, textures: &SharedTextures) {
+    program.set_uniform(ctx, "time", &0.0f32);
+    program.set_uniform(ctx, "ambient_light", &Vector3::<f32>::new(1.0, 1.0, 1.0));
+    program.set_uniform(ctx, "camera_position", &Vector3::new(0.0f32, 10.0, 0.0));
+    program.set_uniform(ctx, "texture_map", textures.texture_array());
+    // The shadow sampler has to point at a different texture unit than `texture_map` even
+    // when shadows are disabled, because samplers of different types can't share a unit.
+    program.set_uniform(ctx, "shadow_map", &(SHADOW_TEXTURE_UNIT as i32));
+    program.set_uniform(ctx, "shadows_enabled", &0i32);
+    program.set_uniform(ctx, "alpha_test", &1i32);
+    set_animation_uniforms(ctx, program, textures.animations());
+}
+
+fn setup_water_program(ctx: &Context, program: &mut Program, textures: &SharedTextures) {
+    program.set_uniform(ctx, "time", &0.0f32);
+    program.set_uniform(ctx, "ambient_light", &Vector3::<f32>::new(1.0, 1.0, 1.0));
+    program.set_uniform(ctx, "camera_position", &Vector3::new(0.0f32, 10.0, 0.0));
+    program.set_uniform(ctx, "texture_map", textures.texture_array());
+    set_animation_uniforms(ctx, program, textures.animations());
+}
+
 pub struct TerrainRenderer {
     ctx: Context,
+    assets: Arc<AssetManager>,
+    changes: ReaderId<AssetChanged>,
     terrain_program: Program,
     water_program: Program,
-    textures: TextureArray2d,
+    /// The version of the shared textures that the programs were set up for.
+    textures_version: u64,
+    shadows: Option<ShadowMaps>,
+    /// Seconds since the renderer was created, which drives texture animations.
+    time: f64,
 }
 
 impl TerrainRenderer {
-    pub fn new(ctx: &mut Context, names: Vec<String>) -> Self {
-        let textures = TextureArray2d::new(ctx, 16, 16, names.len());
-        let images: Result<Vec<_>, _> = names
-            .into_iter()
-            .map(|name| {
-                info!("trying to open resources/textures/{}", &name);
-                image::open(format!("resources/textures/{}", name))
-            })
-            .collect();
-        // FIXME: omg propogate this error plz
-        textures.upload_textures(ctx, images.unwrap().into_iter().map(|img| img.to_rgba()));
-        // let textures = Texture2D::new();
-        // textures
-        //     .source_from_image("resources/textures.png")
-        //     .unwrap();
-        // textures.min_filter(MinimizationFilter::Nearest);
-        // textures.mag_filter(MagnificationFilter::Nearest);
-        // textures.texture_wrap_behavior(TextureAxis::R, WrapMode::Repeat);
-        // textures.texture_wrap_behavior(TextureAxis::S, WrapMode::Repeat);
-        // textures.set_texture_bank(0);
-
-        let mut terrain_program = load_shader(
-            ctx,
-            "resources/shaders/terrain.vs",
-            "resources/shaders/terrain.fs",
-        );
-        terrain_program.set_uniform(ctx, "time", &0.0f32);
-        terrain_program.set_uniform(ctx, "ambient_light", &Vector3::<f32>::new(1.0, 1.0, 1.0));
-        terrain_program.set_uniform(ctx, "camera_position", &Vector3::new(0.0f32, 10.0, 0.0));
-        terrain_program.set_uniform(ctx, "texture_map", &textures);
-        let mut water_program = load_shader(
-            ctx,
-            "resources/shaders/water.vs",
-            "resources/shaders/water.fs",
-        );
-        water_program.set_uniform(ctx, "time", &0.0f32);
-        water_program.set_uniform(ctx, "ambient_light", &Vector3::<f32>::new(1.0, 1.0, 1.0));
-        water_program.set_uniform(ctx, "camera_position", &Vector3::new(0.0f32, 10.0, 0.0));
-        water_program.set_uniform(ctx, "texture_map", &textures);
+    pub fn new(
+        ctx: &mut Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+        textures: &SharedTextures,
+    ) -> Self {
+        let mut terrain_program =
+            assets.load_shader(ctx, "shaders/terrain.vs", "shaders/terrain.fs");
+        setup_terrain_program(ctx, &mut terrain_program, textures);
+        let shadows = ShadowMaps::new(ctx, &assets);
+
+        let mut water_program = assets.load_shader(ctx, "shaders/water.vs", "shaders/water.fs");
+        setup_water_program(ctx, &mut water_program, textures);
 
         TerrainRenderer {
             ctx: ctx.clone(),
+            assets,
+            changes: changes.register_reader(),
             terrain_program,
             water_program,
-            textures,
+            textures_version: textures.version(),
+            shadows,
+            time: 0.0,
+        }
+    }
+
+    /// Recompiles the shaders that changed, and sets the programs up again
+    /// when the textures were rebuilt. A shader that doesn't compile is
+    /// reported in the console, and the old program keeps being used until it
+    /// is fixed.
+    fn reload(
+        &mut self,
+        changed: &ChangedAssets,
+        textures: &SharedTextures,
+        console: &mut Console,
+    ) {
+        if textures.version() != self.textures_version {
+            self.textures_version = textures.version();
+            setup_terrain_program(&self.ctx, &mut self.terrain_program, textures);
+            setup_water_program(&self.ctx, &mut self.water_program, textures);
+        }
+
+        if changed.any("shaders/terrain.") {
+            match self
+                .assets
+                .try_load_shader(&self.ctx, "shaders/terrain.vs", "shaders/terrain.fs")
+            {
+                Ok(mut program) => {
+                    setup_terrain_program(&self.ctx, &mut program, textures);
+                    self.terrain_program = program;
+                    console.print("Reloaded the terrain shader");
+                }
+                Err(err) => console.print(err),
+            }
+        }
+
+        if changed.any("shaders/water.") {
+            match self
+                .assets
+                .try_load_shader(&self.ctx, "shaders/water.vs", "shaders/water.fs")
+            {
+                Ok(mut program) => {
+                    setup_water_program(&self.ctx, &mut program, textures);
+                    self.water_program = program;
+                    console.print("Reloaded the water shader");
+                }
+                Err(err) => console.print(err),
+            }
+        }
+
+        if let Some(shadows) = self.shadows.as_mut() {
+            if changed.any("shaders/shadow.") {
+                match shadows.reload_shader(&self.assets) {
+                    Ok(()) => console.print("Reloaded the shadow shader"),
+                    Err(err) => console.print(err),
+                }
+            }
         }
     }
 }
@@ -87,11 +196,30 @@ impl<'a> System<'a> for TerrainRenderer {
         WriteStorage<'a, TerrainMeshes>,
         ReadStorage<'a, comp::Transform>,
         ReadExpect<'a, Camera>,
+        Read<'a, res::SunDirection>,
+        Read<'a, res::Dt>,
+        Read<'a, EventChannel<AssetChanged>>,
+        ReadExpect<'a, SharedTextures>,
+        Write<'a, Console>,
     );
 
-    fn run(&mut self, (mut meshes, transforms, camera): Self::SystemData) {
+    fn run(
+        &mut self,
+        (mut meshes, transforms, camera, sun, dt, changes, textures, mut console): Self::SystemData,
+    ) {
         use gl_api::buffer::UsageType;
 
+        let changed = ChangedAssets::read(&changes, &mut self.changes);
+        if !changed.is_empty() || textures.version() != self.textures_version {
+            self.reload(&changed, &textures, &mut console);
+        }
+
+        self.time += dt.as_secs();
+        let time = self.time as f32;
+        self.terrain_program
+            .set_uniform(&mut self.ctx, "time", &time);
+        self.water_program.set_uniform(&mut self.ctx, "time", &time);
+
         for mesh in (&mut meshes).join() {
             if mesh.terrain.needs_new_gpu_mesh() {
                 mesh.terrain
@@ -104,6 +232,43 @@ impl<'a> System<'a> for TerrainRenderer {
                     .upload(&self.ctx, UsageType::StaticDraw)
                     .unwrap();
             }
+
+            if mesh.cutout.needs_new_gpu_mesh() {
+                mesh.cutout
+                    .upload(&self.ctx, UsageType::StaticDraw)
+                    .unwrap();
+            }
+        }
+
+        // Translucent faces have to be drawn back to front, so the quads in each
+        // chunk are re-sorted whenever the camera moves into another block.
+        for (mesh, tfm) in (&mut meshes, &transforms).join() {
+            let eye = (camera.position - tfm.position.to_vec())
+                .cast::<f32>()
+                .unwrap();
+            if mesh.sort_translucent(eye) || mesh.translucent.needs_new_gpu_mesh() {
+                mesh.translucent
+                    .upload(&self.ctx, UsageType::DynamicDraw)
+                    .unwrap();
+            }
+        }
+
+        if let Some(shadows) = self.shadows.as_mut() {
+            shadows.render(
+                &camera,
+                sun.0,
+                (&meshes, &transforms).join().flat_map(|(mesh, tfm)| {
+                    let model = tfm.model_matrix().cast::<f32>().unwrap();
+                    // cutout faces cast shadows as if they had no holes
+                    mesh.terrain
+                        .gpu_mesh
+                        .as_ref()
+                        .into_iter()
+                        .chain(mesh.cutout.gpu_mesh.as_ref())
+                        .map(move |mesh| (mesh, model))
+                }),
+            );
+            shadows.bind_to(&self.ctx, &mut self.terrain_program, sun.0);
         }
 
         let projection = camera.projection_matrix().cast::<f32>().unwrap();
@@ -122,14 +287,22 @@ impl<'a> System<'a> for TerrainRenderer {
             &camera.position.cast::<f32>().unwrap(),
         );
 
-        // Draw terrain
+        let view_matrix: Matrix4<f32> = camera.view_matrix().cast().unwrap();
+        self.terrain_program
+            .set_uniform(&mut self.ctx, "view_matrix", &view_matrix);
+        self.terrain_program
+            .set_uniform(&mut self.ctx, "alpha_test", &1i32);
+
+        // Draw terrain, then the cutout faces
         for (mesh, tfm) in (&meshes, &transforms).join() {
             let tfm: Matrix4<f32> = tfm.model_matrix().cast::<f32>().unwrap();
-            let view_matrix: Matrix4<f32> = camera.view_matrix().cast().unwrap();
 
-            if let Some(mesh) = mesh.terrain.gpu_mesh.as_ref() {
-                self.terrain_program
-                    .set_uniform(&mut self.ctx, "view_matrix", &view_matrix);
+            for mesh in mesh
+                .terrain
+                .gpu_mesh
+                .iter()
+                .chain(mesh.cutout.gpu_mesh.iter())
+            {
                 self.terrain_program
                     .set_uniform(&mut self.ctx, "model_matrix", &tfm);
                 mesh.draw_with(&mut self.ctx, &self.terrain_program);
@@ -149,5 +322,31 @@ impl<'a> System<'a> for TerrainRenderer {
                 mesh.draw_with(&mut self.ctx, &self.water_program);
             }
         }
+
+        // Draw translucent faces last, with the farthest chunks first. They don't
+        // write depth so that faces behind them in the same chunk still show up.
+        let mut translucent: Vec<_> = (&meshes, &transforms)
+            .join()
+            .filter_map(|(mesh, tfm)| {
+                let center = tfm.position + Vector3::from_value(chunk::SIZE as f64 / 2.0);
+                let distance = center.distance2(camera.position);
+                mesh.translucent
+                    .gpu_mesh
+                    .as_ref()
+                    .map(|mesh| (distance, mesh, tfm))
+            })
+            .collect();
+        translucent.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+
+        self.terrain_program
+            .set_uniform(&mut self.ctx, "alpha_test", &0i32);
+        gl_call!(assert DepthMask(gl::FALSE));
+        for (_, mesh, tfm) in translucent {
+            let tfm: Matrix4<f32> = tfm.model_matrix().cast::<f32>().unwrap();
+            self.terrain_program
+                .set_uniform(&mut self.ctx, "model_matrix", &tfm);
+            mesh.draw_with(&mut self.ctx, &self.terrain_program);
+        }
+        gl_call!(assert DepthMask(gl::TRUE));
     }
 }
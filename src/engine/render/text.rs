@@ -0,0 +1,204 @@
+use engine::{
+    assets::{AssetChanged, AssetManager, ChangedAssets},
+    prelude::*,
+};
+use gl_api::{
+    buffer::Buffer,
+    context::Context,
+    shader::program::Program,
+    texture::{MagFilter, MinFilter, Texture2d},
+    PrimitiveType, UsageType,
+};
+use std::sync::Arc;
+
+vertex! {
+    vertex TextVertex {
+        pos: Vector2<f32>,
+        uv: Vector2<f32>,
+        color: Vector4<f32>,
+    }
+}
+
+/// Size of a single cell in the font atlas, in texels.
+const CELL_SIZE: f32 = 8.0;
+const ATLAS_COLUMNS: u32 = 16;
+const ATLAS_ROWS: u32 = 6;
+/// The character in the first cell of the atlas.
+const FIRST_CHAR: u32 = 32;
+/// The atlas cell that is completely filled in, used to draw plain rectangles.
+const SOLID_CHAR: u32 = 127;
+
+/// Horizontal distance between characters, in unscaled pixels. Glyphs are only
+/// five texels wide, so we don't advance by the whole cell.
+pub const GLYPH_ADVANCE: f32 = 6.0;
+/// Vertical distance between lines, in unscaled pixels.
+pub const LINE_HEIGHT: f32 = 9.0;
+
+fn cell_uv(code: u32) -> (Vector2<f32>, Vector2<f32>) {
+    let index = code - FIRST_CHAR;
+    let (col, row) = (index % ATLAS_COLUMNS, index / ATLAS_COLUMNS);
+    let atlas_size = Vector2::new(
+        ATLAS_COLUMNS as f32 * CELL_SIZE,
+        ATLAS_ROWS as f32 * CELL_SIZE,
+    );
+
+    let min = Vector2::new(col as f32 * CELL_SIZE, row as f32 * CELL_SIZE);
+    let max = min + Vector2::new(GLYPH_ADVANCE, CELL_SIZE);
+    (
+        Vector2::new(min.x / atlas_size.x, min.y / atlas_size.y),
+        Vector2::new(max.x / atlas_size.x, max.y / atlas_size.y),
+    )
+}
+
+fn glyph_uv(ch: char) -> (Vector2<f32>, Vector2<f32>) {
+    let code = ch as u32;
+    if code >= FIRST_CHAR && code < SOLID_CHAR {
+        cell_uv(code)
+    } else {
+        cell_uv('?' as u32)
+    }
+}
+
+/// Batches text and flat rectangles into a single vertex buffer that gets
+/// drawn on top of everything else when `flush` is called. Positions are in
+/// physical pixels, with the origin in the top left of the window.
+pub struct TextRenderer {
+    ctx: Context,
+    assets: Arc<AssetManager>,
+    changes: ReaderId<AssetChanged>,
+    program: Program,
+    texture: Texture2d,
+    buffer: Buffer<TextVertex>,
+    vertices: Vec<TextVertex>,
+}
+
+fn load_font(ctx: &Context, assets: &AssetManager) -> Texture2d {
+    let texture = Texture2d::from_image(ctx, &assets.load_image_or_missing("font.png"));
+    texture.set_min_filter(ctx, MinFilter::Nearest);
+    texture.set_mag_filter(ctx, MagFilter::Nearest);
+    texture
+}
+
+impl TextRenderer {
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> Self {
+        let program = assets.load_shader(ctx, "shaders/text.vs", "shaders/text.fs");
+
+        TextRenderer {
+            ctx: ctx.clone(),
+            texture: load_font(ctx, &assets),
+            assets,
+            changes: changes.register_reader(),
+            program,
+            buffer: Buffer::new(ctx),
+            vertices: Vec::new(),
+        }
+    }
+
+    /// Reloads the font and the text shader if they changed. Whatever owns
+    /// the renderer has to call this every frame. A shader that doesn't
+    /// compile is shown in game by `DrawAssetProblems`, and the old one keeps
+    /// being used until it is fixed.
+    pub fn reload(&mut self, changes: &EventChannel<AssetChanged>) {
+        let changed = ChangedAssets::read(changes, &mut self.changes);
+        if changed.any("font.png") {
+            self.texture = load_font(&self.ctx, &self.assets);
+        }
+        if changed.any("shaders/text.") {
+            match self
+                .assets
+                .try_load_shader(&self.ctx, "shaders/text.vs", "shaders/text.fs")
+            {
+                Ok(program) => self.program = program,
+                Err(err) => warn!("{}", err),
+            }
+        }
+    }
+
+    fn push_quad(
+        &mut self,
+        min: Point2<f32>,
+        max: Point2<f32>,
+        (uv_min, uv_max): (Vector2<f32>, Vector2<f32>),
+        color: Vector4<f32>,
+    ) {
+        let vertex = |x, y, u, v| TextVertex {
+            pos: Vector2::new(x, y),
+            uv: Vector2::new(u, v),
+            color,
+        };
+
+        let top_left = vertex(min.x, min.y, uv_min.x, uv_min.y);
+        let top_right = vertex(max.x, min.y, uv_max.x, uv_min.y);
+        let bottom_left = vertex(min.x, max.y, uv_min.x, uv_max.y);
+        let bottom_right = vertex(max.x, max.y, uv_max.x, uv_max.y);
+
+        self.vertices.extend(&[
+            top_left,
+            bottom_left,
+            top_right,
+            top_right,
+            bottom_left,
+            bottom_right,
+        ]);
+    }
+
+    /// Queues `text` to be drawn with its top left corner at `pos`. Newlines
+    /// start a new line at the original x position.
+    pub fn draw_text(&mut self, text: &str, pos: Point2<f32>, scale: f32, color: Vector4<f32>) {
+        let mut cursor = pos;
+        for ch in text.chars() {
+            if ch == '\n' {
+                cursor = Point2::new(pos.x, cursor.y + LINE_HEIGHT * scale);
+                continue;
+            }
+
+            if ch != ' ' {
+                let max = cursor + scale * Vector2::new(GLYPH_ADVANCE, CELL_SIZE);
+                self.push_quad(cursor, max, glyph_uv(ch), color);
+            }
+            cursor.x += GLYPH_ADVANCE * scale;
+        }
+    }
+
+    /// Queues a flat colored rectangle.
+    pub fn draw_rect(&mut self, min: Point2<f32>, max: Point2<f32>, color: Vector4<f32>) {
+        self.push_quad(min, max, cell_uv(SOLID_CHAR), color);
+    }
+
+    /// The width of the widest line of `text`, in pixels.
+    pub fn text_width(text: &str, scale: f32) -> f32 {
+        text.lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0) as f32
+            * GLYPH_ADVANCE
+            * scale
+    }
+
+    /// Draws everything that was queued since the last flush.
+    pub fn flush(&mut self, resolution: (f32, f32)) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        self.buffer
+            .upload(&self.ctx, &self.vertices, UsageType::DynamicDraw)
+            .unwrap();
+        self.vertices.clear();
+
+        self.program
+            .set_uniform(&self.ctx, "resolution", &resolution);
+        self.program.set_uniform(&self.ctx, "font", &self.texture);
+
+        gl_call!(assert Disable(gl::DEPTH_TEST));
+        gl_call!(assert Disable(gl::CULL_FACE));
+        self.ctx
+            .draw_arrays(PrimitiveType::Triangles, &self.program, &self.buffer);
+        gl_call!(assert Enable(gl::CULL_FACE));
+        gl_call!(assert Enable(gl::DEPTH_TEST));
+    }
+}
@@ -0,0 +1,187 @@
+use engine::{
+    assets::{AssetChanged, AssetManager, ChangedAssets},
+    item::{ItemIcon, ItemId, ItemRegistry},
+    prelude::*,
+    render::terrain::TEXTURE_SIZE,
+    world::block::{BlockRegistry, TextureConfig},
+    Side,
+};
+use gl_api::{context::Context, texture_array::TextureArray2d};
+use image::RgbaImage;
+use std::sync::Arc;
+
+/// The texture array layers of an animated texture, which are cycled through
+/// by the terrain shaders.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Animation {
+    pub first_layer: i32,
+    /// When each frame stops being shown, counted from the start of the
+    /// animation.
+    pub frame_ends: Vec<f32>,
+}
+
+/// Loads every texture, splitting animated strips into their frames so that
+/// each image in the returned list is one layer of the texture array.
+/// Textures that are missing or have the wrong size are replaced with a
+/// checkerboard.
+fn load_layers(
+    assets: &AssetManager,
+    textures: &[TextureConfig],
+) -> (Vec<RgbaImage>, Vec<Animation>) {
+    let mut layers = Vec::new();
+    let mut animations = Vec::new();
+
+    for texture in textures {
+        let asset = format!("textures/{}", texture.file_name());
+        let frames = texture.frame_count() as u32;
+        let image = assets.load_texture(&asset, TEXTURE_SIZE, TEXTURE_SIZE * frames);
+
+        if let TextureConfig::Animated { frame_times, .. } = texture {
+            let mut end = 0.0;
+            animations.push(Animation {
+                first_layer: layers.len() as i32,
+                frame_ends: frame_times
+                    .iter()
+                    .map(|time| {
+                        end += time;
+                        end
+                    })
+                    .collect(),
+            });
+        }
+
+        for frame in 0..frames {
+            layers.push(RgbaImage::from_fn(TEXTURE_SIZE, TEXTURE_SIZE, |x, y| {
+                *image.get_pixel(x, y + frame * TEXTURE_SIZE)
+            }));
+        }
+    }
+
+    (layers, animations)
+}
+
+/// Every block texture followed by every item texture, in one texture array
+/// that the terrain, the hotbar and dropped items are all drawn with. It is a
+/// resource so that there is only one copy of it, which `UpdateTextures`
+/// rebuilds when the textures change.
+pub struct SharedTextures {
+    textures: TextureArray2d,
+    /// The textures in the texture array, which have to be reloaded when the
+    /// block or item registry changes.
+    block_textures: Vec<TextureConfig>,
+    item_textures: Vec<String>,
+    animations: Vec<Animation>,
+    /// The layer of the first item texture, after all the block textures.
+    first_item_layer: usize,
+    /// Goes up every time the textures are rebuilt, so that renderers know
+    /// when to set up their programs again.
+    version: u64,
+}
+
+impl SharedTextures {
+    pub fn load(
+        ctx: &Context,
+        assets: &AssetManager,
+        block_textures: &[TextureConfig],
+        item_textures: &[String],
+    ) -> Self {
+        let mut all_textures = block_textures.to_vec();
+        all_textures.extend(item_textures.iter().cloned().map(TextureConfig::Static));
+        let (layers, animations) = load_layers(assets, &all_textures);
+
+        let textures = TextureArray2d::new(
+            ctx,
+            TEXTURE_SIZE as usize,
+            TEXTURE_SIZE as usize,
+            layers.len(),
+        );
+        textures.upload_textures(ctx, layers);
+
+        SharedTextures {
+            textures,
+            block_textures: block_textures.to_vec(),
+            item_textures: item_textures.to_vec(),
+            animations,
+            first_item_layer: block_textures.iter().map(TextureConfig::frame_count).sum(),
+            version: 0,
+        }
+    }
+
+    pub fn texture_array(&self) -> &TextureArray2d {
+        &self.textures
+    }
+
+    pub fn animations(&self) -> &[Animation] {
+        &self.animations
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Whether the registries have different textures to the ones that were
+    /// loaded.
+    pub fn is_outdated(&self, blocks: &BlockRegistry, items: &ItemRegistry) -> bool {
+        blocks.textures() != &self.block_textures[..] || items.textures() != &self.item_textures[..]
+    }
+
+    /// The layer to draw one side of an item with. Items that aren't blocks
+    /// look the same from every side.
+    pub fn layer(
+        &self,
+        blocks: &BlockRegistry,
+        items: &ItemRegistry,
+        item: ItemId,
+        side: Side,
+    ) -> Option<i32> {
+        match items.icon(item) {
+            ItemIcon::Block(block) => blocks
+                .block_texture(block, side)
+                .map(|face| *face.texture.primary() as i32),
+            ItemIcon::Texture(idx) => Some((self.first_item_layer + idx) as i32),
+        }
+    }
+}
+
+/// Rebuilds the `SharedTextures` when a texture changes, or when the blocks or
+/// items are reloaded with different textures. This has to run before any of
+/// the renderers that use them.
+pub struct UpdateTextures {
+    ctx: Context,
+    assets: Arc<AssetManager>,
+    changes: ReaderId<AssetChanged>,
+}
+
+impl UpdateTextures {
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> Self {
+        UpdateTextures {
+            ctx: ctx.clone(),
+            assets,
+            changes: changes.register_reader(),
+        }
+    }
+}
+
+impl<'a> System<'a> for UpdateTextures {
+    type SystemData = (
+        ReadExpect<'a, VoxelWorld>,
+        ReadExpect<'a, ItemRegistry>,
+        Read<'a, EventChannel<AssetChanged>>,
+        WriteExpect<'a, SharedTextures>,
+    );
+
+    fn run(&mut self, (world, items, changes, mut textures): Self::SystemData) {
+        let changed = ChangedAssets::read(&changes, &mut self.changes);
+        let blocks = world.get_registry();
+        if changed.any("textures/") || textures.is_outdated(blocks, &items) {
+            let version = textures.version + 1;
+            *textures =
+                SharedTextures::load(&self.ctx, &self.assets, blocks.textures(), items.textures());
+            textures.version = version;
+        }
+    }
+}
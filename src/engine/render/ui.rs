@@ -1,12 +1,22 @@
-use engine::{prelude::*, render::verts};
+use engine::{
+    assets::{AssetChanged, AssetManager, ChangedAssets},
+    components::HOTBAR_SIZE,
+    console::Console,
+    item::ItemRegistry,
+    prelude::*,
+    render::{
+        text::{TextRenderer, LINE_HEIGHT},
+        textures::SharedTextures,
+        verts,
+    },
+    Side,
+};
 use gl_api::{
-    buffer::Buffer,
-    context::Context,
-    shader::{load_shader, program::Program},
-    texture::Texture2d,
-    PrimitiveType, UsageType,
+    buffer::Buffer, context::Context, shader::program::Program, texture::Texture2d, PrimitiveType,
+    UsageType,
 };
 use glutin::GlWindow;
+use std::sync::Arc;
 
 fn gen_quad(ctx: &Context) -> Buffer<verts::PosUv> {
     let mut buf = Buffer::new(ctx);
@@ -17,45 +27,450 @@ fn gen_quad(ctx: &Context) -> Buffer<verts::PosUv> {
 
 pub struct DrawCrosshair {
     ctx: Context,
+    assets: Arc<AssetManager>,
+    changes: ReaderId<AssetChanged>,
     texture: Texture2d,
     program: Program,
     buffer: Buffer<verts::PosUv>,
 }
 
 impl DrawCrosshair {
-    pub fn new(ctx: &Context) -> Self {
-        let texture = Texture2d::from_image(
-            ctx,
-            &::image::open("resources/crosshair.png").unwrap().to_rgba(),
-        );
-
-        let mut program = load_shader(
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> Self {
+        let texture = Texture2d::from_image(ctx, &assets.load_image_or_missing("crosshair.png"));
+        let program = assets.load_shader(
             ctx,
-            "resources/shaders/simple_texture.vs",
-            "resources/shaders/simple_texture.fs",
+            "shaders/simple_texture.vs",
+            "shaders/simple_texture.fs",
         );
-        program.set_uniform(ctx, "tex", &texture);
 
         DrawCrosshair {
             ctx: ctx.clone(),
+            assets,
+            changes: changes.register_reader(),
             texture,
             program,
             buffer: gen_quad(ctx),
         }
     }
+
+    fn reload(&mut self, changed: &ChangedAssets, console: &mut Console) {
+        if changed.any("crosshair.png") {
+            self.texture = Texture2d::from_image(
+                &self.ctx,
+                &self.assets.load_image_or_missing("crosshair.png"),
+            );
+        }
+
+        if changed.any("shaders/simple_texture.") {
+            match self.assets.try_load_shader(
+                &self.ctx,
+                "shaders/simple_texture.vs",
+                "shaders/simple_texture.fs",
+            ) {
+                Ok(program) => {
+                    self.program = program;
+                    console.print("Reloaded the crosshair shader");
+                }
+                Err(err) => console.print(err),
+            }
+        }
+    }
 }
 
 impl<'a> System<'a> for DrawCrosshair {
-    type SystemData = (ReadExpect<'a, GlWindow>);
+    type SystemData = (
+        ReadExpect<'a, GlWindow>,
+        Read<'a, EventChannel<AssetChanged>>,
+        Write<'a, Console>,
+    );
+
+    fn run(&mut self, (window, changes, mut console): Self::SystemData) {
+        let changed = ChangedAssets::read(&changes, &mut self.changes);
+        if !changed.is_empty() {
+            self.reload(&changed, &mut console);
+        }
 
-    fn run(&mut self, window: Self::SystemData) {
         let size = window.get_inner_size().unwrap();
         let size: (f64, f64) = size.to_physical(window.get_hidpi_factor()).into();
         let size = (size.0 as f32, size.1 as f32);
 
         self.program.set_uniform(&self.ctx, "resolution", &size);
+        // other UI passes bind their own textures to the same unit
+        self.program.set_uniform(&self.ctx, "tex", &self.texture);
+
+        self.ctx
+            .draw_arrays(PrimitiveType::Triangles, &self.program, &self.buffer);
+    }
+}
+
+vertex! {
+    vertex IconVertex {
+        pos: Vector2<f32>,
+        uv: Vector2<f32>,
+        layer: i32,
+    }
+}
+
+/// Size of a hotbar slot, in pixels.
+const SLOT_SIZE: f32 = 44.0;
+const ICON_SIZE: f32 = 32.0;
+const BORDER: f32 = 2.0;
+/// Distance between the hotbar and the bottom of the window.
+const MARGIN: f32 = 8.0;
+const COUNT_SCALE: f32 = 2.0;
+/// Height of the bar under a damaged item that shows how much durability it
+/// has left.
+const WEAR_BAR_HEIGHT: f32 = 3.0;
+
+const BORDER_COLOR: Vector4<f32> = Vector4 {
+    x: 0.2,
+    y: 0.2,
+    z: 0.2,
+    w: 0.8,
+};
+const SELECTED_COLOR: Vector4<f32> = Vector4 {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+    w: 1.0,
+};
+const SLOT_COLOR: Vector4<f32> = Vector4 {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+    w: 0.5,
+};
+const COUNT_COLOR: Vector4<f32> = Vector4 {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+    w: 1.0,
+};
+const WEAR_BAR_BACKGROUND: Vector4<f32> = Vector4 {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+    w: 1.0,
+};
+
+fn setup_icon_program(ctx: &Context, program: &mut Program, textures: &SharedTextures) {
+    program.set_uniform(ctx, "texture_map", textures.texture_array());
+}
+
+/// Draws the client player's hotbar along the bottom of the window, with the
+/// selected slot highlighted and the number of items in each stack.
+pub struct DrawHotbar {
+    ctx: Context,
+    assets: Arc<AssetManager>,
+    changes: ReaderId<AssetChanged>,
+    text: TextRenderer,
+    program: Program,
+    buffer: Buffer<IconVertex>,
+    vertices: Vec<IconVertex>,
+}
+
+impl DrawHotbar {
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> Self {
+        let program = assets.load_shader(ctx, "shaders/icon.vs", "shaders/icon.fs");
+
+        DrawHotbar {
+            ctx: ctx.clone(),
+            text: TextRenderer::new(ctx, assets.clone(), changes),
+            assets,
+            changes: changes.register_reader(),
+            program,
+            buffer: Buffer::new(ctx),
+            vertices: Vec::new(),
+        }
+    }
+
+    fn reload(&mut self, changed: &ChangedAssets, console: &mut Console) {
+        if changed.any("shaders/icon.") {
+            match self
+                .assets
+                .try_load_shader(&self.ctx, "shaders/icon.vs", "shaders/icon.fs")
+            {
+                Ok(program) => {
+                    self.program = program;
+                    console.print("Reloaded the icon shader");
+                }
+                Err(err) => console.print(err),
+            }
+        }
+    }
+
+    fn push_icon(&mut self, min: Point2<f32>, max: Point2<f32>, layer: i32) {
+        let vertex = |x, y, u, v| IconVertex {
+            pos: Vector2::new(x, y),
+            uv: Vector2::new(u, v),
+            layer,
+        };
+
+        let top_left = vertex(min.x, min.y, 0.0, 0.0);
+        let top_right = vertex(max.x, min.y, 1.0, 0.0);
+        let bottom_left = vertex(min.x, max.y, 0.0, 1.0);
+        let bottom_right = vertex(max.x, max.y, 1.0, 1.0);
+
+        self.vertices.extend(&[
+            top_left,
+            bottom_left,
+            top_right,
+            top_right,
+            bottom_left,
+            bottom_right,
+        ]);
+    }
 
+    fn flush_icons(&mut self, resolution: (f32, f32), textures: &SharedTextures) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        self.buffer
+            .upload(&self.ctx, &self.vertices, UsageType::DynamicDraw)
+            .unwrap();
+        self.vertices.clear();
+
+        self.program
+            .set_uniform(&self.ctx, "resolution", &resolution);
+        // other UI passes bind their own textures to the same unit
+        setup_icon_program(&self.ctx, &mut self.program, textures);
+
+        gl_call!(assert Disable(gl::DEPTH_TEST));
+        gl_call!(assert Disable(gl::CULL_FACE));
         self.ctx
             .draw_arrays(PrimitiveType::Triangles, &self.program, &self.buffer);
+        gl_call!(assert Enable(gl::CULL_FACE));
+        gl_call!(assert Enable(gl::DEPTH_TEST));
+    }
+}
+
+impl<'a> System<'a> for DrawHotbar {
+    type SystemData = (
+        ReadExpect<'a, GlWindow>,
+        ReadExpect<'a, VoxelWorld>,
+        ReadExpect<'a, ItemRegistry>,
+        ReadExpect<'a, SharedTextures>,
+        Read<'a, EventChannel<AssetChanged>>,
+        Write<'a, Console>,
+        ReadStorage<'a, comp::ClientControlled>,
+        ReadStorage<'a, comp::Player>,
+        ReadStorage<'a, comp::Inventory>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            window,
+            world,
+            items,
+            textures,
+            changes,
+            mut console,
+            client_controlled,
+            players,
+            inventories,
+        ): Self::SystemData,
+    ) {
+        let changed = ChangedAssets::read(&changes, &mut self.changes);
+        if !changed.is_empty() {
+            self.reload(&changed, &mut console);
+        }
+        self.text.reload(&changes);
+        let blocks = world.get_registry();
+
+        let inventory = match (&client_controlled, &players, &inventories).join().next() {
+            Some((_, _, inventory)) => inventory,
+            None => return,
+        };
+
+        let size = window.get_inner_size().unwrap();
+        let size: (f32, f32) = {
+            let size: (f64, f64) = size.to_physical(window.get_hidpi_factor()).into();
+            (size.0 as f32, size.1 as f32)
+        };
+
+        let left = (size.0 - HOTBAR_SIZE as f32 * SLOT_SIZE) / 2.0;
+        let top = size.1 - SLOT_SIZE - MARGIN;
+        let mut counts = Vec::new();
+        let mut wear_bars = Vec::new();
+
+        for (slot, stack) in inventory.hotbar().iter().enumerate() {
+            let min = Point2::new(left + slot as f32 * SLOT_SIZE, top);
+            let max = min + Vector2::new(SLOT_SIZE, SLOT_SIZE);
+            let border = if slot == inventory.selected() {
+                SELECTED_COLOR
+            } else {
+                BORDER_COLOR
+            };
+            self.text.draw_rect(min, max, border);
+            self.text.draw_rect(
+                min + Vector2::new(BORDER, BORDER),
+                max - Vector2::new(BORDER, BORDER),
+                SLOT_COLOR,
+            );
+
+            let stack = match stack {
+                Some(stack) => stack,
+                None => continue,
+            };
+            let inset = (SLOT_SIZE - ICON_SIZE) / 2.0;
+            if let Some(layer) = textures.layer(blocks, &items, stack.item, Side::Front) {
+                self.push_icon(
+                    min + Vector2::new(inset, inset),
+                    max - Vector2::new(inset, inset),
+                    layer,
+                );
+            }
+            if stack.count > 1 {
+                let text = stack.count.to_string();
+                let width = TextRenderer::text_width(&text, COUNT_SCALE);
+                let height = LINE_HEIGHT * COUNT_SCALE;
+                counts.push((text, max - Vector2::new(width + BORDER, height)));
+            }
+            if let (Some(durability), true) = (items.durability(stack.item), stack.damage > 0) {
+                let left = durability.saturating_sub(stack.damage) as f32 / durability as f32;
+                let bar_min = Point2::new(min.x + inset, max.y - inset);
+                wear_bars.push((bar_min, left));
+            }
+        }
+
+        // the slots go under the icons, and the counts go on top of them
+        self.text.flush(size);
+        self.flush_icons(size, &textures);
+        for (text, pos) in counts {
+            self.text.draw_text(&text, pos, COUNT_SCALE, COUNT_COLOR);
+        }
+        for (min, left) in wear_bars {
+            let max = min + Vector2::new(ICON_SIZE, WEAR_BAR_HEIGHT);
+            self.text.draw_rect(min, max, WEAR_BAR_BACKGROUND);
+            let color = Vector4::new(1.0 - left, left, 0.0, 1.0);
+            let filled = Point2::new(min.x + ICON_SIZE * left, max.y);
+            self.text.draw_rect(min, filled, color);
+        }
+        self.text.flush(size);
+    }
+}
+
+/// Size of a heart or an air bubble, in pixels.
+const STATUS_ICON_SIZE: f32 = 14.0;
+/// Space between the hearts and the bubbles, and between them and the hotbar.
+const STATUS_GAP: f32 = 4.0;
+/// Number of bubbles in the air bar.
+const AIR_BUBBLES: u32 = 10;
+
+const HEART_COLOR: Vector4<f32> = Vector4 {
+    x: 0.85,
+    y: 0.1,
+    z: 0.1,
+    w: 1.0,
+};
+const AIR_COLOR: Vector4<f32> = Vector4 {
+    x: 0.4,
+    y: 0.7,
+    z: 1.0,
+    w: 1.0,
+};
+const EMPTY_STATUS_COLOR: Vector4<f32> = Vector4 {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+    w: 0.5,
+};
+
+/// Draws the client player's hearts above the left of the hotbar, and how
+/// much air they have left above the right of it while they are under water.
+/// Nothing is drawn for players that can't be hurt.
+pub struct DrawStatusBars {
+    text: TextRenderer,
+}
+
+impl DrawStatusBars {
+    pub fn new(
+        ctx: &Context,
+        assets: Arc<AssetManager>,
+        changes: &mut EventChannel<AssetChanged>,
+    ) -> Self {
+        DrawStatusBars {
+            text: TextRenderer::new(ctx, assets, changes),
+        }
+    }
+
+    /// Draws a row of icons that are each worth 2 points, where `filled` is
+    /// how many points there are. Icons worth 1 point are half filled.
+    fn draw_row(&mut self, start: Point2<f32>, icons: u32, filled: u32, color: Vector4<f32>) {
+        for icon in 0..icons {
+            let min = start + Vector2::new(icon as f32 * (STATUS_ICON_SIZE + BORDER), 0.0);
+            let max = min + Vector2::new(STATUS_ICON_SIZE, STATUS_ICON_SIZE);
+            self.text.draw_rect(min, max, EMPTY_STATUS_COLOR);
+
+            let points = filled.saturating_sub(icon * 2).min(2);
+            if points > 0 {
+                let width = STATUS_ICON_SIZE * points as f32 / 2.0;
+                self.text
+                    .draw_rect(min, Point2::new(min.x + width, max.y), color);
+            }
+        }
+    }
+}
+
+impl<'a> System<'a> for DrawStatusBars {
+    type SystemData = (
+        ReadExpect<'a, GlWindow>,
+        ReadStorage<'a, comp::ClientControlled>,
+        ReadStorage<'a, comp::Health>,
+        ReadStorage<'a, comp::Breath>,
+        ReadStorage<'a, comp::GameMode>,
+        Read<'a, EventChannel<AssetChanged>>,
+    );
+
+    fn run(
+        &mut self,
+        (window, client_controlled, healths, breaths, modes, changes): Self::SystemData,
+    ) {
+        self.text.reload(&changes);
+        let player = (&client_controlled, &healths, breaths.maybe(), modes.maybe())
+            .join()
+            .next();
+        let (health, breath) = match player {
+            Some((_, health, breath, mode)) if mode.map_or(true, |mode| mode.takes_damage()) => {
+                (health, breath)
+            }
+            _ => return,
+        };
+
+        let size = window.get_inner_size().unwrap();
+        let size: (f32, f32) = {
+            let size: (f64, f64) = size.to_physical(window.get_hidpi_factor()).into();
+            (size.0 as f32, size.1 as f32)
+        };
+
+        let left = (size.0 - HOTBAR_SIZE as f32 * SLOT_SIZE) / 2.0;
+        let right = left + HOTBAR_SIZE as f32 * SLOT_SIZE;
+        let top = size.1 - SLOT_SIZE - MARGIN - STATUS_GAP - STATUS_ICON_SIZE;
+
+        let hearts = (health.max + 1) / 2;
+        self.draw_row(Point2::new(left, top), hearts, health.current, HEART_COLOR);
+
+        if let Some(breath) = breath.filter(|breath| breath.remaining < breath.max) {
+            let width = AIR_BUBBLES as f32 * (STATUS_ICON_SIZE + BORDER) - BORDER;
+            let filled = (breath.remaining / breath.max * (AIR_BUBBLES * 2) as f64).ceil();
+            self.draw_row(
+                Point2::new(right - width, top),
+                AIR_BUBBLES,
+                filled as u32,
+                AIR_COLOR,
+            );
+        }
+
+        self.text.flush(size);
     }
 }
@@ -0,0 +1,215 @@
+use cgmath::{Point3, Vector2};
+use engine::{
+    keybinds::ActionState,
+    world::{gen::GeneratorKind, info::WorldInfo, ChunkPos},
+};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    mem,
+    path::Path,
+    time::Duration,
+};
+
+const FORMAT_VERSION: u32 = 1;
+
+/// The first line of a recording.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    seed: u32,
+    #[serde(default)]
+    generator: GeneratorKind,
+}
+
+/// Everything that affects the world during a single tick that doesn't come
+/// from the world itself.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TickInput {
+    /// The frame time that systems saw this tick, in nanoseconds.
+    pub dt: u64,
+    pub actions: ActionState,
+    pub mouse_delta: [f64; 2],
+    /// Chunks that finished generating this tick, in the order they were
+    /// added to the world. Generation happens on worker threads, so this is
+    /// needed to make chunks show up on the same tick when replaying.
+    pub chunks: Vec<[i32; 3]>,
+}
+
+enum Mode {
+    Live,
+    Recording(BufWriter<File>),
+    Replaying(VecDeque<TickInput>),
+}
+
+/// Records input to a file, or feeds input from a file back into the game
+/// instead of the window.
+///
+/// Recordings are JSON lines: a header describing the world, followed by one
+/// `TickInput` per tick. Each tick is flushed as it is written, so a recording
+/// of a crash is still usable.
+pub struct Replay {
+    mode: Mode,
+    tick: TickInput,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Replay {
+            mode: Mode::Live,
+            tick: TickInput::default(),
+        }
+    }
+}
+
+fn duration_to_nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + duration.subsec_nanos() as u64
+}
+
+impl Replay {
+    /// Starts recording to `path`, overwriting anything that is already
+    /// there.
+    pub fn record<P: AsRef<Path>>(path: P, world: WorldInfo) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(
+            &mut writer,
+            &Header {
+                version: FORMAT_VERSION,
+                seed: world.seed,
+                generator: world.generator,
+            },
+        )?;
+        writer.write_all(b"\n")?;
+
+        Ok(Replay {
+            mode: Mode::Recording(writer),
+            tick: TickInput::default(),
+        })
+    }
+
+    /// Loads a recording, returning it along with the world it has to be
+    /// played back in for the replay to match.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<(Self, WorldInfo), Box<Error>> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let header: Header = match lines.next() {
+            Some(line) => serde_json::from_str(&line?)?,
+            None => return Err("recording is empty".into()),
+        };
+
+        if header.version != FORMAT_VERSION {
+            return Err(format!(
+                "recording has version {}, but only version {} is supported",
+                header.version, FORMAT_VERSION
+            )
+            .into());
+        }
+
+        let mut ticks = VecDeque::new();
+        for line in lines {
+            ticks.push_back(serde_json::from_str(&line?)?);
+        }
+
+        let replay = Replay {
+            mode: Mode::Replaying(ticks),
+            tick: TickInput::default(),
+        };
+        let world = WorldInfo {
+            seed: header.seed,
+            generator: header.generator,
+        };
+        Ok((replay, world))
+    }
+
+    pub fn is_recording(&self) -> bool {
+        match self.mode {
+            Mode::Recording(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether a recording or replay is running, which means the game has to
+    /// play out the same way every time.
+    pub fn is_active(&self) -> bool {
+        match self.mode {
+            Mode::Live => false,
+            _ => true,
+        }
+    }
+
+    /// The recorded input for this tick, if a replay is running.
+    pub fn current(&self) -> Option<&TickInput> {
+        match self.mode {
+            Mode::Replaying(_) => Some(&self.tick),
+            _ => None,
+        }
+    }
+
+    /// Starts a new tick, returning the frame time that systems should use.
+    /// This is `dt` unless a replay is running.
+    pub fn begin_tick(&mut self, dt: Duration) -> Duration {
+        let next = match &mut self.mode {
+            Mode::Replaying(ticks) => ticks.pop_front(),
+            _ => None,
+        };
+
+        match next {
+            Some(tick) => self.tick = tick,
+            None => {
+                if let Mode::Replaying(_) = self.mode {
+                    info!("Replay finished, switching to live input");
+                    self.mode = Mode::Live;
+                }
+
+                self.tick = TickInput {
+                    dt: duration_to_nanos(dt),
+                    ..Default::default()
+                };
+            }
+        }
+
+        Duration::from_nanos(self.tick.dt)
+    }
+
+    pub fn record_input(&mut self, actions: &ActionState, mouse_delta: Vector2<f64>) {
+        if self.is_recording() {
+            self.tick.actions = actions.clone();
+            self.tick.mouse_delta = [mouse_delta.x, mouse_delta.y];
+        }
+    }
+
+    pub fn record_chunk(&mut self, pos: ChunkPos) {
+        if self.is_recording() {
+            self.tick.chunks.push([pos.0.x, pos.0.y, pos.0.z]);
+        }
+    }
+
+    /// The chunks that have to be added to the world this tick while
+    /// replaying.
+    pub fn replayed_chunks(&self) -> Option<Vec<ChunkPos>> {
+        self.current().map(|tick| {
+            tick.chunks
+                .iter()
+                .map(|&[x, y, z]| ChunkPos(Point3::new(x, y, z)))
+                .collect()
+        })
+    }
+
+    /// Writes out the tick if we are recording.
+    pub fn end_tick(&mut self) {
+        let tick = mem::replace(&mut self.tick, TickInput::default());
+        let result = match &mut self.mode {
+            Mode::Recording(writer) => serde_json::to_writer(&mut *writer, &tick)
+                .map_err(io::Error::from)
+                .and_then(|_| writer.write_all(b"\n"))
+                .and_then(|_| writer.flush()),
+            _ => Ok(()),
+        };
+
+        if let Err(err) = result {
+            warn!("Stopped recording input: {}", err);
+            self.mode = Mode::Live;
+        }
+    }
+}
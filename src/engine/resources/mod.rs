@@ -1,6 +1,12 @@
 use cgmath::{Deg, Matrix3, Matrix4, PerspectiveFov, Point3, Vector2, Vector3};
 use collision::Ray3;
-use std::time::Duration;
+use engine::world::BlockPos;
+use rand::{rngs::SmallRng, SeedableRng};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 #[derive(Copy, Clone, Debug, PartialEq, Default)]
 pub struct CursorPos {
@@ -11,15 +17,6 @@ pub struct CursorPos {
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct StopGameLoop(pub bool);
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub struct ViewDistance(pub Vector3<i32>);
-
-impl Default for ViewDistance {
-    fn default() -> Self {
-        ViewDistance(Vector3::new(3, 3, 3))
-    }
-}
-
 #[derive(Copy, Clone, Debug, PartialEq, Default)]
 pub struct Dt(pub Duration);
 
@@ -38,3 +35,103 @@ pub struct ActiveDirections {
     pub down: bool,
     pub up: bool,
 }
+
+/// How far the mouse moved this frame, in the units glutin reports raw mouse
+/// motion in.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MouseDelta(pub Vector2<f64>);
+
+impl Default for MouseDelta {
+    fn default() -> Self {
+        MouseDelta(Vector2::new(0.0, 0.0))
+    }
+}
+
+/// Direction the sunlight travels in, used for shading and shadows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SunDirection(pub Vector3<f64>);
+
+impl SunDirection {
+    /// Where the sun is at `hour` o'clock. It rises in the +X direction at 6
+    /// and is overhead at noon, tilted a bit towards +Z so that shadows don't
+    /// line up exactly with the block grid.
+    pub fn from_time_of_day(hour: f64) -> Self {
+        let angle = (hour - 6.0) / 12.0 * ::std::f64::consts::PI;
+        SunDirection(-Vector3::new(angle.cos(), angle.sin(), -0.3))
+    }
+}
+
+impl Default for SunDirection {
+    fn default() -> Self {
+        SunDirection(Vector3::new(0.4, -1.0, 0.3))
+    }
+}
+
+/// The seed that terrain generation was started with.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct WorldSeed(pub u32);
+
+/// The random numbers that the simulation uses, like where mobs spawn and
+/// which way they wander. It is seeded from the world seed instead of the
+/// system, so that a replay plays out the same way as its recording.
+#[derive(Clone, Debug)]
+pub struct SimulationRng(pub SmallRng);
+
+impl SimulationRng {
+    pub fn new(seed: WorldSeed) -> Self {
+        let mut bytes = <SmallRng as SeedableRng>::Seed::default();
+        for (idx, byte) in bytes.as_mut().iter_mut().enumerate().take(4) {
+            *byte = (seed.0 >> (8 * idx)) as u8;
+        }
+        SimulationRng(SmallRng::from_seed(bytes))
+    }
+}
+
+/// Where players come back to life after dying.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpawnPoint(pub Point3<f64>);
+
+impl Default for SpawnPoint {
+    fn default() -> Self {
+        SpawnPoint(Point3::new(0.0, 0.0, 0.0))
+    }
+}
+
+/// Whether the F3 debug overlay is being shown.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct ShowDebugOverlay(pub bool);
+
+/// The block the client player is in the middle of breaking, and how far
+/// along they are, from 0 to 1.
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub struct BlockBreaking(pub Option<(BlockPos, f32)>);
+
+/// Number of chunks that have been requested from the terrain generator but
+/// haven't been generated yet.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct PendingChunks(pub usize);
+
+/// Average run time of each traced system, in milliseconds. The handle is
+/// shared between the system tracers and anything that wants to display the
+/// timings, so it can be updated from inside the dispatcher.
+#[derive(Clone, Debug, Default)]
+pub struct SystemTimings(Arc<Mutex<HashMap<&'static str, f64>>>);
+
+impl SystemTimings {
+    pub fn record(&self, name: &'static str, ms: f64) {
+        self.0.lock().unwrap().insert(name, ms);
+    }
+
+    /// All recorded timings, sorted by system name.
+    pub fn snapshot(&self) -> Vec<(&'static str, f64)> {
+        let mut timings: Vec<_> = self
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(&name, &ms)| (name, ms))
+            .collect();
+        timings.sort_by_key(|&(name, _)| name);
+        timings
+    }
+}
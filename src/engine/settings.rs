@@ -0,0 +1,226 @@
+use serde_json::{self, Map, Value};
+use std::{
+    error::Error,
+    fs::{self, File},
+    io,
+    path::Path,
+};
+
+macro_rules! settings {
+    ($(
+        $(#[$meta:meta])*
+        $name:ident: $ty:ty = $default:expr, $check:expr, $requirement:expr;
+    )*) => {
+        /// Tunables that are saved between runs.
+        #[derive(Clone, Debug, PartialEq, Serialize)]
+        pub struct Settings {
+            $($(#[$meta])* pub $name: $ty,)*
+        }
+
+        impl Default for Settings {
+            fn default() -> Self {
+                Settings {
+                    $($name: $default,)*
+                }
+            }
+        }
+
+        /// The names of all the settings, in the order they are declared.
+        pub const SETTING_NAMES: &[&str] = &[$(stringify!($name)),*];
+
+        impl Settings {
+            /// Sets a single setting from a JSON value, leaving it untouched if
+            /// the value has the wrong type or is out of range.
+            pub fn set_value(&mut self, name: &str, value: Value) -> Result<(), SettingError> {
+                match name {
+                    $(stringify!($name) => {
+                        let parsed: $ty = serde_json::from_value(value)
+                            .map_err(|err| SettingError::Invalid(name.into(), err.to_string()))?;
+                        if !($check)(&parsed) {
+                            return Err(SettingError::Invalid(name.into(), $requirement.into()));
+                        }
+                        self.$name = parsed;
+                        Ok(())
+                    })*
+                    _ => Err(SettingError::Unknown(name.into())),
+                }
+            }
+
+            /// The value of a setting formatted as JSON.
+            pub fn get(&self, name: &str) -> Option<String> {
+                match name {
+                    $(stringify!($name) => serde_json::to_string(&self.$name).ok(),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+pub const MIN_VIEW_DISTANCE: i32 = 1;
+pub const MAX_VIEW_DISTANCE: i32 = 32;
+
+settings! {
+    /// How many chunks are loaded in each direction around the player.
+    view_distance: i32 = 3,
+        |&v| v >= MIN_VIEW_DISTANCE && v <= MAX_VIEW_DISTANCE,
+        "must be between 1 and 32";
+    /// Vertical field of view, in degrees.
+    fov: f64 = 80.0, |&v| v >= 30.0 && v <= 120.0, "must be between 30 and 120";
+    /// Vertical field of view while zooming, in degrees.
+    zoom_fov: f64 = 20.0, |&v| v >= 5.0 && v <= 120.0, "must be between 5 and 120";
+    /// Degrees of rotation per unit of mouse movement.
+    sensitivity: f64 = 0.25, |&v| v > 0.0 && v <= 10.0, "must be between 0 and 10";
+    /// Only read at startup.
+    vsync: bool = true, |_| true, "";
+    /// Initial size of the window in logical pixels. Only read at startup.
+    window_width: u32 = 1024, |&v| v >= 320, "must be at least 320";
+    window_height: u32 = 768, |&v| v >= 240, "must be at least 240";
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SettingError {
+    Unknown(String),
+    /// The name of the setting and why the value was rejected.
+    Invalid(String, String),
+}
+
+impl ::std::fmt::Display for SettingError {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match self {
+            SettingError::Unknown(name) => write!(f, "unknown setting `{}`", name),
+            SettingError::Invalid(name, reason) => {
+                write!(f, "invalid value for `{}`: {}", name, reason)
+            }
+        }
+    }
+}
+
+impl Error for SettingError {
+    fn description(&self) -> &str {
+        match self {
+            SettingError::Unknown(_) => "unknown setting",
+            SettingError::Invalid(..) => "invalid setting value",
+        }
+    }
+}
+
+/// Whether a setting only takes effect after restarting the game.
+pub fn needs_restart(name: &str) -> bool {
+    match name {
+        "vsync" | "window_width" | "window_height" => true,
+        _ => false,
+    }
+}
+
+impl Settings {
+    /// Loads settings from a JSON object. Settings that are missing keep their
+    /// defaults, and unknown or invalid entries are skipped with a warning.
+    /// A missing file just means that all the defaults are used.
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<Error>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(Settings::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let values: Map<String, Value> = serde_json::from_reader(file)?;
+        let mut settings = Settings::default();
+        for (name, value) in values {
+            if let Err(err) = settings.set_value(&name, value) {
+                warn!("Ignoring setting: {}", err);
+            }
+        }
+
+        Ok(settings)
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Sets a setting from text typed into the console. Text that isn't valid
+    /// JSON is treated as a string.
+    pub fn set_from_str(&mut self, name: &str, value: &str) -> Result<(), SettingError> {
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.into()));
+        self.set_value(name, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(name: &str, reason: &str) -> Result<(), SettingError> {
+        Err(SettingError::Invalid(name.into(), reason.into()))
+    }
+
+    #[test]
+    fn set_value_in_range() {
+        let mut settings = Settings::default();
+        assert_eq!(
+            settings.set_value("view_distance", Value::from(MAX_VIEW_DISTANCE)),
+            Ok(())
+        );
+        assert_eq!(settings.set_value("fov", Value::from(30.0)), Ok(()));
+        assert_eq!(settings.set_value("window_width", Value::from(320)), Ok(()));
+        assert_eq!(
+            settings,
+            Settings {
+                view_distance: MAX_VIEW_DISTANCE,
+                fov: 30.0,
+                window_width: 320,
+                ..Settings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_value_out_of_range() {
+        let mut settings = Settings::default();
+        assert_eq!(
+            settings.set_value("view_distance", Value::from(MIN_VIEW_DISTANCE - 1)),
+            invalid("view_distance", "must be between 1 and 32")
+        );
+        assert_eq!(
+            settings.set_value("sensitivity", Value::from(0.0)),
+            invalid("sensitivity", "must be between 0 and 10")
+        );
+        assert_eq!(
+            settings.set_value("window_height", Value::from(100)),
+            invalid("window_height", "must be at least 240")
+        );
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn set_value_wrong_type() {
+        let mut settings = Settings::default();
+        match settings.set_value("vsync", Value::from("yes")) {
+            Err(SettingError::Invalid(name, _)) => assert_eq!(name, "vsync"),
+            other => panic!("expected an invalid value, got {:?}", other),
+        }
+        assert_eq!(
+            settings.set_value("speed", Value::from(1)),
+            Err(SettingError::Unknown("speed".into()))
+        );
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn set_from_str_falls_back_to_strings() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.set_from_str("zoom_fov", "10"), Ok(()));
+        assert!(settings.set_from_str("vsync", "maybe").is_err());
+        assert_eq!(settings.set_from_str("vsync", "false"), Ok(()));
+        assert_eq!(
+            settings,
+            Settings {
+                zoom_fov: 10.0,
+                vsync: false,
+                ..Settings::default()
+            }
+        );
+    }
+}
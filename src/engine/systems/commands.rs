@@ -0,0 +1,585 @@
+use engine::{
+    console::{parse_coordinate, CommandInfo, CommandRegistry, Console, ConsoleCommand},
+    item::ItemRegistry,
+    mob::MobRegistry,
+    prelude::*,
+    render::debug::DebugAccumulator,
+    settings::{self, Settings, SETTING_NAMES},
+    systems::{spawn_mob, ReadClientPlayer},
+};
+use std::path::PathBuf;
+
+/// The largest number of blocks `fill` will touch at once, so a typo can't
+/// freeze the game.
+const MAX_FILL_VOLUME: i64 = 32 * 32 * 32 * 4;
+
+const TP: CommandInfo = CommandInfo {
+    name: "tp",
+    usage: "tp <x> <y> <z>",
+    help: "Teleports the player. Coordinates starting with ~ are relative to the player",
+};
+const GIVE: CommandInfo = CommandInfo {
+    name: "give",
+    usage: "give <item> [count]",
+    help: "Puts items in the player's inventory",
+};
+const GAMEMODE: CommandInfo = CommandInfo {
+    name: "gamemode",
+    usage: "gamemode [survival|creative|spectator]",
+    help: "Shows or changes the player's game mode",
+};
+const SETBLOCK: CommandInfo = CommandInfo {
+    name: "setblock",
+    usage: "setblock <x> <y> <z> <block>[property=value,...]",
+    help: "Replaces a single block",
+};
+const FILL: CommandInfo = CommandInfo {
+    name: "fill",
+    usage: "fill <x1> <y1> <z1> <x2> <y2> <z2> <block>[property=value,...]",
+    help: "Replaces every block in a box",
+};
+const SUMMON: CommandInfo = CommandInfo {
+    name: "summon",
+    usage: "summon <mob> [x y z]",
+    help: "Spawns a mob, at the player unless a position is given",
+};
+const SEED: CommandInfo = CommandInfo {
+    name: "seed",
+    usage: "seed",
+    help: "Shows the seed the world was generated with",
+};
+const TIME: CommandInfo = CommandInfo {
+    name: "time",
+    usage: "time <hour|sunrise|noon|sunset|midnight>",
+    help: "Moves the sun to the given time of day, in hours",
+};
+const VIEW_DISTANCE: CommandInfo = CommandInfo {
+    name: "viewdistance",
+    usage: "viewdistance [chunks]",
+    help: "Shows or sets how many chunks are loaded around the player",
+};
+const DEBUG: CommandInfo = CommandInfo {
+    name: "debug",
+    usage: "debug <section>",
+    help: "Toggles debug drawing for a section, like `chunk grid` or `physics`",
+};
+const QUIT: CommandInfo = CommandInfo {
+    name: "quit",
+    usage: "quit",
+    help: "Exits the game",
+};
+const SET: CommandInfo = CommandInfo {
+    name: "set",
+    usage: "set <setting> <value>",
+    help: "Changes a setting. Use `settings save` to keep it for the next run",
+};
+const SETTINGS: CommandInfo = CommandInfo {
+    name: "settings",
+    usage: "settings [save|reload]",
+    help: "Lists the settings, or saves them to or reloads them from the settings file",
+};
+
+fn parse_position(args: &[String], origin: Point3<f64>) -> Option<Point3<f64>> {
+    if args.len() < 3 {
+        return None;
+    }
+
+    Some(Point3::new(
+        parse_coordinate(&args[0], origin.x)?,
+        parse_coordinate(&args[1], origin.y)?,
+        parse_coordinate(&args[2], origin.z)?,
+    ))
+}
+
+fn parse_block_pos(args: &[String], origin: Point3<f64>) -> Option<BlockPos> {
+    parse_position(args, origin).map(|pos| WorldPos(pos).into())
+}
+
+fn player_position(player: &ReadClientPlayer) -> Point3<f64> {
+    player
+        .get_transform()
+        .map_or(Point3::origin(), |tfm| tfm.position)
+}
+
+/// Commands that move the player around or change what they are carrying.
+pub struct PlayerCommands {
+    reader: ReaderId<ConsoleCommand>,
+}
+
+impl PlayerCommands {
+    pub fn new(
+        commands: &mut EventChannel<ConsoleCommand>,
+        registry: &mut CommandRegistry,
+    ) -> Self {
+        registry.register(TP);
+        registry.register(GIVE);
+        registry.register(GAMEMODE);
+        PlayerCommands {
+            reader: commands.register_reader(),
+        }
+    }
+}
+
+impl<'a> System<'a> for PlayerCommands {
+    type SystemData = (
+        Read<'a, EventChannel<ConsoleCommand>>,
+        Write<'a, Console>,
+        Entities<'a>,
+        ReadStorage<'a, comp::ClientControlled>,
+        ReadStorage<'a, comp::Player>,
+        WriteStorage<'a, comp::Transform>,
+        WriteStorage<'a, comp::RigidBody>,
+        WriteStorage<'a, comp::Airborne>,
+        WriteStorage<'a, comp::Inventory>,
+        WriteStorage<'a, comp::GameMode>,
+        WriteStorage<'a, comp::Flying>,
+        ReadExpect<'a, ItemRegistry>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            commands,
+            mut console,
+            entities,
+            client,
+            players,
+            mut transforms,
+            mut bodies,
+            mut airborne,
+            mut inventories,
+            mut modes,
+            mut flying,
+            items,
+        ): Self::SystemData,
+    ) {
+        for command in commands.read(&mut self.reader) {
+            match command.name.as_str() {
+                "tp" => {
+                    for (entity, _, _, tfm, body) in (
+                        &entities,
+                        &client,
+                        &players,
+                        &mut transforms,
+                        (&mut bodies).maybe(),
+                    )
+                        .join()
+                    {
+                        match parse_position(&command.args, tfm.position) {
+                            Some(pos) => {
+                                tfm.position = pos;
+                                if let Some(body) = body {
+                                    body.velocity = Vector3::zero();
+                                }
+                                airborne.remove(entity);
+                                console.print(format!(
+                                    "Teleported to {:.2} {:.2} {:.2}",
+                                    pos.x, pos.y, pos.z
+                                ));
+                            }
+                            None => console.print_usage(&TP),
+                        }
+                    }
+                }
+
+                "give" => {
+                    let count = match command.args.get(1) {
+                        Some(_) => command.arg::<u32>(1).filter(|&count| count > 0),
+                        None => Some(1),
+                    };
+                    let (name, count) = match (command.args.get(0), count) {
+                        (Some(name), Some(count)) => (name, count),
+                        _ => {
+                            console.print_usage(&GIVE);
+                            continue;
+                        }
+                    };
+                    let item = match items.get_id(name) {
+                        Some(item) => item,
+                        None => {
+                            console.print(format!("Unknown item `{}`", name));
+                            continue;
+                        }
+                    };
+
+                    for (_, _, inventory) in (&client, &players, &mut inventories).join() {
+                        let left = inventory.insert(&items, comp::ItemStack::new(item, count));
+                        console.print(format!("Gave {} {}", count - left, name));
+                        if left > 0 {
+                            console.print(format!("{} did not fit in the inventory", left));
+                        }
+                    }
+                }
+
+                "gamemode" => {
+                    let new_mode = match command.args.get(0) {
+                        _ if command.args.len() > 1 => {
+                            console.print_usage(&GAMEMODE);
+                            continue;
+                        }
+                        Some(arg) => match arg.parse::<comp::GameMode>() {
+                            Ok(mode) => Some(mode),
+                            Err(err) => {
+                                console.print(err);
+                                continue;
+                            }
+                        },
+                        None => None,
+                    };
+
+                    for (entity, _, _, mode) in (&entities, &client, &players, &mut modes).join() {
+                        match new_mode {
+                            Some(new_mode) => {
+                                *mode = new_mode;
+                                // only creative players choose whether to fly
+                                if new_mode != comp::GameMode::Creative {
+                                    flying.remove(entity);
+                                }
+                                console.print(format!("Game mode set to {}", new_mode.name()));
+                            }
+                            None => console.print(format!("Game mode is {}", mode.name())),
+                        }
+                    }
+                }
+
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Commands that change or inspect the blocks in the world.
+pub struct WorldCommands {
+    reader: ReaderId<ConsoleCommand>,
+}
+
+impl WorldCommands {
+    pub fn new(
+        commands: &mut EventChannel<ConsoleCommand>,
+        registry: &mut CommandRegistry,
+    ) -> Self {
+        registry.register(SETBLOCK);
+        registry.register(FILL);
+        registry.register(SUMMON);
+        registry.register(SEED);
+        WorldCommands {
+            reader: commands.register_reader(),
+        }
+    }
+}
+
+fn lookup_block(console: &mut Console, world: &VoxelWorld, name: &str) -> Option<BlockId> {
+    let id = world.get_registry().parse_state(name);
+    if id.is_none() {
+        console.print(format!("Unknown block state `{}`", name));
+    }
+    id
+}
+
+impl<'a> System<'a> for WorldCommands {
+    type SystemData = (
+        Read<'a, EventChannel<ConsoleCommand>>,
+        Write<'a, Console>,
+        WriteExpect<'a, VoxelWorld>,
+        Read<'a, res::WorldSeed>,
+        ReadClientPlayer<'a>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        ReadExpect<'a, MobRegistry>,
+    );
+
+    fn run(
+        &mut self,
+        (commands, mut console, mut world, seed, player, entities, lazy, mobs): Self::SystemData,
+    ) {
+        let origin = player_position(&player);
+
+        for command in commands.read(&mut self.reader) {
+            match command.name.as_str() {
+                "setblock" => {
+                    let pos = parse_block_pos(&command.args, origin);
+                    let (pos, name) = match (pos, command.args.get(3)) {
+                        (Some(pos), Some(name)) => (pos, name),
+                        _ => {
+                            console.print_usage(&SETBLOCK);
+                            continue;
+                        }
+                    };
+
+                    if let Some(id) = lookup_block(&mut console, &world, name) {
+                        let state = world.get_registry().state_name(id);
+                        match world.set_block_id(pos, id) {
+                            Some(_) => console.print(format!(
+                                "Set {} {} {} to {}",
+                                pos.0.x, pos.0.y, pos.0.z, state
+                            )),
+                            None => console.print("That block is not loaded"),
+                        }
+                    }
+                }
+
+                "fill" => {
+                    let start = parse_block_pos(&command.args, origin);
+                    let end = command
+                        .args
+                        .get(3..)
+                        .and_then(|args| parse_block_pos(args, origin));
+                    let (start, end, name) = match (start, end, command.args.get(6)) {
+                        (Some(start), Some(end), Some(name)) => (start, end, name),
+                        _ => {
+                            console.print_usage(&FILL);
+                            continue;
+                        }
+                    };
+
+                    let min = BlockPos(Point3::new(
+                        start.0.x.min(end.0.x),
+                        start.0.y.min(end.0.y),
+                        start.0.z.min(end.0.z),
+                    ));
+                    let max = BlockPos(Point3::new(
+                        start.0.x.max(end.0.x),
+                        start.0.y.max(end.0.y),
+                        start.0.z.max(end.0.z),
+                    ));
+                    let size = (max.0 - min.0).cast::<i64>().unwrap() + Vector3::new(1, 1, 1);
+                    let volume = size.x * size.y * size.z;
+                    if volume > MAX_FILL_VOLUME {
+                        console.print(format!(
+                            "Refusing to fill {} blocks, the limit is {}",
+                            volume, MAX_FILL_VOLUME
+                        ));
+                        continue;
+                    }
+
+                    if let Some(id) = lookup_block(&mut console, &world, name) {
+                        let mut unloaded = 0;
+                        for x in min.0.x..=max.0.x {
+                            for y in min.0.y..=max.0.y {
+                                for z in min.0.z..=max.0.z {
+                                    let pos = BlockPos(Point3::new(x, y, z));
+                                    if world.set_block_id(pos, id).is_none() {
+                                        unloaded += 1;
+                                    }
+                                }
+                            }
+                        }
+
+                        let state = world.get_registry().state_name(id);
+                        console.print(format!(
+                            "Filled {} blocks with {}",
+                            volume - unloaded,
+                            state
+                        ));
+                        if unloaded > 0 {
+                            console.print(format!("{} blocks were not loaded", unloaded));
+                        }
+                    }
+                }
+
+                "summon" => {
+                    let pos = match command.args.len() {
+                        1 => Some(origin),
+                        4 => parse_position(&command.args[1..], origin),
+                        _ => None,
+                    };
+                    let (name, pos) = match (command.args.get(0), pos) {
+                        (Some(name), Some(pos)) => (name, pos),
+                        _ => {
+                            console.print_usage(&SUMMON);
+                            continue;
+                        }
+                    };
+
+                    match mobs.get_id(name) {
+                        Some(kind) => {
+                            spawn_mob(&lazy, &entities, &mobs, kind, WorldPos(pos));
+                            console.print(format!(
+                                "Summoned a {} at {:.2} {:.2} {:.2}",
+                                name, pos.x, pos.y, pos.z
+                            ));
+                        }
+                        None => console.print(format!("Unknown mob `{}`", name)),
+                    }
+                }
+
+                "seed" => console.print(format!("Seed: {}", seed.0)),
+
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Commands for the environment and general game state.
+pub struct GameCommands {
+    reader: ReaderId<ConsoleCommand>,
+}
+
+impl GameCommands {
+    pub fn new(
+        commands: &mut EventChannel<ConsoleCommand>,
+        registry: &mut CommandRegistry,
+    ) -> Self {
+        registry.register(TIME);
+        registry.register(VIEW_DISTANCE);
+        registry.register(DEBUG);
+        registry.register(QUIT);
+        GameCommands {
+            reader: commands.register_reader(),
+        }
+    }
+}
+
+fn parse_time(arg: &str) -> Option<f64> {
+    match arg {
+        "sunrise" => Some(6.0),
+        "noon" => Some(12.0),
+        "sunset" => Some(18.0),
+        "midnight" => Some(0.0),
+        _ => arg.parse().ok(),
+    }
+}
+
+impl<'a> System<'a> for GameCommands {
+    type SystemData = (
+        Read<'a, EventChannel<ConsoleCommand>>,
+        Write<'a, Console>,
+        Write<'a, res::SunDirection>,
+        Write<'a, Settings>,
+        WriteExpect<'a, DebugAccumulator>,
+        Write<'a, res::StopGameLoop>,
+    );
+
+    fn run(
+        &mut self,
+        (commands, mut console, mut sun, mut settings, mut debug, mut stop): Self::SystemData,
+    ) {
+        for command in commands.read(&mut self.reader) {
+            match command.name.as_str() {
+                "time" => match command.args.get(0).and_then(|arg| parse_time(arg)) {
+                    Some(hour) => {
+                        *sun = res::SunDirection::from_time_of_day(hour);
+                        console.print(format!("Set the time to {:.1}h", hour % 24.0));
+                    }
+                    None => console.print_usage(&TIME),
+                },
+
+                "viewdistance" => match command.args.get(0) {
+                    Some(arg) => match settings.set_from_str("view_distance", arg) {
+                        Ok(()) => console.print(format!(
+                            "Set the view distance to {}",
+                            settings.view_distance
+                        )),
+                        Err(err) => console.print(err.to_string()),
+                    },
+                    None => console.print(format!("View distance: {}", settings.view_distance)),
+                },
+
+                "debug" => {
+                    if command.args.is_empty() {
+                        console.print_usage(&DEBUG);
+                        continue;
+                    }
+
+                    let section = command.args.join(" ");
+                    debug.toggle(section.clone());
+                    let state = if debug.is_enabled(&section) {
+                        "Enabled"
+                    } else {
+                        "Disabled"
+                    };
+                    console.print(format!("{} debug section `{}`", state, section));
+                }
+
+                "quit" => stop.0 = true,
+
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Commands for changing and saving settings.
+pub struct SettingsCommands {
+    reader: ReaderId<ConsoleCommand>,
+    path: PathBuf,
+}
+
+impl SettingsCommands {
+    /// `path` is where the settings are saved to and reloaded from.
+    pub fn new<P: Into<PathBuf>>(
+        commands: &mut EventChannel<ConsoleCommand>,
+        registry: &mut CommandRegistry,
+        path: P,
+    ) -> Self {
+        registry.register(SET);
+        registry.register(SETTINGS);
+        SettingsCommands {
+            reader: commands.register_reader(),
+            path: path.into(),
+        }
+    }
+}
+
+impl<'a> System<'a> for SettingsCommands {
+    type SystemData = (
+        Read<'a, EventChannel<ConsoleCommand>>,
+        Write<'a, Console>,
+        Write<'a, Settings>,
+    );
+
+    fn run(&mut self, (commands, mut console, mut settings): Self::SystemData) {
+        for command in commands.read(&mut self.reader) {
+            match command.name.as_str() {
+                "set" => {
+                    if command.args.len() < 2 {
+                        console.print_usage(&SET);
+                        continue;
+                    }
+
+                    let name = &command.args[0];
+                    let value = command.args[1..].join(" ");
+                    match settings.set_from_str(name, &value) {
+                        Ok(()) => {
+                            console.print(format!(
+                                "Set {} to {}",
+                                name,
+                                settings.get(name).unwrap()
+                            ));
+                            if settings::needs_restart(name) {
+                                console.print("This setting takes effect after a restart");
+                            }
+                        }
+                        Err(err) => console.print(err.to_string()),
+                    }
+                }
+
+                "settings" => match command.args.get(0).map(String::as_str) {
+                    None => {
+                        for name in SETTING_NAMES {
+                            console.print(format!("{} = {}", name, settings.get(name).unwrap()));
+                        }
+                    }
+                    Some("save") => match settings.save_to_file(&self.path) {
+                        Ok(()) => {
+                            console.print(format!("Saved settings to {}", self.path.display()))
+                        }
+                        Err(err) => console.print(format!("Could not save settings: {}", err)),
+                    },
+                    Some("reload") => match Settings::load_from_file(&self.path) {
+                        Ok(loaded) => {
+                            *settings = loaded;
+                            console
+                                .print(format!("Reloaded settings from {}", self.path.display()));
+                        }
+                        Err(err) => console.print(format!("Could not load settings: {}", err)),
+                    },
+                    Some(_) => console.print_usage(&SETTINGS),
+                },
+
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use engine::{
+    console::{CommandRegistry, Console, ConsoleCommand, BUILTIN_COMMANDS, WAIT},
+    keybinds::{Action, Keybinds},
+    prelude::*,
+};
+use glutin::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+/// Handles typing into the console and turns submitted lines into
+/// `ConsoleCommand` events for the systems that registered them.
+pub struct ConsoleSystem {
+    events_handle: ReaderId<Event>,
+    /// Set when the console is toggled, so that the character produced by the
+    /// toggle key doesn't end up in the input line.
+    swallow_char: bool,
+}
+
+impl ConsoleSystem {
+    pub fn new(event_channel: &mut EventChannel<Event>) -> Self {
+        ConsoleSystem {
+            events_handle: event_channel.register_reader(),
+            swallow_char: false,
+        }
+    }
+
+    fn handle_key(console: &mut Console, key: VirtualKeyCode) {
+        match key {
+            VirtualKeyCode::Escape => console.open = false,
+            VirtualKeyCode::Back => console.backspace(),
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => console.submit_input(),
+            VirtualKeyCode::Up => console.history_prev(),
+            VirtualKeyCode::Down => console.history_next(),
+            _ => {}
+        }
+    }
+
+    /// Runs commands that the console takes care of itself, returning `false`
+    /// if `command` is not one of them.
+    fn run_builtin(
+        console: &mut Console,
+        registry: &CommandRegistry,
+        command: &ConsoleCommand,
+    ) -> bool {
+        match command.name.as_str() {
+            "help" => match command.args.get(0) {
+                Some(name) => match BUILTIN_COMMANDS
+                    .iter()
+                    .find(|info| info.name == name)
+                    .or_else(|| registry.get(name))
+                {
+                    Some(info) => {
+                        console.print_usage(info);
+                        console.print(info.help);
+                    }
+                    None => console.print(format!("Unknown command `{}`", name)),
+                },
+                None => {
+                    for info in BUILTIN_COMMANDS.iter().chain(registry.iter()) {
+                        console.print(format!("{} - {}", info.usage, info.help));
+                    }
+                }
+            },
+            "echo" => console.print(command.args.join(" ")),
+            "clear" => console.clear_output(),
+            "wait" => match command.arg(0) {
+                Some(frames) => console.wait(frames),
+                None => console.print_usage(&WAIT),
+            },
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+impl<'a> System<'a> for ConsoleSystem {
+    type SystemData = (
+        Read<'a, EventChannel<Event>>,
+        Read<'a, Keybinds>,
+        Write<'a, Console>,
+        Read<'a, CommandRegistry>,
+        Write<'a, EventChannel<ConsoleCommand>>,
+    );
+
+    fn run(
+        &mut self,
+        (window_events, keybinds, mut console, registry, mut commands): Self::SystemData,
+    ) {
+        for event in window_events.read(&mut self.events_handle) {
+            if let Event::WindowEvent { event, .. } = event {
+                match event {
+                    WindowEvent::KeyboardInput {
+                        input:
+                            input @ KeyboardInput {
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } => {
+                        self.swallow_char = false;
+                        if keybinds.matches_key(Action::ToggleConsole, input) {
+                            console.open = !console.open;
+                            self.swallow_char = true;
+                        } else if console.open {
+                            if let Some(key) = input.virtual_keycode {
+                                Self::handle_key(&mut console, key);
+                            }
+                        }
+                    }
+
+                    WindowEvent::ReceivedCharacter(ch) => {
+                        if self.swallow_char {
+                            self.swallow_char = false;
+                        } else if console.open && !ch.is_control() {
+                            console.push_char(*ch);
+                        }
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+
+        while let Some(line) = console.next_line() {
+            let command = match ConsoleCommand::parse(&line) {
+                Some(command) => command,
+                None => continue,
+            };
+
+            if Self::run_builtin(&mut console, &registry, &command) {
+                continue;
+            }
+
+            if registry.get(&command.name).is_some() {
+                commands.single_write(command);
+            } else {
+                console.print(format!("Unknown command `{}`, try `help`", command.name));
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use engine::{console::Console, prelude::*, systems::drop_item};
+
+/// Falls from lower than this many blocks don't hurt.
+const SAFE_FALL_HEIGHT: f64 = 3.0;
+const DROWNING_DAMAGE: u32 = 2;
+const SUFFOCATION_DAMAGE: u32 = 1;
+/// How long an entity can't be hurt again for after taking damage, in
+/// seconds.
+const DAMAGE_COOLDOWN: f64 = 0.5;
+/// How fast breath comes back out of water, in seconds of air per second.
+const BREATH_RECOVERY: f64 = 5.0;
+
+fn takes_damage(mode: Option<&comp::GameMode>) -> bool {
+    mode.map_or(true, |mode| mode.takes_damage())
+}
+
+/// Hurts entities that land too hard, run out of air under water, or have a
+/// solid block in their head.
+pub struct DamageSources {
+    landings: ReaderId<comp::Landed>,
+}
+
+impl DamageSources {
+    pub fn new(landings: &mut EventChannel<comp::Landed>) -> Self {
+        DamageSources {
+            landings: landings.register_reader(),
+        }
+    }
+}
+
+impl<'a> System<'a> for DamageSources {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, comp::Transform>,
+        ReadStorage<'a, comp::Health>,
+        WriteStorage<'a, comp::Breath>,
+        ReadStorage<'a, comp::GameMode>,
+        ReadExpect<'a, VoxelWorld>,
+        Read<'a, EventChannel<comp::Landed>>,
+        Write<'a, EventChannel<comp::Damage>>,
+        Read<'a, res::Dt>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, transforms, healths, mut breaths, modes, world, landings, mut damage, dt): Self::SystemData,
+    ) {
+        for landed in landings.read(&mut self.landings) {
+            if landed.height > SAFE_FALL_HEIGHT {
+                damage.single_write(comp::Damage {
+                    entity: landed.entity,
+                    amount: (landed.height - SAFE_FALL_HEIGHT).ceil() as u32,
+                    source: comp::DamageSource::Fall,
+                });
+            }
+        }
+
+        let dt = dt.as_secs();
+        for (entity, transform, _, mode) in (&entities, &transforms, &healths, modes.maybe()).join()
+        {
+            if !takes_damage(mode) {
+                continue;
+            }
+
+            let head = world.registry(WorldPos(transform.position).into());
+            let in_liquid = head.map_or(false, |props| props.liquid());
+            let in_wall = head.map_or(false, |props| props.opaque() && props.collidable());
+
+            if let Some(breath) = breaths.get_mut(entity) {
+                if in_liquid {
+                    breath.remaining = (breath.remaining - dt).max(0.0);
+                } else {
+                    breath.remaining = (breath.remaining + BREATH_RECOVERY * dt).min(breath.max);
+                }
+
+                if breath.remaining == 0.0 {
+                    damage.single_write(comp::Damage {
+                        entity,
+                        amount: DROWNING_DAMAGE,
+                        source: comp::DamageSource::Drowning,
+                    });
+                }
+            }
+
+            if in_wall {
+                damage.single_write(comp::Damage {
+                    entity,
+                    amount: SUFFOCATION_DAMAGE,
+                    source: comp::DamageSource::Suffocation,
+                });
+            }
+        }
+    }
+}
+
+/// Takes damage away from health. Players that die drop everything they are
+/// carrying and come back at the spawn point, and anything else that dies is
+/// removed.
+pub struct ApplyDamage {
+    damage: ReaderId<comp::Damage>,
+}
+
+impl ApplyDamage {
+    pub fn new(damage: &mut EventChannel<comp::Damage>) -> Self {
+        ApplyDamage {
+            damage: damage.register_reader(),
+        }
+    }
+}
+
+impl<'a> System<'a> for ApplyDamage {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        WriteStorage<'a, comp::Health>,
+        WriteStorage<'a, comp::Breath>,
+        ReadStorage<'a, comp::GameMode>,
+        ReadStorage<'a, comp::Player>,
+        ReadStorage<'a, comp::ClientControlled>,
+        WriteStorage<'a, comp::Transform>,
+        WriteStorage<'a, comp::RigidBody>,
+        WriteStorage<'a, comp::Airborne>,
+        WriteStorage<'a, comp::Inventory>,
+        Read<'a, EventChannel<comp::Damage>>,
+        Read<'a, res::SpawnPoint>,
+        Read<'a, res::Dt>,
+        Write<'a, Console>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            lazy,
+            mut healths,
+            mut breaths,
+            modes,
+            players,
+            client_controlled,
+            mut transforms,
+            mut bodies,
+            mut airborne,
+            mut inventories,
+            damage_events,
+            spawn,
+            dt,
+            mut console,
+        ): Self::SystemData,
+    ) {
+        let dt = dt.as_secs();
+        for health in (&mut healths).join() {
+            health.cooldown = (health.cooldown - dt).max(0.0);
+        }
+
+        let mut deaths = Vec::new();
+        for damage in damage_events.read(&mut self.damage) {
+            let health = match healths.get_mut(damage.entity) {
+                Some(health) => health,
+                None => continue,
+            };
+            let vulnerable = takes_damage(modes.get(damage.entity));
+            if !vulnerable || health.cooldown > 0.0 || health.is_dead() {
+                continue;
+            }
+
+            health.current = health.current.saturating_sub(damage.amount);
+            health.cooldown = DAMAGE_COOLDOWN;
+            if health.is_dead() {
+                deaths.push((damage.entity, damage.source));
+            }
+        }
+
+        for (entity, source) in deaths {
+            if players.get(entity).is_none() {
+                let _ = entities.delete(entity);
+                continue;
+            }
+
+            if client_controlled.get(entity).is_some() {
+                console.print(format!("You {}", source.death_message()));
+            }
+            if let (Some(inventory), Some(transform)) =
+                (inventories.get_mut(entity), transforms.get(entity))
+            {
+                for stack in inventory.drain() {
+                    drop_item(&lazy, &entities, WorldPos(transform.position), stack);
+                }
+            }
+
+            if let Some(transform) = transforms.get_mut(entity) {
+                transform.position = spawn.0;
+            }
+            if let Some(body) = bodies.get_mut(entity) {
+                body.velocity = Vector3::zero();
+            }
+            // the fall so far doesn't count, since they aren't there anymore
+            airborne.remove(entity);
+            if let Some(health) = healths.get_mut(entity) {
+                *health = comp::Health::new(health.max);
+            }
+            if let Some(breath) = breaths.get_mut(entity) {
+                *breath = comp::Breath::new(breath.max);
+            }
+        }
+    }
+}
@@ -1,11 +1,18 @@
 use cgmath::Deg;
 use engine::{
     camera::Camera,
+    console::Console,
+    item::ItemRegistry,
+    keybinds::{Action, ActionState, Input, Keybinds, ScrollDirection},
     prelude::*,
     render::debug::{DebugAccumulator, Shape},
+    replay::Replay,
+    settings::{Settings, MAX_VIEW_DISTANCE, MIN_VIEW_DISTANCE},
+    systems::drop_item,
+    world::spatial::{trace, EntityIndex, RayHit},
 };
 use glutin::{
-    ElementState, Event, GlWindow, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent,
+    ElementState, Event, GlWindow, KeyboardInput, ModifiersState, MouseScrollDelta, WindowEvent,
 };
 use shrev::EventChannel;
 
@@ -35,121 +42,18 @@ fn set_wireframe(wf: bool) {
     });
 }
 
-pub enum Key {
-    Physical(u32),
-    Virtual(VirtualKeyCode),
-}
-
-impl From<VirtualKeyCode> for Key {
-    fn from(vkk: VirtualKeyCode) -> Self {
-        Key::Virtual(vkk)
-    }
-}
-
-impl From<u32> for Key {
-    fn from(sc: u32) -> Self {
-        Key::Physical(sc)
-    }
-}
-
-pub struct Keybind {
-    key: Key,
-    modifiers: Option<ModifiersState>,
-}
-
-impl Keybind {
-    pub fn new<K: Into<Key>>(key: K, modifiers: Option<ModifiersState>) -> Self {
-        Keybind {
-            key: key.into(),
-            modifiers,
-        }
-    }
-
-    pub fn matches_input(&self, input: KeyboardInput) -> bool {
-        self.modifiers
-            .map_or(true, |modifiers| input.modifiers == modifiers)
-            && match self.key {
-                Key::Virtual(vkk) => input.virtual_keycode.map_or(false, |vkk_in| vkk_in == vkk),
-                Key::Physical(scancode) => input.scancode == scancode,
-            }
-    }
-}
-
-const NO_MODIFIERS: ModifiersState = ModifiersState {
-    shift: false,
-    ctrl: false,
-    alt: false,
-    logo: false,
-};
-
-const CTRL_MODIFIERS: ModifiersState = ModifiersState {
-    shift: false,
-    ctrl: true,
-    alt: false,
-    logo: false,
-};
-
-const KEYBIND_FORWARDS: Keybind = Keybind {
-    key: Key::Physical(0x11),
-    modifiers: None,
-};
-const KEYBIND_BACKWARDS: Keybind = Keybind {
-    key: Key::Physical(0x1F),
-    modifiers: None,
-};
-const KEYBIND_LEFT: Keybind = Keybind {
-    key: Key::Physical(0x1E),
-    modifiers: None,
-};
-const KEYBIND_RIGHT: Keybind = Keybind {
-    key: Key::Physical(0x20),
-    modifiers: None,
-};
-const KEYBIND_UP: Keybind = Keybind {
-    key: Key::Physical(0x39),
-    modifiers: None,
-};
-const KEYBIND_DOWN: Keybind = Keybind {
-    key: Key::Physical(0x2A),
-    modifiers: None,
-};
-const KEYBIND_ZOOM: Keybind = Keybind {
-    key: Key::Physical(0x2E),
-    modifiers: Some(NO_MODIFIERS),
-};
-
-const KEYBIND_EXIT: Keybind = Keybind {
-    key: Key::Virtual(VirtualKeyCode::Escape),
-    modifiers: Some(NO_MODIFIERS),
-};
-const KEYBIND_DEBUG: Keybind = Keybind {
-    key: Key::Virtual(VirtualKeyCode::B),
-    modifiers: Some(CTRL_MODIFIERS),
-};
-const KEYBIND_TOGGLE_WIREFRAME: Keybind = Keybind {
-    key: Key::Virtual(VirtualKeyCode::F),
-    modifiers: Some(CTRL_MODIFIERS),
-};
-const KEYBIND_INC_RENDER_DISTANCE: Keybind = Keybind {
-    key: Key::Virtual(VirtualKeyCode::RBracket),
-    modifiers: Some(CTRL_MODIFIERS),
-};
-const KEYBIND_DEC_RENDER_DISTANCE: Keybind = Keybind {
-    key: Key::Virtual(VirtualKeyCode::LBracket),
-    modifiers: Some(CTRL_MODIFIERS),
-};
-
 use engine::components as comp;
 
 #[derive(SystemData)]
 pub struct ReadClientPlayer<'a> {
+    entities: Entities<'a>,
     client_controlled: ReadStorage<'a, comp::ClientControlled>,
     player_marker: ReadStorage<'a, comp::Player>,
     transform: ReadStorage<'a, comp::Transform>,
 }
 
 impl<'a> ReadClientPlayer<'a> {
-    fn get_transform(&self) -> Option<&comp::Transform> {
+    pub fn get_transform(&self) -> Option<&comp::Transform> {
         (
             &self.client_controlled,
             &self.player_marker,
@@ -159,31 +63,49 @@ impl<'a> ReadClientPlayer<'a> {
             .next()
             .map(|(_, _, tfm)| tfm)
     }
+
+    pub fn get_entity(&self) -> Option<Entity> {
+        (&self.entities, &self.client_controlled, &self.player_marker)
+            .join()
+            .next()
+            .map(|(entity, _, _)| entity)
+    }
 }
 
+/// Debug sections that can be toggled with a keybind.
+const DEBUG_SECTIONS: &[(Action, &str)] = &[
+    (Action::DebugChunkGrid, "chunk grid"),
+    (Action::DebugTerrainGeneration, "terrain generation"),
+    (Action::DebugMesher, "mesher"),
+    (Action::DebugPhysics, "physics"),
+    (Action::DebugInteraction, "interaction"),
+];
+
 impl<'a> System<'a> for InputHandler {
     type SystemData = (
         Read<'a, EventChannel<Event>>,
+        Read<'a, ActionState>,
         WriteStorage<'a, comp::MoveDelta>,
         Write<'a, res::StopGameLoop>,
         Write<'a, res::ActiveDirections>,
-        WriteExpect<'a, Camera>,
-        WriteExpect<'a, res::ViewDistance>,
+        Write<'a, Settings>,
         ReadClientPlayer<'a>,
         WriteExpect<'a, DebugAccumulator>,
+        Write<'a, res::ShowDebugOverlay>,
     );
 
     fn run(
         &mut self,
         (
             window_events,
+            actions,
             mut move_deltas,
             mut stop_flag,
             mut active_directions,
-            mut camera,
-            mut view_distance,
+            mut settings,
             player,
             mut debug,
+            mut show_overlay,
         ): Self::SystemData,
     ) {
         for delta in (&mut move_deltas).join() {
@@ -191,132 +113,175 @@ impl<'a> System<'a> for InputHandler {
         }
 
         for event in window_events.read(&mut self.events_handle) {
-            if let Event::WindowEvent { event, .. } = event {
-                match event {
-                    WindowEvent::CloseRequested => {
-                        stop_flag.0 = true;
-                        break;
+            if let Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } = event
+            {
+                stop_flag.0 = true;
+            }
+        }
+
+        active_directions.front = actions.is_pressed(Action::MoveForward);
+        active_directions.back = actions.is_pressed(Action::MoveBackward);
+        active_directions.left = actions.is_pressed(Action::MoveLeft);
+        active_directions.right = actions.is_pressed(Action::MoveRight);
+        active_directions.up = actions.is_pressed(Action::Jump);
+        active_directions.down = actions.is_pressed(Action::Crouch);
+
+        if actions.was_pressed(Action::Exit) {
+            stop_flag.0 = true;
+        }
+
+        if actions.was_pressed(Action::IncreaseViewDistance) {
+            settings.view_distance = (settings.view_distance + 1).min(MAX_VIEW_DISTANCE);
+        }
+        if actions.was_pressed(Action::DecreaseViewDistance) {
+            settings.view_distance = (settings.view_distance - 1).max(MIN_VIEW_DISTANCE);
+        }
+
+        for &(action, section) in DEBUG_SECTIONS {
+            if actions.was_pressed(action) {
+                debug.toggle(section.to_owned());
+            }
+        }
+
+        if actions.was_pressed(Action::PrintPosition) {
+            let tfm = player.get_transform().unwrap();
+            let bpos: BlockPos = WorldPos(tfm.position).into();
+            let (cpos, offset) = bpos.chunk_pos_offset();
+            debug!("client position: {:?}", tfm.position);
+            debug!("chunk/offset: {:?}/{:?}", cpos, offset);
+        }
+        if actions.was_pressed(Action::ToggleOverlay) {
+            show_overlay.0 = !show_overlay.0;
+        }
+        if actions.was_pressed(Action::ToggleWireframe) {
+            info!("Toggled wireframe rendering");
+            self.wireframe = !self.wireframe;
+            set_wireframe(self.wireframe);
+        }
+    }
+}
+
+/// Turns keyboard and mouse events into `ActionState` and `MouseDelta` using
+/// the player's keybinds. Nothing is pressed while the console is open, since
+/// the keys are being used for typing. While a replay is running, the recorded
+/// input is used instead of the window's.
+pub struct ActionMapper {
+    reader: ReaderId<Event>,
+    modifiers: ModifiersState,
+}
+
+impl ActionMapper {
+    pub fn new(events: &mut EventChannel<Event>) -> Self {
+        ActionMapper {
+            reader: events.register_reader(),
+            modifiers: ModifiersState::default(),
+        }
+    }
+}
+
+impl<'a> System<'a> for ActionMapper {
+    type SystemData = (
+        Read<'a, EventChannel<Event>>,
+        Read<'a, Keybinds>,
+        Read<'a, Console>,
+        Write<'a, ActionState>,
+        Write<'a, res::MouseDelta>,
+        Write<'a, Replay>,
+    );
+
+    fn run(
+        &mut self,
+        (events, keybinds, console, mut actions, mut mouse_delta, mut replay): Self::SystemData,
+    ) {
+        if let Some(tick) = replay.current() {
+            // the events still have to be read so they don't pile up
+            for _ in events.read(&mut self.reader) {}
+            *actions = tick.actions.clone();
+            mouse_delta.0 = Vector2::new(tick.mouse_delta[0], tick.mouse_delta[1]);
+            return;
+        }
+
+        actions.begin_frame();
+        mouse_delta.0 = Vector2::zero();
+        if console.open {
+            actions.release_all();
+        }
+
+        for event in events.read(&mut self.reader) {
+            match event {
+                Event::WindowEvent {
+                    event: WindowEvent::KeyboardInput { input, .. },
+                    ..
+                } => {
+                    self.modifiers = input.modifiers;
+                    if console.open && input.state == ElementState::Pressed {
+                        continue;
                     }
 
-                    WindowEvent::KeyboardInput {
-                        input:
-                            input @ KeyboardInput {
-                                state: ElementState::Pressed,
-                                ..
-                            },
-                        ..
-                    } => {
-                        if KEYBIND_FORWARDS.matches_input(*input) {
-                            active_directions.front = true;
-                        }
-                        if KEYBIND_BACKWARDS.matches_input(*input) {
-                            active_directions.back = true;
-                        }
-                        if KEYBIND_LEFT.matches_input(*input) {
-                            active_directions.left = true;
-                        }
-                        if KEYBIND_RIGHT.matches_input(*input) {
-                            active_directions.right = true;
-                        }
-                        if KEYBIND_UP.matches_input(*input) {
-                            active_directions.up = true;
-                        }
-                        if KEYBIND_DOWN.matches_input(*input) {
-                            active_directions.down = true;
-                        }
-                        if KEYBIND_ZOOM.matches_input(*input) {
-                            camera.projection.fovy = Deg(20.0).into();
-                        }
-                        if KEYBIND_EXIT.matches_input(*input) {
-                            stop_flag.0 = true;
-                            break;
-                        }
-
-                        if KEYBIND_INC_RENDER_DISTANCE.matches_input(*input) {
-                            view_distance.0 += Vector3::new(1, 1, 1);
-                        }
-                        if KEYBIND_DEC_RENDER_DISTANCE.matches_input(*input) {
-                            view_distance.0 -= Vector3::new(1, 1, 1);
-                        }
-
-                        // TODO: I don't like having arbitrary strings in my program like this,
-                        // maybe I can find a cleaner way to do debug
-                        // sections later, but it's fine for now.
-                        for &(key, section) in &[
-                            (VirtualKeyCode::C, "chunk grid"),
-                            (VirtualKeyCode::T, "terrain generation"),
-                            (VirtualKeyCode::M, "mesher"),
-                            (VirtualKeyCode::P, "physics"),
-                            (VirtualKeyCode::I, "interaction"),
-                        ] {
-                            if Keybind::new(
-                                key,
-                                Some(ModifiersState {
-                                    shift: false,
-                                    ctrl: true,
-                                    alt: false,
-                                    logo: false,
-                                }),
-                            )
-                            .matches_input(*input)
-                            {
-                                debug.toggle(section.to_owned());
-                            }
-                        }
-
-                        if KEYBIND_DEBUG.matches_input(*input) {
-                            let tfm = player.get_transform().unwrap();
-                            let bpos: BlockPos = WorldPos(tfm.position).into();
-                            let (cpos, offset) = bpos.chunk_pos_offset();
-                            debug!("client position: {:?}", tfm.position);
-                            debug!("chunk/offset: {:?}/{:?}", cpos, offset);
-                        }
-                        if KEYBIND_TOGGLE_WIREFRAME.matches_input(*input) {
-                            info!("Toggled wireframe rendering");
-                            self.wireframe = !self.wireframe;
-                            set_wireframe(self.wireframe);
-                        }
+                    let KeyboardInput {
+                        scancode,
+                        virtual_keycode,
+                        modifiers,
+                        state,
+                    } = *input;
+                    actions.handle_input(&keybinds, Input::Scancode(scancode), modifiers, state);
+                    if let Some(key) = virtual_keycode {
+                        actions.handle_input(&keybinds, Input::Key(key), modifiers, state);
                     }
+                }
 
-                    WindowEvent::KeyboardInput {
-                        input:
-                            input @ KeyboardInput {
-                                state: ElementState::Released,
-                                ..
-                            },
-                        ..
-                    } => {
-                        if KEYBIND_FORWARDS.matches_input(*input) {
-                            active_directions.front = false;
-                        }
-                        if KEYBIND_BACKWARDS.matches_input(*input) {
-                            active_directions.back = false;
-                        }
-                        if KEYBIND_LEFT.matches_input(*input) {
-                            active_directions.left = false;
-                        }
-                        if KEYBIND_RIGHT.matches_input(*input) {
-                            active_directions.right = false;
-                        }
-                        if KEYBIND_UP.matches_input(*input) {
-                            active_directions.up = false;
-                        }
-                        if KEYBIND_DOWN.matches_input(*input) {
-                            active_directions.down = false;
-                        }
-                        if KEYBIND_ZOOM.matches_input(*input) {
-                            camera.projection.fovy = Deg(80.0).into();
-                        }
+                Event::DeviceEvent {
+                    event: DeviceEvent::Button { button, state },
+                    ..
+                } => {
+                    if console.open && *state == ElementState::Pressed {
+                        continue;
                     }
+                    actions.handle_input(&keybinds, Input::Mouse(*button), self.modifiers, *state);
+                }
 
-                    // Event::Key(Key::F3, _, Action::Press, _) => { self.capture_mouse =
-                    // !self.capture_mouse; set_mouse_capture(&mut *self.window.lock().unwrap(),
-                    // self.capture_mouse) }, Event::Key(Key::RightBracket, _,
-                    // Action::Press, _) => { view_distance.0 += Vector3::new(1, 1, 1); },
-                    _ => {}
+                Event::WindowEvent {
+                    event: WindowEvent::MouseWheel { delta, .. },
+                    ..
+                } => {
+                    if console.open {
+                        continue;
+                    }
+                    let dy = match delta {
+                        MouseScrollDelta::LineDelta(_, dy) => *dy as f64,
+                        MouseScrollDelta::PixelDelta(pos) => pos.y,
+                    };
+                    let direction = if dy > 0.0 {
+                        ScrollDirection::Up
+                    } else if dy < 0.0 {
+                        ScrollDirection::Down
+                    } else {
+                        continue;
+                    };
+                    // the wheel has no held state, so each step is a whole click
+                    let input = Input::Scroll(direction);
+                    for &state in &[ElementState::Pressed, ElementState::Released] {
+                        actions.handle_input(&keybinds, input, self.modifiers, state);
+                    }
+                }
+
+                Event::DeviceEvent {
+                    event: DeviceEvent::MouseMotion { delta: (dx, dy) },
+                    ..
+                } => {
+                    if !console.open {
+                        mouse_delta.0 += Vector2::new(*dx, *dy);
+                    }
                 }
+
+                _ => {}
             }
         }
+
+        replay.record_input(&actions, mouse_delta.0);
     }
 }
 
@@ -328,38 +293,83 @@ impl<'a> System<'a> for CameraUpdater {
         WriteExpect<'a, Camera>,
         ReadExpect<'a, GlWindow>,
         ReadClientPlayer<'a>,
+        Read<'a, Settings>,
+        Read<'a, ActionState>,
     );
 
-    fn run(&mut self, (mut camera, window, player): Self::SystemData) {
+    fn run(&mut self, (mut camera, window, player, settings, actions): Self::SystemData) {
         let pos = player.get_transform().unwrap().position;
         let aspect = ::util::aspect_ratio(&window).unwrap();
 
         camera.projection.aspect = aspect;
+        camera.projection.fovy = Deg(if actions.is_pressed(Action::Zoom) {
+            settings.zoom_fov
+        } else {
+            settings.fov
+        })
+        .into();
         camera.position = pos;
     }
 }
 
-pub struct BlockInteraction {
-    reader: ReaderId<Event>,
-}
-
-impl BlockInteraction {
-    pub fn new(events: &mut EventChannel<Event>) -> Self {
-        BlockInteraction {
-            reader: events.register_reader(),
-        }
-    }
-}
+/// The actions that select each hotbar slot, in order.
+const HOTBAR_ACTIONS: &[Action] = &[
+    Action::Hotbar1,
+    Action::Hotbar2,
+    Action::Hotbar3,
+    Action::Hotbar4,
+    Action::Hotbar5,
+    Action::Hotbar6,
+    Action::Hotbar7,
+    Action::Hotbar8,
+    Action::Hotbar9,
+];
+
+/// Breaks and places blocks for the client's player, using the block in the
+/// selected hotbar slot for placing. Spectators can only change the selected
+/// slot.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub struct BlockInteraction;
 
 impl<'a> System<'a> for BlockInteraction {
     type SystemData = (
-        Read<'a, EventChannel<Event>>,
+        Read<'a, ActionState>,
         WriteExpect<'a, VoxelWorld>,
         ReadExpect<'a, Camera>,
         ReadExpect<'a, DebugAccumulator>,
+        ReadStorage<'a, comp::ClientControlled>,
+        ReadStorage<'a, comp::Player>,
+        WriteStorage<'a, comp::Inventory>,
+        ReadStorage<'a, comp::GameMode>,
+        ReadExpect<'a, ItemRegistry>,
+        Read<'a, res::Dt>,
+        Write<'a, res::BlockBreaking>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        Read<'a, EntityIndex>,
+        ReadStorage<'a, comp::DroppedItem>,
     );
 
-    fn run(&mut self, (events, mut world, camera, debug): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            actions,
+            mut world,
+            camera,
+            debug,
+            client_controlled,
+            players,
+            mut inventories,
+            game_modes,
+            items,
+            dt,
+            mut breaking,
+            entities,
+            lazy,
+            index,
+            dropped,
+        ): Self::SystemData,
+    ) {
         let mut section = debug.section("interaction");
         let ray = camera.camera_ray();
         section.draw(Shape::Ray(10.0, ray, Vector4::new(1.0, 0.0, 0.0, 1.0)));
@@ -367,79 +377,149 @@ impl<'a> System<'a> for BlockInteraction {
             section.draw(Shape::Block(3.0, block, Vector4::new(1.0, 1.0, 1.0, 1.0)));
         }
 
-        for event in events.read(&mut self.reader) {
-            match event {
-                &Event::DeviceEvent {
-                    event:
-                        DeviceEvent::Button {
-                            button,
-                            state: ElementState::Pressed,
-                        },
-                    ..
-                } => match button {
-                    1 => {
-                        if let Some((block, _)) =
-                            world.trace_block(camera.camera_ray(), 10.0, &mut section)
-                        {
-                            world.set_block_id(block, ::engine::world::block::AIR);
-                        }
-                    }
-                    3 => {
-                        if let Some((block, Some(normal))) =
-                            world.trace_block(camera.camera_ray(), 10.0, &mut section)
-                        {
-                            world.set_block_id(block.offset(normal), ::engine::world::block::STONE);
-                        }
-                    }
-                    _ => {}
-                },
+        let player = (
+            &entities,
+            &client_controlled,
+            &players,
+            &mut inventories,
+            &game_modes,
+        )
+            .join()
+            .next();
+        let (player, inventory, mode) = match player {
+            Some((entity, _, _, inventory, mode)) => (entity, inventory, *mode),
+            None => {
+                breaking.0 = None;
+                return;
+            }
+        };
 
-                _ => (),
+        for (slot, &action) in HOTBAR_ACTIONS.iter().enumerate() {
+            if actions.was_pressed(action) {
+                inventory.select(slot);
             }
         }
-    }
-}
+        if actions.was_pressed(Action::HotbarNext) {
+            inventory.scroll(1);
+        }
+        if actions.was_pressed(Action::HotbarPrevious) {
+            inventory.scroll(-1);
+        }
 
-pub struct CameraRotationUpdater {
-    reader: ReaderId<Event>,
-}
+        // entities in the way keep the blocks behind them from being reached,
+        // but dropped items are small enough to reach past
+        let targetable = |entity: Entity| entity != player && dropped.get(entity).is_none();
+        let target = match trace(&world, &index, ray, 10.0, targetable, &mut section) {
+            Some(RayHit::Block(pos, _)) => Some(pos),
+            _ => None,
+        };
+        breaking.0 = match (target, mode) {
+            (Some(_), _) if !actions.is_pressed(Action::BreakBlock) => None,
+            (None, _) | (_, comp::GameMode::Spectator) => None,
+
+            (Some(pos), comp::GameMode::Creative) => {
+                if actions.was_pressed(Action::BreakBlock) {
+                    world.set_block_id(pos, block::AIR);
+                }
+                None
+            }
 
-impl CameraRotationUpdater {
-    pub fn new(events: &mut EventChannel<Event>) -> Self {
-        CameraRotationUpdater {
-            reader: events.register_reader(),
+            (Some(pos), comp::GameMode::Survival) => {
+                // looking at a different block starts over
+                let previous = match breaking.0 {
+                    Some((old, progress)) if old == pos => progress,
+                    _ => 0.0,
+                };
+                let id = world.get_block_id(pos).unwrap_or(block::AIR);
+                let registry = world.get_registry();
+                let speed = inventory
+                    .selected_stack()
+                    .map_or(1.0, |stack| items.breaking_speed(stack.item, registry, id));
+                let time = registry.hardness(id) / speed;
+                let progress = if time > 0.0 {
+                    previous + dt.as_secs() as f32 / time
+                } else {
+                    1.0
+                };
+
+                if progress < 1.0 {
+                    Some((pos, progress))
+                } else {
+                    if let Some(item) = items.block_item(registry.default_state(id)) {
+                        let stack = comp::ItemStack::new(item, 1);
+                        drop_item(&lazy, &entities, pos.center(), stack);
+                    }
+                    // blocks that break instantly don't wear tools out
+                    if time > 0.0 {
+                        inventory.wear_selected(&items, 1);
+                    }
+                    world.set_block_id(pos, block::AIR);
+                    None
+                }
+            }
+        };
+
+        if mode.interacts() && actions.was_pressed(Action::PlaceBlock) {
+            if let Some(RayHit::Block(block, Some(normal))) =
+                trace(&world, &index, ray, 10.0, targetable, &mut section)
+            {
+                // blocks like tall grass are replaced instead of being built on
+                let target = match world.registry(block) {
+                    Some(props) if props.has_tag("replaceable") => block,
+                    _ => block.offset(normal),
+                };
+                let replaceable = world
+                    .registry(target)
+                    .map_or(false, |props| props.has_tag("replaceable"));
+
+                let placed = inventory
+                    .selected_stack()
+                    .and_then(|stack| items.block(stack.item));
+                if let (true, Some(id)) = (replaceable, placed) {
+                    // blocks with an axis, like logs, line up with the face they were placed on
+                    let axis = match (normal.x, normal.y) {
+                        (0, 0) => "z",
+                        (0, _) => "y",
+                        _ => "x",
+                    };
+                    let id = world
+                        .get_registry()
+                        .with_property(id, "axis", axis)
+                        .unwrap_or(id);
+                    world.set_block_id(target, id);
+
+                    if mode == comp::GameMode::Survival {
+                        inventory.take_selected();
+                    }
+                }
+            }
         }
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub struct CameraRotationUpdater;
+
 use glutin::DeviceEvent;
 
 impl<'a> System<'a> for CameraRotationUpdater {
-    type SystemData = (Read<'a, EventChannel<Event>>, WriteExpect<'a, Camera>);
-
-    fn run(&mut self, (events, mut camera): Self::SystemData) {
-        for event in events.read(&mut self.reader) {
-            match event {
-                &Event::DeviceEvent {
-                    event: DeviceEvent::MouseMotion { delta: (dx, dy) },
-                    ..
-                } => {
-                    let sensitivity = 0.25;
-
-                    let dx = sensitivity * dx;
-                    let dy = sensitivity * dy;
-                    // Ok, I know this looks weird, but `target` describes which *axis* should
-                    // be rotated around. It just so happens that the Y
-                    // coordinate of the mouse corresponds to a rotation around the X axis
-                    // So that's why we add the change in x to the y component of the look
-                    // target.
-                    camera.orientation.x =
-                        Deg(::util::clamp(camera.orientation.x.0 + dy, -90.0, 90.0));
-                    camera.orientation.y += Deg(dx);
-                }
+    type SystemData = (
+        Read<'a, res::MouseDelta>,
+        Read<'a, Settings>,
+        WriteExpect<'a, Camera>,
+    );
 
-                _ => (),
-            }
-        }
+    fn run(&mut self, (mouse_delta, settings, mut camera): Self::SystemData) {
+        let sensitivity = settings.sensitivity;
+
+        let dx = sensitivity * mouse_delta.0.x;
+        let dy = sensitivity * mouse_delta.0.y;
+        // Ok, I know this looks weird, but `target` describes which *axis* should
+        // be rotated around. It just so happens that the Y
+        // coordinate of the mouse corresponds to a rotation around the X axis
+        // So that's why we add the change in x to the y component of the look
+        // target.
+        camera.orientation.x = Deg(::util::clamp(camera.orientation.x.0 + dy, -90.0, 90.0));
+        camera.orientation.y += Deg(dx);
     }
 }
@@ -0,0 +1,142 @@
+use collision::{prelude::*, Aabb3};
+use engine::{
+    item::ItemRegistry, prelude::*, systems::physics::GRAVITY, world::spatial::EntityIndex,
+};
+
+/// How fast items pop out of a block when it is broken.
+const POP_SPEED: f64 = 4.0;
+/// Half the width of a dropped item's collision box.
+const ITEM_RADIUS: f64 = 0.125;
+/// How long an item lies around before it disappears, in seconds.
+const DESPAWN_TIME: f64 = 300.0;
+/// How long after being dropped an item can be picked up, so that it can be
+/// seen popping out of the block first.
+const PICKUP_DELAY: f64 = 0.5;
+/// How far outside of a player's collision box items are picked up.
+const PICKUP_REACH: f64 = 0.5;
+/// How close two stacks of the same item have to be to merge.
+const MERGE_DISTANCE: f64 = 0.75;
+
+/// Queues an entity for a stack of items lying at `pos`, which is added to
+/// the world at the end of the tick.
+pub fn drop_item(lazy: &LazyUpdate, entities: &EntitiesRes, pos: WorldPos, stack: comp::ItemStack) {
+    let radius = Vector3::new(ITEM_RADIUS, ITEM_RADIUS, ITEM_RADIUS);
+    lazy.create_entity(entities)
+        .with(comp::DroppedItem::new(stack))
+        .with(comp::Transform::default().with_position(pos.0))
+        .with(comp::RigidBody {
+            mass: 1.0,
+            drag: Vector3::new(2.0, 0.5, 2.0),
+            velocity: Vector3::new(0.0, POP_SPEED, 0.0),
+        })
+        .with(comp::Collidable {
+            aabb: Aabb3::new(Point3::from_vec(-radius), Point3::from_vec(radius)),
+        })
+        .build();
+}
+
+/// Makes dropped items fall and despawn, merges stacks of the same item that
+/// are next to each other, and moves items into the inventories of players
+/// that walk into them.
+///
+/// Items that are being deleted are left with a count of 0 until the end of
+/// the tick, so that the later steps can skip them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub struct DroppedItems;
+
+impl<'a> System<'a> for DroppedItems {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, comp::DroppedItem>,
+        WriteStorage<'a, comp::RigidBody>,
+        ReadStorage<'a, comp::Transform>,
+        ReadStorage<'a, comp::Collidable>,
+        ReadStorage<'a, comp::Player>,
+        ReadStorage<'a, comp::GameMode>,
+        WriteStorage<'a, comp::Inventory>,
+        ReadExpect<'a, ItemRegistry>,
+        Read<'a, EntityIndex>,
+        Read<'a, res::Dt>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut dropped,
+            mut bodies,
+            transforms,
+            collidables,
+            players,
+            modes,
+            mut inventories,
+            items,
+            index,
+            dt,
+        ): Self::SystemData,
+    ) {
+        let dt = dt.as_secs();
+        for (entity, item, body) in (&entities, &mut dropped, &mut bodies).join() {
+            item.age += dt;
+            body.velocity.y -= GRAVITY * dt;
+            if item.age > DESPAWN_TIME {
+                item.stack.count = 0;
+                let _ = entities.delete(entity);
+            }
+        }
+
+        let positions: Vec<_> = (&entities, &dropped, &transforms)
+            .join()
+            .filter(|(_, item, _)| item.stack.count > 0)
+            .map(|(entity, _, transform)| (entity, transform.position))
+            .collect();
+        for &(entity, pos) in &positions {
+            for (other, _) in index.within(pos, MERGE_DISTANCE) {
+                let other_stack = match dropped.get(other) {
+                    Some(item) if other != entity => item.stack,
+                    _ => continue,
+                };
+
+                let stack = dropped.get(entity).unwrap().stack;
+                let same = stack.item == other_stack.item && stack.damage == other_stack.damage;
+                let fits = stack.count + other_stack.count <= items.max_stack(stack.item);
+                if same && fits && stack.count > 0 && other_stack.count > 0 {
+                    dropped.get_mut(entity).unwrap().stack.count += other_stack.count;
+                    dropped.get_mut(other).unwrap().stack.count = 0;
+                    let _ = entities.delete(other);
+                }
+            }
+        }
+
+        let reach = Vector3::new(PICKUP_REACH, PICKUP_REACH, PICKUP_REACH);
+        for (_, transform, collidable, mode, inventory) in (
+            &players,
+            &transforms,
+            &collidables,
+            modes.maybe(),
+            &mut inventories,
+        )
+            .join()
+        {
+            if !mode.map_or(true, |mode| mode.interacts()) {
+                continue;
+            }
+            let bounds = collidable.aabb.add_v(::util::to_vector(transform.position));
+            let bounds = Aabb3::new(bounds.min - reach, bounds.max + reach);
+
+            for (entity, _) in index.intersecting(bounds) {
+                let item = match dropped.get_mut(entity) {
+                    Some(item) => item,
+                    None => continue,
+                };
+                let ready = item.stack.count > 0 && item.age >= PICKUP_DELAY;
+                if ready {
+                    item.stack.count = inventory.insert(&items, item.stack);
+                    if item.stack.count == 0 {
+                        let _ = entities.delete(entity);
+                    }
+                }
+            }
+        }
+    }
+}
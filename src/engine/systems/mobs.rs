@@ -0,0 +1,400 @@
+use cgmath::Rad;
+use collision::Aabb3;
+use engine::{
+    mob::{Behaviour, MobId, MobRegistry, SpawnRule},
+    prelude::*,
+    systems::{feet_block, physics::GRAVITY, player_controller::JUMP_SPEED},
+};
+use rand::prelude::*;
+use std::collections::HashMap;
+
+/// How quickly mobs get up to walking speed, or stop.
+const MOB_ACCELERATION: f64 = 8.0;
+const MOB_DRAG: Vector3<f64> = Vector3 {
+    x: 1.0,
+    y: 0.5,
+    z: 1.0,
+};
+/// How long a mob wanders or looks in one direction for, in seconds.
+const MIN_IDLE_TIME: f64 = 2.0;
+const MAX_IDLE_TIME: f64 = 6.0;
+/// How close a mob has to get to the middle of a block on its path before
+/// walking to the next one.
+const WAYPOINT_REACH: f64 = 0.4;
+/// How far a mob can be from the next block on its path before a new path is
+/// searched for, like after it gets pushed off of it.
+const MAX_PATH_STRAY: f64 = 2.5;
+
+/// How often mobs try to spawn, in seconds.
+const SPAWN_INTERVAL: f64 = 1.0;
+/// How many blocks above and below a player spawning looks for ground.
+const SPAWN_SEARCH_HEIGHT: i32 = 16;
+/// Mobs this far from every player are removed.
+const DESPAWN_DISTANCE: f64 = 128.0;
+/// How far up the sky is looked for when working out if a block is covered.
+const SKY_SEARCH_HEIGHT: i32 = 64;
+
+/// The direction a mob walks in when it faces `heading`.
+fn heading_vector(heading: Deg<f64>) -> Vector3<f64> {
+    Vector3::new(heading.sin(), 0.0, heading.cos())
+}
+
+/// The heading that faces along `dir`, ignoring height.
+fn heading_towards(dir: Vector3<f64>) -> Deg<f64> {
+    Rad(dir.x.atan2(dir.z)).into()
+}
+
+fn random_heading<R: Rng>(rng: &mut R) -> Deg<f64> {
+    Deg(rng.gen_range(0.0, 360.0))
+}
+
+/// Where a mob following a path should walk to next, skipping the blocks it
+/// has already reached. Returns `None` once the end of the path is reached.
+fn next_waypoint(nav: &mut comp::Navigation, pos: Point3<f64>) -> Option<Point3<f64>> {
+    let path = nav.path.clone()?;
+    while let Some(cell) = path.cells.get(nav.waypoint) {
+        let center = cell.center().0;
+        let distance = Vector2::new(center.x - pos.x, center.z - pos.z).magnitude();
+        if distance > MAX_PATH_STRAY {
+            nav.replan();
+        }
+        if distance > WAYPOINT_REACH {
+            return Some(center);
+        }
+        nav.waypoint += 1;
+    }
+    None
+}
+
+fn is_collidable(world: &VoxelWorld, pos: BlockPos) -> bool {
+    world
+        .registry(pos)
+        .map_or(false, |props| props.collidable())
+}
+
+/// How bright it is at `pos`, from 0 to 15. There are no light-giving blocks,
+/// so this is the light from the sky, and anything under cover is dark.
+fn light_level(world: &VoxelWorld, sun: &res::SunDirection, pos: BlockPos) -> u8 {
+    for dy in 1..=SKY_SEARCH_HEIGHT {
+        match world.registry(pos.offset((0, dy, 0))) {
+            Some(props) if props.opaque() => return 0,
+            Some(_) => {}
+            // nothing above has been generated yet, so it is most likely sky
+            None => break,
+        }
+    }
+
+    // sunlight travels down during the day
+    let daylight = (-sun.0.normalize().y * 2.0).max(0.0).min(1.0);
+    (daylight * 15.0).round() as u8
+}
+
+/// Queues a new mob standing at `pos`, which is added to the world at the end
+/// of the tick.
+pub fn spawn_mob(
+    lazy: &LazyUpdate,
+    entities: &EntitiesRes,
+    mobs: &MobRegistry,
+    kind: MobId,
+    pos: WorldPos,
+) {
+    let info = mobs.get(kind);
+    let half_width = info.width / 2.0;
+    let mut builder = lazy
+        .create_entity(entities)
+        .with(comp::Mob::new(kind))
+        .with(comp::Transform::default().with_position(pos.0))
+        .with(comp::RigidBody {
+            mass: 50.0,
+            drag: MOB_DRAG,
+            velocity: Vector3::zero(),
+        })
+        .with(comp::Collidable {
+            aabb: Aabb3::new(
+                Point3::new(-half_width, 0.0, -half_width),
+                Point3::new(half_width, info.height, half_width),
+            ),
+        })
+        .with(comp::Health::new(info.health))
+        .with(comp::Navigation::default());
+    if let Some(model) = info.model {
+        builder = builder.with(comp::EntityModel::new(model));
+    }
+    builder.build();
+}
+
+/// Picks what each mob does from its behaviours, and moves it by setting the
+/// velocity of its rigid body, leaving the rest to physics. Mobs following a
+/// player find their way with `Navigation`, and jump up blocks that are in
+/// their way.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct MobAi;
+
+impl<'a> System<'a> for MobAi {
+    type SystemData = (
+        WriteStorage<'a, comp::Mob>,
+        WriteStorage<'a, comp::Navigation>,
+        WriteStorage<'a, comp::Transform>,
+        WriteStorage<'a, comp::RigidBody>,
+        ReadStorage<'a, comp::Collidable>,
+        ReadStorage<'a, comp::Grounded>,
+        ReadStorage<'a, comp::Player>,
+        ReadStorage<'a, comp::GameMode>,
+        ReadExpect<'a, VoxelWorld>,
+        ReadExpect<'a, MobRegistry>,
+        Read<'a, res::Dt>,
+        WriteExpect<'a, res::SimulationRng>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut mobs,
+            mut navigations,
+            mut transforms,
+            mut bodies,
+            collidables,
+            grounded,
+            players,
+            modes,
+            world,
+            registry,
+            dt,
+            mut rng,
+        ): Self::SystemData,
+    ) {
+        let dt = dt.as_secs();
+        let rng = &mut rng.0;
+
+        // spectators can't be seen
+        let player_positions: Vec<_> = (&players, &transforms, collidables.maybe(), modes.maybe())
+            .join()
+            .filter(|(_, _, _, mode)| mode.map_or(true, |mode| mode.interacts()))
+            .map(|(_, tfm, collidable, _)| {
+                let feet = match collidable {
+                    Some(collidable) => feet_block(tfm, collidable),
+                    None => WorldPos(tfm.position).into(),
+                };
+                (tfm.position, feet)
+            })
+            .collect();
+
+        for (mob, nav, tfm, body, grounded) in (
+            &mut mobs,
+            &mut navigations,
+            &mut transforms,
+            &mut bodies,
+            grounded.maybe(),
+        )
+            .join()
+        {
+            let info = registry.get(mob.kind);
+            mob.timer -= dt;
+
+            let nearest = player_positions
+                .iter()
+                .map(|&(pos, feet)| (pos, feet, pos.distance(tfm.position)))
+                .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+            let within = |range: f64| nearest.map_or(false, |(_, _, distance)| distance < range);
+
+            let chosen = info
+                .behaviours
+                .iter()
+                .position(|behaviour| match *behaviour {
+                    Behaviour::Flee { range } => within(range),
+                    Behaviour::FollowPlayer { range, .. } => within(range),
+                    Behaviour::Wander { chance } => {
+                        let wandering = mob.behaviour.map_or(false, |idx| {
+                            info.behaviours[idx] == *behaviour && mob.timer > 0.0
+                        });
+                        wandering || rng.gen_bool((chance * dt).min(1.0))
+                    }
+                    Behaviour::LookAround => true,
+                });
+            let started = chosen != mob.behaviour;
+            mob.behaviour = chosen;
+
+            nav.goal = None;
+            match (chosen.map(|idx| &info.behaviours[idx]), nearest) {
+                (Some(Behaviour::Flee { .. }), Some((player, _, _))) => {
+                    mob.heading = heading_towards(tfm.position - player);
+                    mob.walking = true;
+                }
+                (Some(Behaviour::FollowPlayer { distance, .. }), Some((player, feet, dist))) => {
+                    nav.goal = Some(feet);
+                    // head straight for the player until there is a path
+                    let target = next_waypoint(nav, tfm.position).unwrap_or(player);
+                    mob.heading = heading_towards(target - tfm.position);
+                    mob.walking = dist > *distance;
+                }
+                (Some(Behaviour::Wander { .. }), _) => {
+                    if started {
+                        mob.heading = random_heading(rng);
+                        mob.timer = rng.gen_range(MIN_IDLE_TIME, MAX_IDLE_TIME);
+                    }
+                    mob.walking = true;
+                }
+                _ => {
+                    if started || mob.timer <= 0.0 {
+                        mob.heading = random_heading(rng);
+                        mob.timer = rng.gen_range(MIN_IDLE_TIME, MAX_IDLE_TIME);
+                    }
+                    mob.walking = false;
+                }
+            }
+
+            let target = if mob.walking {
+                heading_vector(mob.heading) * info.speed
+            } else {
+                Vector3::zero()
+            };
+            let blend = (MOB_ACCELERATION * dt).min(1.0);
+            body.velocity.x += (target.x - body.velocity.x) * blend;
+            body.velocity.z += (target.z - body.velocity.z) * blend;
+            tfm.orientation.y = mob.heading;
+
+            let feet: BlockPos = WorldPos(tfm.position).into();
+            // wait for the terrain to load instead of falling through it
+            if world.get_block_id(feet).is_some() {
+                body.velocity.y -= GRAVITY * dt;
+            }
+
+            if mob.walking && grounded.is_some() {
+                let reach = info.width / 2.0 + 0.3;
+                let ahead: BlockPos =
+                    WorldPos(tfm.position + heading_vector(mob.heading) * reach).into();
+                let blocked = is_collidable(&world, ahead);
+                let clear_above = (1..=info.height.ceil() as i32)
+                    .all(|dy| !is_collidable(&world, ahead.offset((0, dy, 0))));
+                if blocked && clear_above {
+                    body.velocity.y = JUMP_SPEED;
+                }
+            }
+        }
+    }
+}
+
+/// Finds the top of a block that `rule` lets a mob spawn on, in the column
+/// through `around` and not too far above or below it, with room for the mob
+/// above it.
+fn find_spawn_spot(
+    world: &VoxelWorld,
+    rule: &SpawnRule,
+    height: f64,
+    around: BlockPos,
+) -> Option<BlockPos> {
+    let registry = world.get_registry();
+    let room = height.ceil() as i32;
+
+    (-SPAWN_SEARCH_HEIGHT..=SPAWN_SEARCH_HEIGHT)
+        .rev()
+        .map(|dy| around.offset((0, dy, 0)))
+        .find(|&ground| {
+            let on_allowed = world.get_block_id(ground).map_or(false, |id| {
+                rule.blocks.iter().any(|name| registry.name(id) == name)
+            });
+            on_allowed
+                && (1..=room).all(|dy| match world.registry(ground.offset((0, dy, 0))) {
+                    Some(props) => !props.collidable() && !props.liquid(),
+                    None => false,
+                })
+        })
+        .map(|ground| ground.offset((0, 1, 0)))
+}
+
+/// Spawns mobs around players following the spawn rules in their mob
+/// definitions, and removes mobs that are far away from every player.
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub struct MobSpawner {
+    since_last_attempt: f64,
+}
+
+impl<'a> System<'a> for MobSpawner {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        ReadStorage<'a, comp::Mob>,
+        ReadStorage<'a, comp::Player>,
+        ReadStorage<'a, comp::Transform>,
+        ReadExpect<'a, VoxelWorld>,
+        ReadExpect<'a, MobRegistry>,
+        Read<'a, res::SunDirection>,
+        Read<'a, res::Dt>,
+        WriteExpect<'a, res::SimulationRng>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            lazy,
+            mobs,
+            players,
+            transforms,
+            world,
+            registry,
+            sun,
+            dt,
+            mut rng,
+        ): Self::SystemData,
+    ) {
+        self.since_last_attempt += dt.as_secs();
+        if self.since_last_attempt < SPAWN_INTERVAL {
+            return;
+        }
+        self.since_last_attempt = 0.0;
+
+        let player_positions: Vec<_> = (&players, &transforms)
+            .join()
+            .map(|(_, tfm)| tfm.position)
+            .collect();
+        if player_positions.is_empty() {
+            return;
+        }
+        let closest_player = |pos: Point3<f64>| {
+            player_positions
+                .iter()
+                .map(|player| player.distance(pos))
+                .fold(::std::f64::INFINITY, f64::min)
+        };
+
+        let mut counts = HashMap::new();
+        for (entity, mob, tfm) in (&entities, &mobs, &transforms).join() {
+            if closest_player(tfm.position) > DESPAWN_DISTANCE {
+                let _ = entities.delete(entity);
+            } else {
+                *counts.entry(mob.kind).or_insert(0) += 1;
+            }
+        }
+
+        let rng = &mut rng.0;
+        for kind in registry.mobs() {
+            let info = registry.get(kind);
+            let rule = match &info.spawn {
+                Some(rule) => rule,
+                None => continue,
+            };
+            if counts.get(&kind).cloned().unwrap_or(0) >= rule.max_count {
+                continue;
+            }
+
+            let player = *player_positions.choose(rng).unwrap();
+            let angle = rng.gen_range(0.0, 2.0 * ::std::f64::consts::PI);
+            let distance =
+                rule.min_distance + rng.gen::<f64>() * (rule.max_distance - rule.min_distance);
+            let column = player + Vector3::new(angle.cos(), 0.0, angle.sin()) * distance;
+            let column: BlockPos = WorldPos(column).into();
+
+            let spot = match find_spawn_spot(&world, rule, info.height, column) {
+                Some(spot) => spot,
+                None => continue,
+            };
+            let light = light_level(&world, &sun, spot);
+            let feet = spot.base().0 + Vector3::new(0.5, 0.0, 0.5);
+            let far_enough = closest_player(feet) >= rule.min_distance;
+            if far_enough && light >= rule.min_light && light <= rule.max_light {
+                spawn_mob(&lazy, &entities, &registry, kind, WorldPos(feet));
+            }
+        }
+    }
+}
@@ -1,9 +1,26 @@
+mod commands;
+mod console;
+mod health;
 mod input;
+mod items;
+mod mobs;
+mod pathfinding;
 mod physics;
 mod player_controller;
+mod reload;
 
 pub use self::{
-    input::{BlockInteraction, CameraRotationUpdater, CameraUpdater, InputHandler},
+    commands::{GameCommands, PlayerCommands, SettingsCommands, WorldCommands},
+    console::ConsoleSystem,
+    health::{ApplyDamage, DamageSources},
+    input::{
+        ActionMapper, BlockInteraction, CameraRotationUpdater, CameraUpdater, InputHandler,
+        ReadClientPlayer,
+    },
+    items::{drop_item, DroppedItems},
+    mobs::{spawn_mob, MobAi, MobSpawner},
+    pathfinding::{feet_block, Pathfinding},
     physics::Physics,
     player_controller::PlayerController,
+    reload::{AssetWatcher, BlockReloader},
 };
@@ -0,0 +1,281 @@
+use engine::{
+    job::Worker,
+    prelude::*,
+    replay::Replay,
+    world::path::{NavGrid, Path, PathLimits, PathRequest, Pathfinder},
+};
+use std::{collections::HashMap, mem, sync::Arc};
+
+/// Extra room around the start and goal that a search can use to get around
+/// things, in blocks.
+const SEARCH_MARGIN: i32 = 8;
+/// Goals further away than this are never searched for.
+const MAX_PATH_DISTANCE: i32 = 48;
+/// How high entities can jump and how far they can drop when following a
+/// path, in blocks. A jump just gets on top of a block, and falls any higher
+/// than this start to hurt.
+const STEP_HEIGHT: i32 = 1;
+const MAX_FALL: i32 = 3;
+/// How long a path is kept around after nothing asks for it, in seconds.
+const CACHE_TIME: f64 = 10.0;
+
+/// The block that the feet of an entity with this collision box are in.
+pub fn feet_block(transform: &comp::Transform, collidable: &comp::Collidable) -> BlockPos {
+    // a little higher, so that standing right on top of a block doesn't round
+    // down into it
+    let feet = transform.position.y + collidable.aabb.min.y + 0.01;
+    WorldPos(Point3::new(
+        transform.position.x,
+        feet,
+        transform.position.z,
+    ))
+    .into()
+}
+
+/// Whether two blocks touch, including at the edges and corners.
+fn is_next_to(a: BlockPos, b: BlockPos) -> bool {
+    let diff = a.0 - b.0;
+    diff.x.abs() <= 1 && diff.y.abs() <= 1 && diff.z.abs() <= 1
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+struct PathKey {
+    start: BlockPos,
+    goal: BlockPos,
+    limits: PathLimits,
+}
+
+impl PathKey {
+    /// The box of blocks that the search for this path can use.
+    fn bounds(&self) -> (BlockPos, BlockPos) {
+        let (start, goal) = (self.start.0, self.goal.0);
+        let margin = Vector3::new(SEARCH_MARGIN, SEARCH_MARGIN, SEARCH_MARGIN);
+        let min = Point3::new(
+            start.x.min(goal.x),
+            start.y.min(goal.y),
+            start.z.min(goal.z),
+        );
+        let max = Point3::new(
+            start.x.max(goal.x),
+            start.y.max(goal.y),
+            start.z.max(goal.z),
+        );
+        (BlockPos(min - margin), BlockPos(max + margin))
+    }
+
+    fn search_covers(&self, pos: BlockPos) -> bool {
+        let (min, max) = self.bounds();
+        (0..3).all(|axis| pos.0[axis] >= min.0[axis] && pos.0[axis] <= max.0[axis])
+    }
+
+    fn is_affected_by(&self, path: &Option<Arc<Path>>, pos: BlockPos) -> bool {
+        match path {
+            Some(path) => path.is_affected_by(pos),
+            // any block in the way could be the one that was stopping it
+            None => self.search_covers(pos),
+        }
+    }
+}
+
+struct CachedPath {
+    path: Option<Arc<Path>>,
+    unused_for: f64,
+}
+
+/// Finds paths to the goals of entities with `Navigation`, using A* on
+/// background threads. Finished paths are cached, and are thrown away when a
+/// block along them changes.
+///
+/// While recording or replaying, paths are found right away instead, since
+/// the tick a background search finishes on isn't the same every time.
+pub struct Pathfinding {
+    service: job::Service<Pathfinder>,
+    cache: HashMap<PathKey, CachedPath>,
+    /// Searches that haven't finished, and whether a block they could use has
+    /// changed since they started, which means they have to be done again.
+    pending: HashMap<PathKey, bool>,
+    /// Whether to search on this thread, and the searches that were done that
+    /// way, which are handled at the start of the next tick like the ones from
+    /// the service.
+    synchronous: bool,
+    solved: Vec<(PathRequest, Option<Path>)>,
+}
+
+impl Pathfinding {
+    pub fn new() -> Self {
+        Pathfinding {
+            service: job::Service::new("Pathfinder", 2, Pathfinder),
+            cache: HashMap::new(),
+            pending: HashMap::new(),
+            synchronous: false,
+            solved: Vec::new(),
+        }
+    }
+
+    fn request(&mut self, world: &VoxelWorld, key: PathKey) {
+        let (min, max) = key.bounds();
+        self.pending.insert(key, false);
+        let request = PathRequest {
+            start: key.start,
+            goal: key.goal,
+            limits: key.limits,
+            grid: NavGrid::capture(world, min, max),
+        };
+        if self.synchronous {
+            let path = Pathfinder.compute(&request);
+            self.solved.push((request, path));
+        } else {
+            self.service.request(request);
+        }
+    }
+
+    /// Throws away the paths that go through the changed blocks, and the
+    /// failed searches that the blocks could have been in the way of.
+    fn forget_changed(
+        &mut self,
+        changed: &[BlockPos],
+        navigations: &mut WriteStorage<comp::Navigation>,
+    ) {
+        self.cache.retain(|key, cached| {
+            !changed
+                .iter()
+                .any(|&pos| key.is_affected_by(&cached.path, pos))
+        });
+        for (key, stale) in &mut self.pending {
+            *stale |= changed.iter().any(|&pos| key.search_covers(pos));
+        }
+
+        for nav in navigations.join() {
+            let affected = match &nav.path {
+                Some(path) => changed.iter().any(|&pos| path.is_affected_by(pos)),
+                None => true,
+            };
+            if affected {
+                nav.replan();
+            }
+        }
+    }
+}
+
+impl<'a> System<'a> for Pathfinding {
+    type SystemData = (
+        WriteStorage<'a, comp::Navigation>,
+        ReadStorage<'a, comp::Transform>,
+        ReadStorage<'a, comp::Collidable>,
+        WriteExpect<'a, VoxelWorld>,
+        Read<'a, res::Dt>,
+        Read<'a, Replay>,
+    );
+
+    fn run(
+        &mut self,
+        (mut navigations, transforms, collidables, mut world, dt, replay): Self::SystemData,
+    ) {
+        self.synchronous = replay.is_active();
+
+        let changed: Vec<_> = world.drain_changed_blocks().collect();
+        if !changed.is_empty() {
+            self.forget_changed(&changed, &mut navigations);
+        }
+
+        let mut finished = mem::replace(&mut self.solved, Vec::new());
+        finished.extend(self.service.gather());
+        for (request, path) in finished {
+            let key = PathKey {
+                start: request.start,
+                goal: request.goal,
+                limits: request.limits,
+            };
+            match self.pending.remove(&key) {
+                // search again with the blocks as they are now
+                Some(true) => self.request(&world, key),
+                _ => {
+                    self.cache.insert(
+                        key,
+                        CachedPath {
+                            path: path.map(Arc::new),
+                            unused_for: 0.0,
+                        },
+                    );
+                }
+            }
+        }
+
+        let dt = dt.as_secs();
+        for cached in self.cache.values_mut() {
+            cached.unused_for += dt;
+        }
+        self.cache
+            .retain(|_, cached| cached.unused_for < CACHE_TIME);
+
+        for (nav, transform, collidable) in (&mut navigations, &transforms, &collidables).join() {
+            let goal = match nav.goal {
+                Some(goal) => goal,
+                None => {
+                    nav.path = None;
+                    nav.path_goal = None;
+                    continue;
+                }
+            };
+            if nav.path_goal == Some(goal) {
+                continue;
+            }
+
+            let start = feet_block(transform, collidable);
+            let limits = PathLimits::for_body(&collidable.aabb, STEP_HEIGHT, MAX_FALL);
+            let distance = goal.0 - start.0;
+            let too_far =
+                distance.x.abs().max(distance.y.abs()).max(distance.z.abs()) > MAX_PATH_DISTANCE;
+
+            // entities keep moving while they wait for a path, so any path to
+            // the goal that goes right next to them will do. The one that
+            // starts at the lowest position is picked, because the order of
+            // the cache changes every time the game runs.
+            let cached = self
+                .cache
+                .iter_mut()
+                .filter(|(key, _)| key.goal == goal && key.limits == limits)
+                .filter_map(|(key, cached)| {
+                    let next = match &cached.path {
+                        Some(path) => path
+                            .cells
+                            .iter()
+                            .position(|&cell| is_next_to(cell, start))?,
+                        None if key.start == start => 0,
+                        None => return None,
+                    };
+                    Some((key.start, cached, next))
+                })
+                .min_by_key(|&(from, _, _)| (from.0.x, from.0.y, from.0.z))
+                .map(|(_, cached, next)| (cached, next));
+
+            if too_far {
+                nav.path = None;
+                nav.waypoint = 0;
+            } else if let Some((cached, next)) = cached {
+                cached.unused_for = 0.0;
+                nav.path = cached.path.clone();
+                nav.waypoint = next + 1;
+            } else {
+                let searching = self
+                    .pending
+                    .keys()
+                    .any(|key| key.goal == goal && key.limits == limits);
+                // keep following the old path until the search is done
+                if !searching {
+                    self.request(
+                        &world,
+                        PathKey {
+                            start,
+                            goal,
+                            limits,
+                        },
+                    );
+                }
+                continue;
+            }
+
+            nav.path_goal = Some(goal);
+        }
+    }
+}
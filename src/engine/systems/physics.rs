@@ -2,9 +2,15 @@ use collision::{prelude::*, Aabb3};
 use engine::{
     prelude::*,
     render::debug::{DebugAccumulator, DebugSection, Shape},
-    world::VoxelWorld,
+    world::{spatial::EntityIndex, VoxelWorld},
 };
 
+/// How fast falling bodies speed up, in blocks per second per second.
+pub const GRAVITY: f64 = 25.0;
+/// How hard bodies that overlap push each other apart, in blocks per second
+/// per second for every block they overlap by.
+const PUSH_STRENGTH: f64 = 40.0;
+
 pub struct Physics;
 
 impl Physics {
@@ -13,7 +19,9 @@ impl Physics {
     }
 }
 
-fn collidable_blocks_in_aabb(world: &VoxelWorld, aabb: Aabb3<f64>) -> Vec<BlockPos> {
+/// Finds the collision boxes of every block that touches `aabb`, in world
+/// space.
+fn collidable_blocks_in_aabb(world: &VoxelWorld, aabb: Aabb3<f64>) -> Vec<Aabb3<f64>> {
     let min: BlockPos = WorldPos(aabb.min).into();
     let max: BlockPos = WorldPos(aabb.max).into();
     let mut found = vec![];
@@ -23,9 +31,8 @@ fn collidable_blocks_in_aabb(world: &VoxelWorld, aabb: Aabb3<f64>) -> Vec<BlockP
             for z in min.0.z..=max.0.z {
                 let pos = BlockPos(Point3::new(x, y, z));
                 if let Some(props) = world.registry(pos) {
-                    if props.collidable() {
-                        found.push(pos);
-                    }
+                    let offset = ::util::to_vector(pos.base().0);
+                    found.extend(props.collision_boxes().iter().map(|b| b.add_v(offset)));
                 }
             }
         }
@@ -61,6 +68,8 @@ struct PhysicsStepContext<'a> {
     body: &'a mut comp::RigidBody,
     collision_box: &'a comp::Collidable,
     dt: f64,
+    /// Set when the body is pushed up out of a block.
+    grounded: bool,
 }
 
 impl<'a> PhysicsStepContext<'a> {
@@ -71,11 +80,6 @@ impl<'a> PhysicsStepContext<'a> {
     }
 }
 
-fn cube_aabb(pos: BlockPos) -> Aabb3<f64> {
-    let cube_base = Aabb3::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
-    cube_base.add_v(::util::to_vector(pos.base().0))
-}
-
 // fn physics_step(ctx: &mut PhysicsStepContext, axis: usize, debug: &mut
 // DebugSection) {     ctx.body.velocity[axis]
 // }
@@ -100,9 +104,8 @@ fn physics_step_x(ctx: &mut PhysicsStepContext, debug: &mut DebugSection) {
     };
 
     // try to resolve the collisions
-    for (i, block) in blocks.iter().enumerate() {
+    for (i, &cube) in blocks.iter().enumerate() {
         let entity = ctx.entity_aabb();
-        let cube = cube_aabb(*block);
 
         dbg_aabb(entity, i);
         dbg_aabb(cube, i);
@@ -137,15 +140,17 @@ fn physics_step_y(ctx: &mut PhysicsStepContext, debug: &mut DebugSection) {
     };
 
     // try to resolve the collisions
-    for (i, block) in blocks.iter().enumerate() {
+    for (i, &cube) in blocks.iter().enumerate() {
         let entity = ctx.entity_aabb();
-        let cube = cube_aabb(*block);
 
         dbg_aabb(entity, i);
         dbg_aabb(cube, i);
 
         let resolution = resolve_collision(entity, cube, 1);
 
+        if resolution > 0.0 {
+            ctx.grounded = true;
+        }
         if resolution != 0.0 {
             ctx.transform.position.y += resolution;
             ctx.body.velocity.y = 0.0;
@@ -172,9 +177,8 @@ fn physics_step_z(ctx: &mut PhysicsStepContext, debug: &mut DebugSection) {
     };
 
     // try to resolve the collisions
-    for (i, block) in blocks.iter().enumerate() {
+    for (i, &cube) in blocks.iter().enumerate() {
         let entity = ctx.entity_aabb();
-        let cube = cube_aabb(*block);
 
         dbg_aabb(entity, i);
         dbg_aabb(cube, i);
@@ -188,11 +192,72 @@ fn physics_step_z(ctx: &mut PhysicsStepContext, debug: &mut DebugSection) {
     }
 }
 
+/// Speeds up bodies that overlap other entities away from them, sideways so
+/// that nothing ends up standing on anything else. The push is split between
+/// the two by mass, and entities without a rigid body don't move at all.
+/// Dropped items are left out, so that walking over them doesn't scatter them.
+fn push_apart(
+    index: &EntityIndex,
+    bodies: &mut WriteStorage<comp::RigidBody>,
+    dropped: &ReadStorage<comp::DroppedItem>,
+    dt: f64,
+) {
+    for ((a, a_box), (b, b_box)) in index.overlapping_pairs() {
+        if dropped.get(a).is_some() || dropped.get(b).is_some() {
+            continue;
+        }
+
+        let (a_mass, b_mass) = match (bodies.get(a), bodies.get(b)) {
+            (Some(a), Some(b)) => (a.mass, b.mass),
+            (Some(_), None) => (0.0, 1.0),
+            (None, Some(_)) => (1.0, 0.0),
+            (None, None) => continue,
+        };
+        let total_mass = a_mass + b_mass;
+        if total_mass <= 0.0 {
+            continue;
+        }
+
+        let overlap = |axis: usize| {
+            a_box.max[axis].min(b_box.max[axis]) - a_box.min[axis].max(b_box.min[axis])
+        };
+        let depth = overlap(0).min(overlap(2));
+        if depth <= 0.0 {
+            continue;
+        }
+
+        let center = |aabb: Aabb3<f64>| (aabb.min.to_vec() + aabb.max.to_vec()) / 2.0;
+        let mut away = center(a_box) - center(b_box);
+        away.y = 0.0;
+        // bodies right on top of each other still have to go some way
+        let away = if away.magnitude2() > 1e-6 {
+            away.normalize()
+        } else {
+            Vector3::unit_x()
+        };
+
+        let push = away * depth * PUSH_STRENGTH * dt;
+        if let Some(body) = bodies.get_mut(a) {
+            body.velocity += push * (b_mass / total_mass);
+        }
+        if let Some(body) = bodies.get_mut(b) {
+            body.velocity -= push * (a_mass / total_mass);
+        }
+    }
+}
+
 impl<'a> System<'a> for Physics {
     type SystemData = (
+        Entities<'a>,
         WriteStorage<'a, comp::Transform>,
         WriteStorage<'a, comp::RigidBody>,
         ReadStorage<'a, comp::Collidable>,
+        ReadStorage<'a, comp::GameMode>,
+        ReadStorage<'a, comp::DroppedItem>,
+        WriteStorage<'a, comp::Grounded>,
+        WriteStorage<'a, comp::Airborne>,
+        Write<'a, EventChannel<comp::Landed>>,
+        Write<'a, EntityIndex>,
         ReadExpect<'a, VoxelWorld>,
         Read<'a, res::Dt>,
         WriteExpect<'a, DebugAccumulator>,
@@ -200,15 +265,38 @@ impl<'a> System<'a> for Physics {
 
     fn run(
         &mut self,
-        (mut transforms, mut rigidbodies, collidables, world, dt, debug): Self::SystemData,
+        (
+            entities,
+            mut transforms,
+            mut rigidbodies,
+            collidables,
+            modes,
+            dropped,
+            mut grounded,
+            mut airborne,
+            mut landings,
+            mut index,
+            world,
+            dt,
+            debug,
+        ): Self::SystemData,
     ) {
-        for (transform, rigidbody, collidable) in
-            (&mut transforms, &mut rigidbodies, collidables.maybe()).join()
+        for (entity, transform, rigidbody, collidable, mode) in (
+            &entities,
+            &mut transforms,
+            &mut rigidbodies,
+            collidables.maybe(),
+            modes.maybe(),
+        )
+            .join()
         {
             let mut section = debug.section("physics");
+            // spectators pass straight through blocks
+            let collidable = collidable.filter(|_| mode.map_or(true, |mode| mode.collides()));
             let steps = if collidable.is_some() { 4 } else { 1 };
             // adjusted dt for smaller steps when there are more of them
             let dt = dt.as_secs() / steps as f64;
+            let mut on_ground = false;
 
             for _step in 0..steps {
                 // apply_physics_step(&mut rigidbody, &mut transform, dt);
@@ -219,13 +307,61 @@ impl<'a> System<'a> for Physics {
                         collision_box: collidable,
                         transform,
                         dt,
+                        grounded: false,
                     };
 
                     physics_step_x(&mut ctx, &mut section);
                     physics_step_y(&mut ctx, &mut section);
                     physics_step_z(&mut ctx, &mut section);
+                    on_ground |= ctx.grounded;
+                } else {
+                    let drag = rigidbody.drag * dt;
+                    rigidbody.velocity.x *= 1.0 / (1.0 + drag.x);
+                    rigidbody.velocity.y *= 1.0 / (1.0 + drag.y);
+                    rigidbody.velocity.z *= 1.0 / (1.0 + drag.z);
+                    transform.position += rigidbody.velocity * dt;
                 }
             }
+
+            let y = transform.position.y;
+            if on_ground {
+                if let Some(air) = airborne.remove(entity) {
+                    landings.single_write(comp::Landed {
+                        entity,
+                        height: air.highest - y,
+                    });
+                }
+                let _ = grounded.insert(entity, comp::Grounded);
+            } else {
+                grounded.remove(entity);
+                // swimming and passing through blocks aren't falling, so falls
+                // start over in liquids and for spectators
+                let in_liquid = world
+                    .registry(WorldPos(transform.position).into())
+                    .map_or(false, |props| props.liquid());
+                match airborne.get_mut(entity) {
+                    Some(air) if !in_liquid && collidable.is_some() => {
+                        air.highest = air.highest.max(y)
+                    }
+                    _ => {
+                        let _ = airborne.insert(entity, comp::Airborne { highest: y });
+                    }
+                }
+            }
+        }
+
+        // spectators can't be touched, so they are left out of the index
+        index.clear();
+        for (entity, transform, collidable, mode) in
+            (&entities, &transforms, &collidables, modes.maybe()).join()
+        {
+            if mode.map_or(true, |mode| mode.collides()) {
+                index.insert(
+                    entity,
+                    collidable.aabb.add_v(::util::to_vector(transform.position)),
+                );
+            }
         }
+        push_apart(&index, &mut rigidbodies, &dropped, dt.as_secs());
     }
 }
@@ -1,29 +1,71 @@
 use engine::{
     camera::Camera,
+    keybinds::{Action, ActionState},
     prelude::*,
     render::debug::{DebugAccumulator, Shape},
+    systems::physics::GRAVITY,
     world::chunk::SIZE,
 };
 use specs::prelude::*;
 
+/// How much flying players slow down on each axis.
+const FLYING_DRAG: Vector3<f64> = Vector3 {
+    x: 3.0,
+    y: 6.0,
+    z: 3.0,
+};
+/// How much walking players slow down on each axis. There is hardly any drag
+/// going up and down, so that they fall quickly.
+const WALKING_DRAG: Vector3<f64> = Vector3 {
+    x: 3.0,
+    y: 0.5,
+    z: 3.0,
+};
+/// How fast a player leaves the ground when they jump, which is enough to get
+/// on top of a block.
+pub const JUMP_SPEED: f64 = 8.0;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct PlayerController;
 
 impl<'a> System<'a> for PlayerController {
     type SystemData = (
+        Entities<'a>,
         ReadStorage<'a, comp::Player>,
+        ReadStorage<'a, comp::ClientControlled>,
         WriteStorage<'a, comp::Transform>,
         WriteStorage<'a, comp::RigidBody>,
         ReadStorage<'a, comp::MoveDelta>,
+        ReadStorage<'a, comp::GameMode>,
+        WriteStorage<'a, comp::Flying>,
+        ReadStorage<'a, comp::Grounded>,
+        ReadExpect<'a, VoxelWorld>,
         ReadExpect<'a, Camera>,
         Read<'a, res::ActiveDirections>,
+        Read<'a, ActionState>,
         WriteExpect<'a, DebugAccumulator>,
         ReadExpect<'a, res::Dt>,
     );
 
     fn run(
         &mut self,
-        (player, mut player_transform, mut rigidbody, move_delta, camera, directions, debug, dt): Self::SystemData,
+        (
+            entities,
+            player,
+            client_controlled,
+            mut player_transform,
+            mut rigidbody,
+            move_delta,
+            modes,
+            mut flying,
+            grounded,
+            world,
+            camera,
+            directions,
+            actions,
+            debug,
+            dt,
+        ): Self::SystemData,
     ) {
         let mut section = debug.section("chunk grid");
         for (_, tfm, move_delta) in (&player, &mut player_transform, &move_delta).join() {
@@ -61,9 +103,34 @@ impl<'a> System<'a> for PlayerController {
             ));
         }
 
+        if actions.was_pressed(Action::ToggleFlight) {
+            for (entity, _, _, &mode) in (&entities, &player, &client_controlled, &modes).join() {
+                if mode != comp::GameMode::Creative {
+                    continue;
+                }
+                if flying.remove(entity).is_none() {
+                    let _ = flying.insert(entity, comp::Flying);
+                }
+            }
+        }
+
         let dt = dt.as_secs();
 
-        for (_, rigidbody) in (&player, &mut rigidbody).join() {
+        for (entity, _, tfm, rigidbody, mode) in (
+            &entities,
+            &player,
+            &player_transform,
+            &mut rigidbody,
+            modes.maybe(),
+        )
+            .join()
+        {
+            let is_flying = match mode.cloned().unwrap_or_default() {
+                comp::GameMode::Survival => false,
+                comp::GameMode::Creative => flying.get(entity).is_some(),
+                comp::GameMode::Spectator => true,
+            };
+
             if directions.front {
                 rigidbody.velocity += 20.0 * dt * camera.basis_vectors().0;
             };
@@ -76,11 +143,24 @@ impl<'a> System<'a> for PlayerController {
             if directions.right {
                 rigidbody.velocity += 20.0 * dt * camera.basis_vectors().1;
             };
-            if directions.up {
-                rigidbody.velocity += Vector3::unit_y();
-            }
-            if directions.down {
-                rigidbody.velocity -= Vector3::unit_y();
+
+            if is_flying {
+                rigidbody.drag = FLYING_DRAG;
+                if directions.up {
+                    rigidbody.velocity += Vector3::unit_y();
+                }
+                if directions.down {
+                    rigidbody.velocity -= Vector3::unit_y();
+                }
+            } else {
+                rigidbody.drag = WALKING_DRAG;
+                // wait for the terrain to load instead of falling through it
+                if world.get_block_id(WorldPos(tfm.position).into()).is_some() {
+                    rigidbody.velocity.y -= GRAVITY * dt;
+                }
+                if directions.up && grounded.get(entity).is_some() {
+                    rigidbody.velocity.y = JUMP_SPEED;
+                }
             }
         }
     }
@@ -0,0 +1,214 @@
+use engine::{
+    assets::{AssetChanged, AssetManager},
+    console::Console,
+    item::ItemRegistry,
+    prelude::*,
+    world::block::BlockRegistry,
+};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often the resource packs are scanned for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches the resource pack directories for changed files by polling their
+/// modification times, and sends an `AssetChanged` for each one.
+pub struct AssetWatcher {
+    assets: Arc<AssetManager>,
+    times: HashMap<String, SystemTime>,
+    last_poll: Instant,
+}
+
+impl AssetWatcher {
+    pub fn new(assets: Arc<AssetManager>) -> Self {
+        let times = assets.modification_times().unwrap_or_else(|err| {
+            warn!("Could not watch resources for changes: {}", err);
+            HashMap::new()
+        });
+
+        AssetWatcher {
+            assets,
+            times,
+            last_poll: Instant::now(),
+        }
+    }
+}
+
+impl<'a> System<'a> for AssetWatcher {
+    type SystemData = Write<'a, EventChannel<AssetChanged>>;
+
+    fn run(&mut self, mut changes: Self::SystemData) {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return;
+        }
+        self.last_poll = Instant::now();
+
+        let times = match self.assets.modification_times() {
+            Ok(times) => times,
+            // editors sometimes replace files by deleting them first, so this
+            // is usually fixed by the next poll
+            Err(err) => {
+                debug!("Could not check resources for changes: {}", err);
+                return;
+            }
+        };
+
+        let removed = self
+            .times
+            .keys()
+            .filter(|asset| !times.contains_key(*asset));
+        let changed = times
+            .iter()
+            .filter(|(asset, time)| self.times.get(*asset) != Some(*time))
+            .map(|(asset, _)| asset);
+
+        let mut events: Vec<_> = removed.chain(changed).cloned().collect();
+        events.sort();
+        for asset in events {
+            info!("Resource changed: {}", asset);
+            changes.single_write(AssetChanged(asset));
+        }
+
+        self.times = times;
+    }
+}
+
+/// Reloads `blocks.json` when it changes, remeshing the world with the new
+/// block definitions. The items are rebuilt along with the blocks, and when
+/// `items.json` changes.
+pub struct BlockReloader {
+    assets: Arc<AssetManager>,
+    reader: ReaderId<AssetChanged>,
+}
+
+impl BlockReloader {
+    pub fn new(assets: Arc<AssetManager>, changes: &mut EventChannel<AssetChanged>) -> Self {
+        BlockReloader {
+            assets,
+            reader: changes.register_reader(),
+        }
+    }
+}
+
+impl<'a> System<'a> for BlockReloader {
+    type SystemData = (
+        Read<'a, EventChannel<AssetChanged>>,
+        Write<'a, Console>,
+        WriteExpect<'a, VoxelWorld>,
+        WriteExpect<'a, ItemRegistry>,
+    );
+
+    fn run(&mut self, (changes, mut console, mut world, mut items): Self::SystemData) {
+        let (mut blocks_changed, mut items_changed) = (false, false);
+        for AssetChanged(asset) in changes.read(&mut self.reader) {
+            blocks_changed |= asset == "blocks.json";
+            items_changed |= asset == "items.json";
+        }
+
+        if blocks_changed && self.reload_blocks(&mut console, &mut world) {
+            items_changed = true;
+        }
+        if items_changed {
+            self.reload_items(&mut console, &world, &mut items);
+        }
+    }
+}
+
+impl BlockReloader {
+    /// Returns whether the blocks were replaced.
+    fn reload_blocks(&self, console: &mut Console, world: &mut VoxelWorld) -> bool {
+        let registry = match BlockRegistry::load_from_assets(&self.assets) {
+            Ok(registry) => registry,
+            Err(err) => {
+                self.report(
+                    console,
+                    "blocks.json",
+                    format!("Could not reload blocks.json: {}", err),
+                );
+                return false;
+            }
+        };
+
+        if let Err(err) = check_state_ids(world.get_registry(), &registry) {
+            self.report(
+                console,
+                "blocks.json",
+                format!("Could not reload blocks.json: {}", err),
+            );
+            return false;
+        }
+
+        world.set_registry(registry);
+        self.assets.clear_problem("blocks.json");
+        console.print("Reloaded blocks.json");
+        true
+    }
+
+    /// Prints a reload error in the console, and keeps it as a problem with
+    /// the asset so that it is shown in game until it is fixed.
+    fn report(&self, console: &mut Console, asset: &str, message: String) {
+        console.print(message.clone());
+        self.assets.report_problem(asset, message);
+    }
+
+    fn reload_items(&self, console: &mut Console, world: &VoxelWorld, items: &mut ItemRegistry) {
+        let registry = match ItemRegistry::load_from_assets(&self.assets, world.get_registry()) {
+            Ok(registry) => registry,
+            Err(err) => {
+                self.report(
+                    console,
+                    "items.json",
+                    format!("Could not reload the items: {}", err),
+                );
+                return;
+            }
+        };
+
+        // inventories store item IDs, just like chunks store block IDs
+        let registry = match registry.keeping_ids_of(items) {
+            Ok(registry) => registry,
+            Err(err) => {
+                let message = format!(
+                    "Could not reload the items: {}, but inventories could still have it",
+                    err
+                );
+                self.report(console, "items.json", message);
+                return;
+            }
+        };
+
+        *items = registry;
+        self.assets.clear_problem("items.json");
+        console.print("Reloaded the items");
+    }
+}
+
+/// Chunks store block IDs, so every state in the world has to keep its ID
+/// when the blocks are reloaded. New states can only be added after the
+/// existing ones.
+fn check_state_ids(old: &BlockRegistry, new: &BlockRegistry) -> Result<(), String> {
+    if new.state_count() < old.state_count() {
+        return Err(format!(
+            "it has {} block states, but the world needs at least {}. Blocks can only be \
+             changed or added while the game is running",
+            new.state_count(),
+            old.state_count()
+        ));
+    }
+
+    match old
+        .states()
+        .find(|&id| new.state_name(id) != old.state_name(id))
+    {
+        Some(id) => Err(format!(
+            "`{}` would become `{}`. New blocks and properties can only be added after the \
+             existing ones while the game is running",
+            old.state_name(id),
+            new.state_name(id)
+        )),
+        None => Ok(()),
+    }
+}
@@ -1,6 +1,7 @@
+pub mod model;
 mod registry;
 
-pub use self::registry::*;
+pub use self::{model::BlockModel, registry::*};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default, Serialize, Deserialize)]
 pub struct Faces<T> {
@@ -0,0 +1,167 @@
+use cgmath::Point3;
+use collision::Aabb3;
+use engine::{world::block::Faces, Axis, Side};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Half {
+    Bottom,
+    Top,
+}
+
+/// The horizontal directions a block can face, named after the `Side` they
+/// point towards.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Facing {
+    Right,
+    Left,
+    Front,
+    Back,
+}
+
+/// The shape of a block as it is written in `blocks.json`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelConfig {
+    Cube,
+    /// Two planes crossing diagonally through the block, used for plants.
+    Cross,
+    Slab(Half),
+    /// A bottom slab with a second step on the side the stairs face.
+    Stairs(Facing),
+    /// Boxes given as `[min x, min y, min z, max x, max y, max z]`, where the
+    /// block spans from 0 to 1 on every axis.
+    Boxes(Vec<[f32; 6]>),
+}
+
+fn unit_box() -> Aabb3<f32> {
+    Aabb3::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0))
+}
+
+fn cast_box(bounds: &Aabb3<f32>) -> Aabb3<f64> {
+    Aabb3::new(bounds.min.cast().unwrap(), bounds.max.cast().unwrap())
+}
+
+fn half_box(axis: Axis, positive: bool) -> Aabb3<f32> {
+    let mut bounds = unit_box();
+    if positive {
+        bounds.min[axis as usize] = 0.5;
+    } else {
+        bounds.max[axis as usize] = 0.5;
+    }
+    bounds
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockModel {
+    /// Nothing is drawn, like for air.
+    None,
+    Cube,
+    Cross,
+    Boxes(Vec<Aabb3<f32>>),
+}
+
+impl BlockModel {
+    pub fn from_config(config: &ModelConfig) -> Self {
+        match config {
+            ModelConfig::Cube => BlockModel::Cube,
+            ModelConfig::Cross => BlockModel::Cross,
+            ModelConfig::Slab(half) => {
+                BlockModel::Boxes(vec![half_box(Axis::Y, *half == Half::Top)])
+            }
+            ModelConfig::Stairs(facing) => {
+                let mut step = match facing {
+                    Facing::Right => half_box(Axis::X, true),
+                    Facing::Left => half_box(Axis::X, false),
+                    Facing::Front => half_box(Axis::Z, true),
+                    Facing::Back => half_box(Axis::Z, false),
+                };
+                step.min.y = 0.5;
+                BlockModel::Boxes(vec![half_box(Axis::Y, false), step])
+            }
+            ModelConfig::Boxes(boxes) => BlockModel::Boxes(
+                boxes
+                    .iter()
+                    .map(|b| {
+                        Aabb3::new(Point3::new(b[0], b[1], b[2]), Point3::new(b[3], b[4], b[5]))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Whether the model completely covers a face of the block, so that
+    /// whatever is on the other side of that face can't be seen through it.
+    pub fn covers_face(&self, side: Side) -> bool {
+        match self {
+            BlockModel::Cube => true,
+            BlockModel::None | BlockModel::Cross => false,
+            BlockModel::Boxes(boxes) => boxes_cover_face(boxes, side),
+        }
+    }
+
+    pub fn covered_faces(&self) -> Faces<bool> {
+        Faces {
+            top: self.covers_face(Side::Top),
+            bottom: self.covers_face(Side::Bottom),
+            right: self.covers_face(Side::Right),
+            left: self.covers_face(Side::Left),
+            front: self.covers_face(Side::Front),
+            back: self.covers_face(Side::Back),
+        }
+    }
+
+    /// The boxes that entities collide with, relative to the block's origin.
+    pub fn collision_boxes(&self) -> Vec<Aabb3<f64>> {
+        match self {
+            BlockModel::Boxes(boxes) => boxes.iter().map(cast_box).collect(),
+            _ => vec![cast_box(&unit_box())],
+        }
+    }
+}
+
+/// Checks if the faces of `boxes` that lie on `side` of the block cover the
+/// whole side. The edges of the faces split the side into a grid, and the
+/// side is covered if the center of every cell is inside one of the faces.
+fn boxes_cover_face(boxes: &[Aabb3<f32>], side: Side) -> bool {
+    let axis = Axis::from(side) as usize;
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let on_side = |b: &&Aabb3<f32>| {
+        if side.facing_positive() {
+            b.max[axis] >= 1.0
+        } else {
+            b.min[axis] <= 0.0
+        }
+    };
+
+    let faces: Vec<_> = boxes
+        .iter()
+        .filter(on_side)
+        .map(|b| (b.min[u], b.max[u], b.min[v], b.max[v]))
+        .collect();
+
+    let mut us = vec![0.0, 1.0];
+    let mut vs = vec![0.0, 1.0];
+    for &(u0, u1, v0, v1) in &faces {
+        us.extend_from_slice(&[u0, u1]);
+        vs.extend_from_slice(&[v0, v1]);
+    }
+    let clean = |coords: &mut Vec<f32>| {
+        coords.retain(|&c| c >= 0.0 && c <= 1.0);
+        coords.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        coords.dedup();
+    };
+    clean(&mut us);
+    clean(&mut vs);
+
+    us.windows(2).all(|u| {
+        let cu = (u[0] + u[1]) / 2.0;
+        vs.windows(2).all(|v| {
+            let cv = (v[0] + v[1]) / 2.0;
+            faces
+                .iter()
+                .any(|&(u0, u1, v0, v1)| cu >= u0 && cu <= u1 && cv >= v0 && cv <= v1)
+        })
+    })
+}
@@ -1,7 +1,20 @@
 use cgmath::Vector2;
-use engine::{world::block::Faces, Side};
+use collision::Aabb3;
+use engine::{
+    assets::AssetManager,
+    definitions::{self, Definition, DefinitionError, Problem, SourcedEntry},
+    world::block::{
+        model::{BlockModel, ModelConfig},
+        Faces,
+    },
+    Side,
+};
 use rand::prelude::*;
-use std::{collections::HashMap, error::Error, io, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+};
 
 pub const AIR: BlockId = BlockId(0);
 pub const STONE: BlockId = BlockId(1);
@@ -21,7 +34,7 @@ fn weighted_select<T>(items: &Vec<(f32, T)>) -> &T {
     assert!(items.len() > 0);
 
     let sum: f32 = items.iter().map(|(weight, _)| weight).sum();
-    let mut num = SmallRng::from_entropy().gen_range(1.0, sum);
+    let mut num = SmallRng::from_entropy().gen_range(0.0, sum);
 
     for item in items {
         num -= item.0;
@@ -30,7 +43,8 @@ fn weighted_select<T>(items: &Vec<(f32, T)>) -> &T {
         }
     }
 
-    unreachable!()
+    // rounding errors can leave a tiny bit of `num` after the last item
+    &items[items.len() - 1].1
 }
 
 impl<T> FaceTexture<T> {
@@ -54,6 +68,45 @@ impl<T> FaceTexture<T> {
             FaceTexture::Weighted(vec) => weighted_select(vec),
         }
     }
+
+    /// The first texture, for when the same one has to be picked every time.
+    pub fn primary(&self) -> &T {
+        match self {
+            FaceTexture::Always(item) => item,
+            FaceTexture::Weighted(vec) => &vec[0].1,
+        }
+    }
+}
+
+/// An image in the `textures` directory, either used as is or as a vertical
+/// strip of square frames that are shown one after another.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum TextureConfig {
+    Static(String),
+    Animated {
+        strip: String,
+        /// How long each frame is shown for, in seconds. The strip has to
+        /// have exactly this many frames.
+        frame_times: Vec<f32>,
+    },
+}
+
+impl TextureConfig {
+    pub fn file_name(&self) -> &str {
+        match self {
+            TextureConfig::Static(name) => name,
+            TextureConfig::Animated { strip, .. } => strip,
+        }
+    }
+
+    /// How many layers of the texture array this texture takes up.
+    pub fn frame_count(&self) -> usize {
+        match self {
+            TextureConfig::Static(_) => 1,
+            TextureConfig::Animated { frame_times, .. } => frame_times.len(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Deserialize)]
@@ -66,31 +119,31 @@ pub struct BlockFace<T> {
 pub enum BlockTextures {
     /// The textures for all the faces are all the same
     #[serde(rename = "same")]
-    AllSame(BlockFace<String>),
+    AllSame(BlockFace<TextureConfig>),
 
     /// The textures for the sides are all the same, but the top and the bottom
     /// are different, like a grass block
     #[serde(rename = "top_bottom")]
     TopBottom {
-        top: BlockFace<String>,
-        bottom: BlockFace<String>,
-        side: BlockFace<String>,
+        top: BlockFace<TextureConfig>,
+        bottom: BlockFace<TextureConfig>,
+        side: BlockFace<TextureConfig>,
     },
 
     /// The texture for each face is different
     #[serde(rename = "different")]
     AllDifferent {
-        top: BlockFace<String>,
-        bottom: BlockFace<String>,
-        left: BlockFace<String>,
-        right: BlockFace<String>,
-        front: BlockFace<String>,
-        back: BlockFace<String>,
+        top: BlockFace<TextureConfig>,
+        bottom: BlockFace<TextureConfig>,
+        left: BlockFace<TextureConfig>,
+        right: BlockFace<TextureConfig>,
+        front: BlockFace<TextureConfig>,
+        back: BlockFace<TextureConfig>,
     },
 }
 
 impl BlockTextures {
-    fn expand(self) -> Faces<BlockFace<String>> {
+    fn expand(self) -> Faces<BlockFace<TextureConfig>> {
         match self {
             BlockTextures::AllSame(val) => Faces {
                 top: val.clone(),
@@ -127,124 +180,1002 @@ impl BlockTextures {
     }
 }
 
+/// How the faces of a block are drawn.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderLayer {
+    Opaque,
+    /// Pixels are either fully transparent or fully opaque, like leaves.
+    Cutout,
+    /// Pixels can be partially transparent, like stained glass. These faces
+    /// are sorted back to front before they are drawn.
+    Translucent,
+}
+
+impl Default for RenderLayer {
+    fn default() -> Self {
+        RenderLayer::Opaque
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_hardness() -> f32 {
+    1.0
+}
+
+/// A named property of a block, like the axis a log is facing along, with
+/// every value it can take.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct BlockProperty {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// Overrides for the states of a block whose properties have all the values
+/// in `when`. Later variants take precedence over earlier ones.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct StateVariant {
+    when: HashMap<String, String>,
+    collidable: Option<bool>,
+    textures: Option<BlockTextures>,
+    model: Option<ModelConfig>,
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize)]
 pub struct BlockRegistryEntry {
     name: String,
     collidable: bool,
     opaque: bool,
     liquid: bool,
+    /// How long the block takes to break by hand, in seconds. Blocks with a
+    /// hardness of 0 break straight away.
+    #[serde(default = "default_hardness")]
+    hardness: f32,
     textures: Option<BlockTextures>,
+    /// The shape of the block, which is a full cube if it isn't given. Blocks
+    /// without textures are never drawn.
+    model: Option<ModelConfig>,
+    #[serde(default)]
+    render_layer: RenderLayer,
+    /// Whether faces between two blocks in the same state are hidden, like
+    /// between two panes of glass.
+    #[serde(default = "default_true")]
+    cull_same: bool,
+    #[serde(default)]
+    properties: Vec<BlockProperty>,
+    /// Property values for the state that is used when only the name of the
+    /// block is given. Properties that aren't listed use their first value.
+    #[serde(default)]
+    default_state: HashMap<String, String>,
+    #[serde(default)]
+    variants: Vec<StateVariant>,
+    /// Groups the block belongs to, like `soil` or `mineable/pickaxe`, so that
+    /// game logic doesn't have to name specific blocks.
+    #[serde(default)]
+    tags: Vec<String>,
 }
 
+/// Tags that `tags.json` in a resource pack adds to blocks, by tag name.
+type TagFile = BTreeMap<String, Vec<String>>;
+
+/// Identifies a single state of a block. Blocks without any properties have
+/// exactly one state.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct BlockId(usize);
 
+#[derive(Clone, Debug, PartialEq)]
+struct Property {
+    name: String,
+    values: Vec<String>,
+    /// How far apart the state IDs of two states are if they only differ by
+    /// one step in this property.
+    stride: usize,
+}
+
+/// A tag that has been looked up in the registry, which is faster to check
+/// than going by name.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TagId(usize);
+
+#[derive(Clone, Debug, PartialEq)]
+struct BlockInfo {
+    name: String,
+    properties: Vec<Property>,
+    first_state: usize,
+    default_state: BlockId,
+    tags: Vec<TagId>,
+    hardness: f32,
+}
+
+impl BlockInfo {
+    fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|prop| prop.name == name)
+    }
+
+    fn value_index(&self, prop: &Property, id: BlockId) -> usize {
+        (id.0 - self.first_state) / prop.stride % prop.values.len()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct BlockRegistryBuilder {
     // per-block
-    names: Vec<String>,
+    blocks: Vec<BlockInfo>,
+
+    // per-state
+    state_blocks: Vec<usize>,
     opaque: Vec<bool>,
     collidable: Vec<bool>,
     liquid: Vec<bool>,
     texture_indices: Vec<Option<Faces<BlockFace<usize>>>>,
+    models: Vec<BlockModel>,
+    occludes: Vec<Faces<bool>>,
+    collision_boxes: Vec<Vec<Aabb3<f64>>>,
+    render_layers: Vec<RenderLayer>,
+    cull_same: Vec<bool>,
+
+    // per-tag
+    tag_names: Vec<String>,
+    /// The indices of the blocks with each tag.
+    tag_blocks: Vec<Vec<usize>>,
 
     // other
-    textures: Vec<String>,
+    textures: Vec<TextureConfig>,
+    /// The texture array layer that each texture starts at.
+    texture_layers: Vec<usize>,
+    layer_count: usize,
 }
 
 impl BlockRegistryBuilder {
+    /// Adds a block, along with one state for every combination of its
+    /// property values.
     pub fn register(&mut self, entry: BlockRegistryEntry) {
-        self.names.push(entry.name);
-        self.opaque.push(entry.opaque);
-        self.collidable.push(entry.collidable);
-        self.liquid.push(entry.liquid);
-
-        if let Some(textures) = entry.textures {
-            // expand the face textures into a `Faces`, where all sides are reified
-            // into fields for each face, try to add the face items into the
-            // textures array
-            let faces_ref = textures.expand().map(|face| {
-                let texture = face.texture.map(|name| {
-                    // if the texture was already added to the list, return its index. We pass the
-                    // list of names to the terrain renderer later so it can load the files
-                    // associated with the names. We don't want to load the same thing twice and
-                    // then store the extraneous texture in the texture array.
-                    // TODO: greater than linear time :(
-                    if let Some(idx) = self.get_texture_index(&name) {
-                        idx
-                    } else {
-                        // if the item was not found, push it onto the vec and return its index,
-                        // which will the the index of the last item on the list
-                        self.textures.push(name);
-                        self.textures.len() - 1
+        let mut properties = Vec::with_capacity(entry.properties.len());
+        for prop in entry.properties {
+            if prop.values.is_empty() {
+                warn!(
+                    "Ignoring property `{}` of `{}` because it has no values",
+                    prop.name, entry.name
+                );
+                continue;
+            }
+            properties.push(Property {
+                name: prop.name,
+                values: prop.values,
+                stride: 1,
+            });
+        }
+
+        // the last property changes fastest as the state ID goes up
+        let mut state_count = 1;
+        for prop in properties.iter_mut().rev() {
+            prop.stride = state_count;
+            state_count *= prop.values.len();
+        }
+
+        let block_index = self.blocks.len();
+        let mut block = BlockInfo {
+            name: entry.name,
+            properties,
+            first_state: self.state_blocks.len(),
+            default_state: BlockId(self.state_blocks.len()),
+            tags: Vec::new(),
+            hardness: entry.hardness,
+        };
+
+        let mut default_state = block.first_state;
+        for (name, value) in &entry.default_state {
+            match find_value(&block, name, value) {
+                Some((prop, idx)) => default_state += idx * prop.stride,
+                None => warn!(
+                    "Ignoring default `{}={}` for `{}`: no such property value",
+                    name, value, block.name
+                ),
+            }
+        }
+        block.default_state = BlockId(default_state);
+
+        let variants: Vec<_> = entry
+            .variants
+            .into_iter()
+            .filter(|variant| {
+                for (name, value) in &variant.when {
+                    if find_value(&block, name, value).is_none() {
+                        warn!(
+                            "Ignoring variant of `{}`: no property value `{}={}`",
+                            block.name, name, value
+                        );
+                        return false;
                     }
-                });
-                BlockFace {
-                    texture,
-                    random_orientation: face.random_orientation,
                 }
+                true
+            })
+            .collect();
+
+        let base_textures = entry.textures.map(|textures| self.add_textures(textures));
+        for offset in 0..state_count {
+            let id = BlockId(block.first_state + offset);
+            let mut collidable = entry.collidable;
+            let mut textures = base_textures.clone();
+            let mut model = entry.model.as_ref();
+
+            let matching = variants.iter().filter(|variant| {
+                variant.when.iter().all(|(name, value)| {
+                    let prop = block.property(name).unwrap();
+                    prop.values[block.value_index(prop, id)] == *value
+                })
+            });
+            for variant in matching {
+                if let Some(value) = variant.collidable {
+                    collidable = value;
+                }
+                if let Some(variant_textures) = &variant.textures {
+                    textures = Some(self.add_textures(variant_textures.clone()));
+                }
+                if let Some(variant_model) = &variant.model {
+                    model = Some(variant_model);
+                }
+            }
+
+            let model = match (&textures, model) {
+                (None, _) => BlockModel::None,
+                (Some(_), Some(config)) => BlockModel::from_config(config),
+                (Some(_), None) => BlockModel::Cube,
+            };
+            let covered = model.covered_faces();
+
+            self.state_blocks.push(block_index);
+            // `opaque` in the file is about the material, but everything else
+            // only cares about blocks that hide whatever is behind them
+            let solid = entry.opaque && entry.render_layer == RenderLayer::Opaque;
+            self.opaque.push(solid && model == BlockModel::Cube);
+            self.occludes
+                .push(covered.map(|covered| covered && solid && !entry.liquid));
+            self.collision_boxes.push(if collidable {
+                model.collision_boxes()
+            } else {
+                vec![]
             });
+            self.collidable.push(collidable);
+            self.liquid.push(entry.liquid);
+            self.texture_indices.push(textures);
+            self.models.push(model);
+            self.render_layers.push(entry.render_layer);
+            self.cull_same.push(entry.cull_same);
+        }
+
+        self.blocks.push(block);
+        for tag in &entry.tags {
+            self.add_tag(block_index, tag);
+        }
+    }
+
+    /// Adds a tag to a block that was already registered, like the tags that
+    /// resource packs add in `tags.json`. Returns `false` if there is no block
+    /// called `block`.
+    pub fn tag_block(&mut self, block: &str, tag: &str) -> bool {
+        match self.blocks.iter().position(|info| info.name == block) {
+            Some(idx) => {
+                self.add_tag(idx, tag);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn add_tag(&mut self, block: usize, tag: &str) {
+        let tag = match self.tag_names.iter().position(|name| name == tag) {
+            Some(idx) => TagId(idx),
+            None => {
+                self.tag_names.push(tag.into());
+                self.tag_blocks.push(Vec::new());
+                TagId(self.tag_names.len() - 1)
+            }
+        };
 
-            self.texture_indices.push(Some(faces_ref));
-        } else {
-            self.texture_indices.push(None);
+        if !self.blocks[block].tags.contains(&tag) {
+            self.blocks[block].tags.push(tag);
+            self.tag_blocks[tag.0].push(block);
         }
     }
 
-    fn get_texture_index(&self, name: &str) -> Option<usize> {
-        self.textures.iter().position(|item| item == name)
+    fn add_textures(&mut self, textures: BlockTextures) -> Faces<BlockFace<usize>> {
+        // expand the face textures into a `Faces`, where all sides are reified
+        // into fields for each face, try to add the face items into the
+        // textures array
+        textures.expand().map(|face| {
+            let texture = face.texture.map(|config| {
+                // if the texture was already added to the list, return its layer. We pass the
+                // list of textures to the terrain renderer later so it can load the files
+                // associated with them. We don't want to load the same thing twice and
+                // then store the extraneous texture in the texture array.
+                // TODO: greater than linear time :(
+                if let Some(idx) = self.get_texture_index(&config) {
+                    self.texture_layers[idx]
+                } else {
+                    // if the item was not found, push it onto the vec. Animated textures
+                    // take up one layer for every frame, and faces point at the first one.
+                    let layer = self.layer_count;
+                    self.layer_count += config.frame_count();
+                    self.textures.push(config);
+                    self.texture_layers.push(layer);
+                    layer
+                }
+            });
+            BlockFace {
+                texture,
+                random_orientation: face.random_orientation,
+            }
+        })
+    }
+
+    fn get_texture_index(&self, config: &TextureConfig) -> Option<usize> {
+        self.textures.iter().position(|item| item == config)
     }
 
-    pub fn build(self) -> (BlockRegistry, Vec<String>) {
+    pub fn build(self) -> BlockRegistry {
         let mut registry = BlockRegistry::default();
 
         debug!("builder: {:#?}", &self);
 
         registry.name_map = self
-            .names
-            .into_iter()
+            .blocks
+            .iter()
             .enumerate()
-            .map(|(a, b)| (b, BlockId(a)))
+            .map(|(idx, block)| (block.name.clone(), idx))
             .collect();
+        registry.blocks = self.blocks;
+        registry.tag_map = self
+            .tag_names
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| (name.clone(), TagId(idx)))
+            .collect();
+        registry.tag_names = self.tag_names;
+        registry.tag_blocks = self.tag_blocks;
 
+        registry.state_blocks = self.state_blocks;
         registry.opaque = self.opaque;
         registry.collidable = self.collidable;
         registry.texture_indices = self.texture_indices;
         registry.liquid = self.liquid;
+        registry.models = self.models;
+        registry.occludes = self.occludes;
+        registry.collision_boxes = self.collision_boxes;
+        registry.render_layers = self.render_layers;
+        registry.cull_same = self.cull_same;
+        registry.textures = self.textures;
+
+        registry
+    }
+}
+
+fn find_value<'b>(block: &'b BlockInfo, name: &str, value: &str) -> Option<(&'b Property, usize)> {
+    let prop = block.property(name)?;
+    let idx = prop.values.iter().position(|item| item == value)?;
+    Some((prop, idx))
+}
+
+impl Definition for BlockRegistryEntry {
+    const KIND: &'static str = "blocks";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A block definition along with where it came from.
+type SourcedBlock = SourcedEntry<BlockRegistryEntry>;
+
+impl SourcedBlock {
+    /// Every face texture of the block and its variants, along with the path
+    /// to it inside the entry.
+    fn face_textures(&self) -> Vec<(String, &FaceTexture<TextureConfig>)> {
+        let variants = self
+            .entry
+            .variants
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, variant)| {
+                let textures = variant.textures.as_ref()?;
+                Some((format!("/variants/{}/textures", idx), textures))
+            });
+        let all = self
+            .entry
+            .textures
+            .as_ref()
+            .map(|textures| ("/textures".to_owned(), textures))
+            .into_iter()
+            .chain(variants);
+
+        let mut found = Vec::new();
+        for (path, textures) in all {
+            let faces = match textures {
+                BlockTextures::AllSame(face) => vec![("same", face)],
+                BlockTextures::TopBottom { top, bottom, side } => vec![
+                    ("top_bottom/top", top),
+                    ("top_bottom/bottom", bottom),
+                    ("top_bottom/side", side),
+                ],
+                BlockTextures::AllDifferent {
+                    top,
+                    bottom,
+                    left,
+                    right,
+                    front,
+                    back,
+                } => vec![
+                    ("different/top", top),
+                    ("different/bottom", bottom),
+                    ("different/left", left),
+                    ("different/right", right),
+                    ("different/front", front),
+                    ("different/back", back),
+                ],
+            };
+            for (name, face) in faces {
+                found.push((format!("{}/{}/texture", path, name), &face.texture));
+            }
+        }
+        found
+    }
+
+    /// Every texture the block uses, along with the path to it inside the
+    /// entry.
+    fn texture_configs(&self) -> Vec<(String, &TextureConfig)> {
+        let mut found = Vec::new();
+        for (path, texture) in self.face_textures() {
+            match texture {
+                FaceTexture::Always(config) => found.push((path, config)),
+                FaceTexture::Weighted(items) => {
+                    for (idx, (_, config)) in items.iter().enumerate() {
+                        found.push((format!("{}/{}/1", path, idx), config));
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Checks everything about the blocks that can be checked without loading
+/// their textures.
+fn check_entries(entries: &[SourcedBlock], problems: &mut Vec<Problem>) {
+    match entries.first() {
+        Some(first) if first.entry.name != "air" => problems.push(first.problem(
+            "/name",
+            format!(
+                "the first block has to be `air`, not `{}`",
+                first.entry.name
+            ),
+        )),
+        Some(air) if !air.entry.properties.is_empty() => problems.push(air.problem(
+            "/properties",
+            "`air` can't have properties, because it has to have a single state",
+        )),
+        _ => (),
+    }
+
+    for sourced in entries {
+        check_entry(sourced, problems);
+    }
+}
+
+fn check_entry(sourced: &SourcedBlock, problems: &mut Vec<Problem>) {
+    let entry = &sourced.entry;
+
+    for (idx, prop) in entry.properties.iter().enumerate() {
+        if prop.values.is_empty() {
+            problems.push(sourced.problem(
+                &format!("/properties/{}/values", idx),
+                format!("property `{}` has no values", prop.name),
+            ));
+        }
+        if entry.properties[..idx]
+            .iter()
+            .any(|other| other.name == prop.name)
+        {
+            problems.push(sourced.problem(
+                &format!("/properties/{}/name", idx),
+                format!("property `{}` is defined twice", prop.name),
+            ));
+        }
+        for (value_idx, value) in prop.values.iter().enumerate() {
+            if prop.values[..value_idx].contains(value) {
+                problems.push(sourced.problem(
+                    &format!("/properties/{}/values/{}", idx, value_idx),
+                    format!("property `{}` has the value `{}` twice", prop.name, value),
+                ));
+            }
+        }
+    }
+
+    if entry.hardness < 0.0 {
+        problems.push(sourced.problem("/hardness", "the hardness can't be negative"));
+    }
+
+    for (idx, tag) in entry.tags.iter().enumerate() {
+        if tag.is_empty() {
+            problems.push(sourced.problem(&format!("/tags/{}", idx), "tags can't be empty"));
+        }
+    }
+
+    let has_value = |name: &str, value: &str| {
+        entry
+            .properties
+            .iter()
+            .any(|prop| prop.name == name && prop.values.iter().any(|item| item == value))
+    };
+    for (name, value) in &entry.default_state {
+        if !has_value(name, value) {
+            problems.push(sourced.problem(
+                &format!("/default_state/{}", name),
+                format!("no property value `{}={}`", name, value),
+            ));
+        }
+    }
+    for (idx, variant) in entry.variants.iter().enumerate() {
+        for (name, value) in &variant.when {
+            if !has_value(name, value) {
+                problems.push(sourced.problem(
+                    &format!("/variants/{}/when/{}", idx, name),
+                    format!("no property value `{}={}`", name, value),
+                ));
+            }
+        }
+    }
+
+    let models = entry.model.iter().map(|model| ("/model".to_owned(), model));
+    let variant_models = entry
+        .variants
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, variant)| {
+            let model = variant.model.as_ref()?;
+            Some((format!("/variants/{}/model", idx), model))
+        });
+    for (path, model) in models.chain(variant_models) {
+        if let ModelConfig::Boxes(boxes) = model {
+            for (idx, b) in boxes.iter().enumerate() {
+                let inside = (0..3)
+                    .all(|axis| 0.0 <= b[axis] && b[axis] < b[axis + 3] && b[axis + 3] <= 1.0);
+                if !inside {
+                    problems.push(sourced.problem(
+                        &format!("{}/boxes/{}", path, idx),
+                        "boxes have to have a positive size and fit inside the block",
+                    ));
+                }
+            }
+        }
+    }
+
+    for (path, texture) in sourced.face_textures() {
+        if let FaceTexture::Weighted(items) = texture {
+            if items.is_empty() {
+                problems.push(sourced.problem(&path, "the list of weighted textures is empty"));
+            }
+            for (idx, (weight, _)) in items.iter().enumerate() {
+                if !(*weight > 0.0) {
+                    problems.push(sourced.problem(
+                        &format!("{}/{}/0", path, idx),
+                        format!("texture weights have to be positive, not {}", weight),
+                    ));
+                }
+            }
+        }
+    }
+
+    for (path, config) in sourced.texture_configs() {
+        if let TextureConfig::Animated { frame_times, .. } = config {
+            if frame_times.is_empty() {
+                problems.push(sourced.problem(
+                    &format!("{}/frame_times", path),
+                    "an animated texture needs at least one frame",
+                ));
+            }
+            if frame_times.iter().any(|&time| !(time > 0.0)) {
+                problems.push(sourced.problem(
+                    &format!("{}/frame_times", path),
+                    "frame times have to be positive",
+                ));
+            }
+        }
+    }
+}
+
+/// Checks that the blocks in a `tags.json` exist. Tags can be given to
+/// blocks from any pack, so this has to happen after all of them are loaded.
+fn check_tag_file(
+    file: &str,
+    tags: &TagFile,
+    entries: &[SourcedBlock],
+    problems: &mut Vec<Problem>,
+) {
+    for (tag, blocks) in tags {
+        for (idx, block) in blocks.iter().enumerate() {
+            if !entries.iter().any(|sourced| sourced.entry.name == *block) {
+                problems.push(Problem {
+                    file: file.into(),
+                    pointer: format!("/{}/{}", tag, idx),
+                    name: block.clone(),
+                    message: format!("tag `{}` lists a block that doesn't exist", tag),
+                });
+            }
+        }
+    }
+}
+
+/// Checks that every texture can be loaded, and that it is `texture_size`
+/// pixels wide and tall enough for all of its frames.
+fn check_texture_files(
+    assets: &AssetManager,
+    entries: &[SourcedBlock],
+    texture_size: u32,
+    problems: &mut Vec<Problem>,
+) {
+    let mut checked = HashMap::new();
+    for sourced in entries {
+        for (path, config) in sourced.texture_configs() {
+            let frames = config.frame_count() as u32;
+            if frames == 0 {
+                continue;
+            }
+
+            let (width, height) = (texture_size, texture_size * frames);
+            let file = config.file_name();
+            let problem = checked.entry((file, frames)).or_insert_with(|| {
+                match assets.load_image(&format!("textures/{}", file)) {
+                    Err(err) => Some(format!("could not load texture `{}`: {}", file, err)),
+                    Ok(ref image) if image.dimensions() != (width, height) => Some(format!(
+                        "texture `{}` is {}x{}, but has to be {}x{}",
+                        file,
+                        image.width(),
+                        image.height(),
+                        width,
+                        height
+                    )),
+                    Ok(_) => None,
+                }
+            });
 
-        (registry, self.textures)
+            if let Some(message) = problem {
+                problems.push(sourced.problem(&path, message.clone()));
+            }
+        }
     }
 }
 
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct BlockRegistry {
-    name_map: HashMap<String, BlockId>,
+    name_map: HashMap<String, usize>,
+    blocks: Vec<BlockInfo>,
+    state_blocks: Vec<usize>,
     opaque: Vec<bool>,
     collidable: Vec<bool>,
     liquid: Vec<bool>,
     texture_indices: Vec<Option<Faces<BlockFace<usize>>>>,
+    models: Vec<BlockModel>,
+    occludes: Vec<Faces<bool>>,
+    collision_boxes: Vec<Vec<Aabb3<f64>>>,
+    render_layers: Vec<RenderLayer>,
+    cull_same: Vec<bool>,
+    textures: Vec<TextureConfig>,
+    tag_map: HashMap<String, TagId>,
+    tag_names: Vec<String>,
+    tag_blocks: Vec<Vec<usize>>,
+}
+
+/// Everything the registry is built from, along with the problems found in
+/// it so far.
+struct Definitions {
+    entries: Vec<SourcedBlock>,
+    /// The contents of each `tags.json`, along with the file it came from.
+    tag_files: Vec<(String, TagFile)>,
+    problems: Vec<Problem>,
 }
 
 impl BlockRegistry {
-    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<(Self, Vec<String>), Box<Error>> {
-        let entries: Vec<BlockRegistryEntry> =
-            serde_json::from_reader(::std::fs::File::open(path)?)?;
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, DefinitionError> {
+        let path = path.as_ref();
+        let mut problems = Vec::new();
+        let entries =
+            definitions::parse_file(&path.display().to_string(), &fs::read(path)?, &mut problems)?;
+        check_entries(&entries, &mut problems);
+        BlockRegistry::from_definitions(Definitions {
+            entries,
+            tag_files: Vec::new(),
+            problems,
+        })
+    }
+
+    /// Loads `blocks.json` from every resource pack. Packs can add blocks,
+    /// and a block with the same name as one from an earlier pack replaces it
+    /// without changing the order of the blocks. Packs can also add tags to
+    /// blocks with a `tags.json`, which maps tag names to lists of blocks.
+    pub fn load_from_assets(assets: &AssetManager) -> Result<Self, DefinitionError> {
+        BlockRegistry::from_definitions(BlockRegistry::load_definitions(assets)?)
+    }
+
+    /// Like `load_from_assets`, but also checks that every texture exists and
+    /// has the size the texture array needs. Missing textures are only drawn
+    /// as a checkerboard in game, so this is for finding them ahead of time.
+    pub fn load_validated(
+        assets: &AssetManager,
+        texture_size: u32,
+    ) -> Result<Self, DefinitionError> {
+        let mut definitions = BlockRegistry::load_definitions(assets)?;
+        check_texture_files(
+            assets,
+            &definitions.entries,
+            texture_size,
+            &mut definitions.problems,
+        );
+        BlockRegistry::from_definitions(definitions)
+    }
+
+    fn load_definitions(assets: &AssetManager) -> Result<Definitions, DefinitionError> {
+        let mut problems = Vec::new();
+        let entries: Vec<SourcedBlock> =
+            definitions::load_all(assets, "blocks.json", &mut problems)?;
+
+        if entries.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no resource pack has a `blocks.json`",
+            )
+            .into());
+        }
+        check_entries(&entries, &mut problems);
+
+        let mut tag_files = Vec::new();
+        for (file, data) in assets.read_all("tags.json")? {
+            let tags: TagFile = serde_json::from_slice(&data)
+                .map_err(|err| DefinitionError::Parse(file.clone(), err))?;
+            check_tag_file(&file, &tags, &entries, &mut problems);
+            tag_files.push((file, tags));
+        }
+
+        Ok(Definitions {
+            entries,
+            tag_files,
+            problems,
+        })
+    }
+
+    fn from_definitions(definitions: Definitions) -> Result<Self, DefinitionError> {
+        if !definitions.problems.is_empty() {
+            return Err(DefinitionError::Invalid(
+                BlockRegistryEntry::KIND,
+                definitions.problems,
+            ));
+        }
+
         let mut builder = BlockRegistryBuilder::default();
 
         // could probably use Iterator::fold here for extra cool points :sunglasses:
-        for entry in entries {
+        for SourcedEntry { entry, .. } in definitions.entries {
             debug!("Adding {:#?}", entry);
             builder.register(entry);
         }
 
+        for (_, tags) in &definitions.tag_files {
+            for (tag, blocks) in tags {
+                for block in blocks {
+                    builder.tag_block(block, tag);
+                }
+            }
+        }
+
         Ok(builder.build())
     }
 
+    #[inline(always)]
+    fn block(&self, id: BlockId) -> &BlockInfo {
+        &self.blocks[self.state_blocks[id.0]]
+    }
+
+    /// The name of the block, without any of its properties.
+    pub fn name(&self, id: BlockId) -> &str {
+        &self.block(id).name
+    }
+
+    /// How many seconds the block takes to break by hand.
+    pub fn hardness(&self, id: BlockId) -> f32 {
+        self.block(id).hardness
+    }
+
+    /// The default state of every block, in the order they were defined.
+    pub fn blocks<'r>(&'r self) -> impl Iterator<Item = BlockId> + 'r {
+        self.blocks.iter().map(|block| block.default_state)
+    }
+
+    /// The default state of the block called `name`.
+    pub fn get_id(&self, name: &str) -> Option<BlockId> {
+        self.name_map
+            .get(name)
+            .map(|&idx| self.blocks[idx].default_state)
+    }
+
+    /// Looks up a tag, which only exists if at least one block has it.
+    pub fn tag_id(&self, name: &str) -> Option<TagId> {
+        self.tag_map.get(name).cloned()
+    }
+
+    /// Whether the block has a tag. This is true for every state of the block.
+    #[inline(always)]
+    pub fn in_tag(&self, id: BlockId, tag: TagId) -> bool {
+        self.block(id).tags.contains(&tag)
+    }
+
+    /// Like `in_tag`, but looks up the tag by name. Tags that no block has
+    /// are never matched.
+    pub fn has_tag(&self, id: BlockId, tag: &str) -> bool {
+        self.tag_id(tag).map_or(false, |tag| self.in_tag(id, tag))
+    }
+
+    /// The default states of every block with a tag, in the order the blocks
+    /// were defined.
+    pub fn tagged<'r>(&'r self, tag: &str) -> impl Iterator<Item = BlockId> + 'r {
+        self.tag_id(tag).into_iter().flat_map(move |tag| {
+            self.tag_blocks[tag.0]
+                .iter()
+                .map(move |&idx| self.blocks[idx].default_state)
+        })
+    }
+
+    /// The names of the tags a block has.
+    pub fn tags<'r>(&'r self, id: BlockId) -> impl Iterator<Item = &'r str> + 'r {
+        self.block(id)
+            .tags
+            .iter()
+            .map(move |tag| &self.tag_names[tag.0][..])
+    }
+
+    /// The textures that block faces refer to. The layers in the texture
+    /// array are in the same order as this list, and animated textures take
+    /// up one layer per frame.
+    pub fn textures(&self) -> &[TextureConfig] {
+        &self.textures
+    }
+
+    /// The number of block states, which is one more than the largest
+    /// `BlockId`.
+    pub fn state_count(&self) -> usize {
+        self.state_blocks.len()
+    }
+
+    /// Every block state, in ID order.
+    pub fn states(&self) -> impl Iterator<Item = BlockId> {
+        (0..self.state_count()).map(BlockId)
+    }
+
+    pub fn default_state(&self, id: BlockId) -> BlockId {
+        self.block(id).default_state
+    }
+
+    /// Whether two states belong to the same block.
+    pub fn same_block(&self, a: BlockId, b: BlockId) -> bool {
+        self.state_blocks[a.0] == self.state_blocks[b.0]
+    }
+
+    /// The value of a property for a block state, if the block has that
+    /// property.
+    pub fn property(&self, id: BlockId, name: &str) -> Option<&str> {
+        let block = self.block(id);
+        block
+            .property(name)
+            .map(|prop| &prop.values[block.value_index(prop, id)][..])
+    }
+
+    /// All the properties of a block state and their values.
+    pub fn properties(&self, id: BlockId) -> impl Iterator<Item = (&str, &str)> {
+        let block = self.block(id);
+        block.properties.iter().map(move |prop| {
+            (
+                &prop.name[..],
+                &prop.values[block.value_index(prop, id)][..],
+            )
+        })
+    }
+
+    /// The state that is the same as `id` except for property `name` having
+    /// `value`, or `None` if the block has no such property or value.
+    pub fn with_property(&self, id: BlockId, name: &str, value: &str) -> Option<BlockId> {
+        let block = self.block(id);
+        let (prop, new_idx) = find_value(block, name, value)?;
+        let old_idx = block.value_index(prop, id);
+        Some(BlockId(
+            id.0 - old_idx * prop.stride + new_idx * prop.stride,
+        ))
+    }
+
+    /// Formats a state like `log[axis=x]`, or just the name for blocks without
+    /// properties.
+    pub fn state_name(&self, id: BlockId) -> String {
+        let mut name = self.name(id).to_owned();
+        let properties: Vec<_> = self
+            .properties(id)
+            .map(|(prop, value)| format!("{}={}", prop, value))
+            .collect();
+        if !properties.is_empty() {
+            name += &format!("[{}]", properties.join(","));
+        }
+        name
+    }
+
+    /// Parses a state written like `state_name` formats it. Properties that
+    /// aren't given keep the values they have in the default state.
+    pub fn parse_state(&self, text: &str) -> Option<BlockId> {
+        let (name, properties) = match text.find('[') {
+            Some(idx) if text.ends_with(']') => (&text[..idx], &text[idx + 1..text.len() - 1]),
+            Some(_) => return None,
+            None => (text, ""),
+        };
+
+        let mut id = self.get_id(name)?;
+        for pair in properties.split(',').filter(|pair| !pair.is_empty()) {
+            let mut parts = pair.splitn(2, '=');
+            let prop = parts.next()?.trim();
+            let value = parts.next()?.trim();
+            id = self.with_property(id, prop, value)?;
+        }
+        Some(id)
+    }
+
+    /// Whether the block is a full cube that can't be seen through.
     #[inline(always)]
     pub fn opaque(&self, id: BlockId) -> bool {
         self.opaque[id.0]
     }
 
+    /// Whether the block completely hides the face of a neighbor that is
+    /// touching `side` of it.
+    #[inline(always)]
+    pub fn occludes(&self, id: BlockId, side: Side) -> bool {
+        let faces = &self.occludes[id.0];
+        match side {
+            Side::Top => faces.top,
+            Side::Bottom => faces.bottom,
+            Side::Right => faces.right,
+            Side::Left => faces.left,
+            Side::Front => faces.front,
+            Side::Back => faces.back,
+        }
+    }
+
+    #[inline(always)]
+    pub fn model(&self, id: BlockId) -> &BlockModel {
+        &self.models[id.0]
+    }
+
+    /// Boxes that entities collide with, relative to the block's origin. This
+    /// is empty for blocks that aren't collidable.
+    #[inline(always)]
+    pub fn collision_boxes(&self, id: BlockId) -> &[Aabb3<f64>] {
+        &self.collision_boxes[id.0]
+    }
+
+    #[inline(always)]
+    pub fn render_layer(&self, id: BlockId) -> RenderLayer {
+        self.render_layers[id.0]
+    }
+
+    /// Whether faces between two blocks that are both in this state are
+    /// hidden.
+    #[inline(always)]
+    pub fn cull_same(&self, id: BlockId) -> bool {
+        self.cull_same[id.0]
+    }
+
+    /// Whether the block can be targeted with the crosshair.
+    #[inline(always)]
+    pub fn targetable(&self, id: BlockId) -> bool {
+        self.models[id.0] != BlockModel::None && !self.liquid[id.0]
+    }
+
     #[inline(always)]
     pub fn collidable(&self, id: BlockId) -> bool {
         self.collidable[id.0]
@@ -283,6 +1214,22 @@ pub struct RegistryRef<'r> {
 }
 
 impl<'r> RegistryRef<'r> {
+    pub fn name(&self) -> &'r str {
+        self.registry.name(self.id)
+    }
+
+    pub fn state_name(&self) -> String {
+        self.registry.state_name(self.id)
+    }
+
+    pub fn property(&self, name: &str) -> Option<&'r str> {
+        self.registry.property(self.id, name)
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.registry.has_tag(self.id, tag)
+    }
+
     #[inline(always)]
     pub fn opaque(&self) -> bool {
         self.registry.opaque(self.id)
@@ -293,6 +1240,16 @@ impl<'r> RegistryRef<'r> {
         self.registry.collidable(self.id)
     }
 
+    #[inline(always)]
+    pub fn collision_boxes(&self) -> &'r [Aabb3<f64>] {
+        self.registry.collision_boxes(self.id)
+    }
+
+    #[inline(always)]
+    pub fn targetable(&self) -> bool {
+        self.registry.targetable(self.id)
+    }
+
     #[inline(always)]
     pub fn liquid(&self) -> bool {
         self.registry.liquid(self.id)
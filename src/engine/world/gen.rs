@@ -1,47 +1,122 @@
-use engine::render::debug::{DebugAccumulator, Shape};
-use noise::{Fbm, MultiFractal, NoiseFn, RidgedMulti, SuperSimplex};
+use engine::{
+    render::debug::{DebugAccumulator, Shape},
+    replay::Replay,
+    settings::Settings,
+};
+use noise::{Fbm, MultiFractal, NoiseFn, RidgedMulti, Seedable, SuperSimplex};
 use specs::world::EntitiesRes;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use engine::prelude::*;
 
+/// The blocks that terrain is made of. Generators run on worker threads
+/// without access to the registry, so the blocks are looked up from
+/// `worldgen/*` tags up front.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TerrainBlocks {
+    pub stone: BlockId,
+    /// The layers between stone and the surface.
+    pub soil: BlockId,
+    pub surface: BlockId,
+    /// Used instead of soil and the surface near the water.
+    pub beach: BlockId,
+    pub liquid: BlockId,
+}
+
+/// The blocks of the bundled `blocks.json`, for generating terrain without a
+/// registry.
+impl Default for TerrainBlocks {
+    fn default() -> Self {
+        TerrainBlocks {
+            stone: block::STONE,
+            soil: block::DIRT,
+            surface: block::GRASS,
+            beach: block::SAND,
+            liquid: block::WATER,
+        }
+    }
+}
+
+impl TerrainBlocks {
+    /// Uses the first block with each tag. Tags that no block has fall back to
+    /// the block with the usual name, since the IDs of the default blocks
+    /// depend on the order of `blocks.json`.
+    pub fn from_registry(registry: &block::BlockRegistry) -> Self {
+        let pick = |tag: &str, name: &str| {
+            let mut tagged = registry.tagged(tag);
+            let id = tagged.next().unwrap_or_else(|| match registry.get_id(name) {
+                Some(id) => {
+                    warn!("No block has the tag `{}`, using `{}`", tag, name);
+                    id
+                }
+                None => {
+                    warn!("No block has the tag `{}` or is called `{}`", tag, name);
+                    block::AIR
+                }
+            });
+            if tagged.next().is_some() {
+                debug!("More than one block has the tag `{}`", tag);
+            }
+            id
+        };
+
+        TerrainBlocks {
+            stone: pick("worldgen/stone", "stone"),
+            soil: pick("worldgen/soil", "dirt"),
+            surface: pick("worldgen/surface", "grass"),
+            beach: pick("worldgen/beach", "sand"),
+            liquid: pick("worldgen/liquid", "water"),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct NoiseGenerator {
     noise: RidgedMulti,
     biome_noise: SuperSimplex,
+    blocks: TerrainBlocks,
 }
 
 impl NoiseGenerator {
-    pub fn new_default() -> Self {
+    pub fn new(seed: u32, blocks: TerrainBlocks) -> Self {
         let noise = RidgedMulti::default()
+            .set_seed(seed)
             .set_frequency(0.001)
             .set_lacunarity(4.0)
             // .set_attenuation(0.01)
             .set_persistence(0.7);
-        let biome_noise = SuperSimplex::new();
-        NoiseGenerator { noise, biome_noise }
+        let biome_noise = SuperSimplex::new().set_seed(seed);
+        NoiseGenerator {
+            noise,
+            biome_noise,
+            blocks,
+        }
+    }
+
+    pub fn new_default() -> Self {
+        NoiseGenerator::new(0, TerrainBlocks::default())
     }
 
     fn block_at(&self, x: f64, y: f64, z: f64) -> BlockId {
         let noise = 100.0 * self.noise.get([x, z]);
 
         if noise - 2.0 > y {
-            block::STONE
+            self.blocks.stone
         } else if noise - 1.0 > y {
             if y < -50.0 {
-                block::SAND
+                self.blocks.beach
             } else {
-                block::DIRT
+                self.blocks.soil
             }
         } else if noise > y {
             if y < -50.0 {
-                block::SAND
+                self.blocks.beach
             } else {
-                block::GRASS
+                self.blocks.surface
             }
         } else {
             if y < -52.0 {
-                block::WATER
+                self.blocks.liquid
             } else {
                 block::AIR
             }
@@ -70,6 +145,125 @@ impl job::Worker for NoiseGenerator {
     }
 }
 
+/// A completely flat world, which is handy for testing since nothing about
+/// it depends on the seed.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FlatGenerator {
+    blocks: TerrainBlocks,
+}
+
+impl FlatGenerator {
+    fn block_at(&self, y: i32) -> BlockId {
+        if y < -4 {
+            self.blocks.stone
+        } else if y < -1 {
+            self.blocks.soil
+        } else if y < 0 {
+            self.blocks.surface
+        } else {
+            block::AIR
+        }
+    }
+}
+
+impl job::Worker for FlatGenerator {
+    type Input = ChunkPos;
+    type Output = Chunk;
+
+    fn compute(&mut self, pos: &Self::Input) -> Self::Output {
+        let size = chunk::SIZE as i32;
+        let mut vec = Vec::with_capacity(chunk::VOLUME);
+        let base = pos.base().0;
+        for _ in 0..size {
+            for y in 0..size {
+                let block = self.block_at(base.y + y);
+                for _ in 0..size {
+                    vec.push(block);
+                }
+            }
+        }
+        Chunk::new(vec)
+    }
+}
+
+/// The kinds of terrain a world can be generated with.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeneratorKind {
+    Noise,
+    Flat,
+}
+
+impl Default for GeneratorKind {
+    fn default() -> Self {
+        GeneratorKind::Noise
+    }
+}
+
+impl ::std::str::FromStr for GeneratorKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "noise" => Ok(GeneratorKind::Noise),
+            "flat" => Ok(GeneratorKind::Flat),
+            _ => Err(format!("unknown generator `{}`, expected `noise` or `flat`", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum WorldGenerator {
+    Noise(NoiseGenerator),
+    Flat(FlatGenerator),
+}
+
+/// How far down and up the spawn column is searched for somewhere to stand.
+const SPAWN_SEARCH_MIN: i32 = -256;
+const SPAWN_SEARCH_MAX: i32 = 256;
+
+impl WorldGenerator {
+    pub fn new(kind: GeneratorKind, seed: u32, blocks: TerrainBlocks) -> Self {
+        match kind {
+            GeneratorKind::Noise => WorldGenerator::Noise(NoiseGenerator::new(seed, blocks)),
+            GeneratorKind::Flat => WorldGenerator::Flat(FlatGenerator { blocks }),
+        }
+    }
+
+    fn block_at(&self, pos: BlockPos) -> BlockId {
+        let pos = pos.0;
+        match self {
+            WorldGenerator::Noise(gen) => gen.block_at(pos.x as f64, pos.y as f64, pos.z as f64),
+            WorldGenerator::Flat(gen) => gen.block_at(pos.y),
+        }
+    }
+
+    /// Where players start in a new world and come back to after dying, which
+    /// is the lower of the first two air blocks above the ground in the
+    /// column at the origin. This is worked out from the generator, since the
+    /// chunks there might not have been generated yet. Where the ground is
+    /// under water, it is above the water instead.
+    pub fn spawn_block(&self) -> BlockPos {
+        let at = |y| BlockPos(Point3::new(0, y, 0));
+        let air = |y| self.block_at(at(y)) == block::AIR;
+        (SPAWN_SEARCH_MIN..SPAWN_SEARCH_MAX)
+            .find(|&y| !air(y - 1) && air(y) && air(y + 1))
+            .map_or(at(SPAWN_SEARCH_MAX), at)
+    }
+}
+
+impl job::Worker for WorldGenerator {
+    type Input = ChunkPos;
+    type Output = Chunk;
+
+    fn compute(&mut self, pos: &Self::Input) -> Self::Output {
+        match self {
+            WorldGenerator::Noise(gen) => gen.compute(pos),
+            WorldGenerator::Flat(gen) => gen.compute(pos),
+        }
+    }
+}
+
 use self::job::Worker;
 
 crate fn get_test_chunk() -> Chunk {
@@ -96,16 +290,16 @@ impl<'a> System<'a> for ChunkUnloader {
         ReadStorage<'a, comp::ChunkId>,
         ReadStorage<'a, comp::Player>,
         ReadStorage<'a, comp::Transform>,
-        Read<'a, res::ViewDistance>,
+        Read<'a, Settings>,
         Entities<'a>,
         ReadExpect<'a, DebugAccumulator>,
     );
 
     fn run(
         &mut self,
-        (mut world, mut marked, chunks, players, transforms, distance, entities, _debug): Self::SystemData,
+        (mut world, mut marked, chunks, players, transforms, settings, entities, _debug): Self::SystemData,
     ) {
-        let distance = distance.0;
+        let distance = Vector3::new(1, 1, 1) * settings.view_distance;
 
         for (entity, chunk, _) in (&entities, &chunks, &marked).join() {
             world.unload_chunk(chunk.0);
@@ -131,17 +325,20 @@ impl<'a> System<'a> for ChunkUnloader {
 }
 
 pub struct TerrainGenerator {
-    service: job::Service<NoiseGenerator>,
+    service: job::Service<WorldGenerator>,
     queue: HashSet<ChunkPos>,
+    /// Chunks that have been generated but not added to the world yet.
+    finished: HashMap<ChunkPos, Chunk>,
 }
 
 impl TerrainGenerator {
-    pub fn new() -> Self {
-        let service = job::Service::new("Chunk Generator", 4, NoiseGenerator::new_default());
+    pub fn new(generator: WorldGenerator) -> Self {
+        let service = job::Service::new("Chunk Generator", 4, generator);
 
         TerrainGenerator {
             service,
             queue: HashSet::default(),
+            finished: HashMap::default(),
         }
     }
 
@@ -163,6 +360,25 @@ impl TerrainGenerator {
     pub fn drain_finished_chunks(&mut self) -> impl Iterator<Item = (ChunkPos, Chunk)> + '_ {
         self.service.gather()
     }
+
+    /// Takes the chunk at `pos` out of the finished chunks, waiting for it to
+    /// be generated if it was requested but isn't done yet.
+    fn wait_for_chunk(&mut self, pos: ChunkPos) -> Option<Chunk> {
+        while !self.finished.contains_key(&pos) {
+            if !self.queue.contains(&pos) {
+                return None;
+            }
+
+            match self.service.wait() {
+                Some((finished, chunk)) => {
+                    self.finished.insert(finished, chunk);
+                }
+                None => return None,
+            }
+        }
+
+        self.finished.remove(&pos)
+    }
 }
 
 impl<'a> System<'a> for TerrainGenerator {
@@ -170,22 +386,33 @@ impl<'a> System<'a> for TerrainGenerator {
         WriteExpect<'a, VoxelWorld>,
         ReadStorage<'a, comp::Player>,
         ReadStorage<'a, comp::Transform>,
-        Read<'a, res::ViewDistance>,
+        Read<'a, Settings>,
         Read<'a, LazyUpdate>,
         Read<'a, EntitiesRes>,
         ReadExpect<'a, DebugAccumulator>,
+        Write<'a, res::PendingChunks>,
+        Write<'a, Replay>,
     );
 
     fn run(
         &mut self,
-        (mut voxel_world, players, transforms, view_distance, lazy, entity_res, debug): Self::SystemData,
+        (
+            mut voxel_world,
+            players,
+            transforms,
+            settings,
+            lazy,
+            entity_res,
+            debug,
+            mut pending,
+            mut replay,
+        ): Self::SystemData,
     ) {
-        let dist = view_distance.0;
         for (_, transform) in (&players, &transforms).join() {
             self.enqueue_radius(
                 &voxel_world,
                 WorldPos(transform.position).into(),
-                dist.x as usize,
+                settings.view_distance as usize,
             );
         }
 
@@ -195,6 +422,26 @@ impl<'a> System<'a> for TerrainGenerator {
         }
 
         for (pos, chunk) in self.service.gather() {
+            self.finished.insert(pos, chunk);
+        }
+
+        // when replaying, chunks have to be added on the same tick as they were
+        // in the recording, even if that means waiting for them.
+        let added = match replay.replayed_chunks() {
+            Some(added) => added,
+            None => self.finished.keys().cloned().collect(),
+        };
+
+        for pos in added {
+            let chunk = match self.wait_for_chunk(pos) {
+                Some(chunk) => chunk,
+                None => {
+                    warn!("Replay diverged: chunk {:?} was never requested", pos);
+                    continue;
+                }
+            };
+
+            replay.record_chunk(pos);
             section.draw(Shape::Chunk(2.0, pos, Vector4::new(0.0, 1.0, 0.0, 1.0)));
             voxel_world.set_chunk(pos, chunk);
             self.queue.remove(&pos);
@@ -203,5 +450,7 @@ impl<'a> System<'a> for TerrainGenerator {
                 .with(comp::Transform::default().with_position(pos.base().base().0))
                 .build();
         }
+
+        pending.0 = self.queue.len();
     }
 }
@@ -0,0 +1,70 @@
+use engine::{components::GameMode, world::gen::GeneratorKind};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{error::Error, fs, io, path::Path};
+
+/// The name of the file in a world directory that describes the world.
+pub const INFO_FILE: &str = "world.json";
+/// The name of the file in a world directory that the player is kept in.
+pub const PLAYER_FILE: &str = "player.json";
+
+/// Reads `file` from the world in `dir`, returning `None` if it doesn't exist.
+fn load_file<T: DeserializeOwned>(dir: &Path, file: &str) -> Result<Option<T>, Box<Error>> {
+    match fs::File::open(dir.join(file)) {
+        Ok(file) => Ok(Some(serde_json::from_reader(file)?)),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes `file` into the world in `dir`, creating the directory if needed.
+fn save_file<T: Serialize>(dir: &Path, file: &str, value: &T) -> Result<(), Box<Error>> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(file), serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+/// Everything needed to generate the same terrain again, which is stored in
+/// the world directory.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct WorldInfo {
+    pub seed: u32,
+    #[serde(default)]
+    pub generator: GeneratorKind,
+}
+
+impl WorldInfo {
+    /// Reads the info for the world in `dir`, returning `None` if the world
+    /// hasn't been created yet.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Option<Self>, Box<Error>> {
+        load_file(dir.as_ref(), INFO_FILE)
+    }
+
+    /// Writes the info into `dir`, creating the directory if needed.
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> Result<(), Box<Error>> {
+        save_file(dir.as_ref(), INFO_FILE, self)
+    }
+}
+
+/// Where the player was and how they were playing when the game was closed.
+#[derive(Copy, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct PlayerInfo {
+    pub position: [f64; 3],
+    #[serde(default)]
+    pub game_mode: GameMode,
+    /// Whether a creative player was flying.
+    #[serde(default)]
+    pub flying: bool,
+}
+
+impl PlayerInfo {
+    /// Reads the player for the world in `dir`, returning `None` if the
+    /// player hasn't been saved yet.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Option<Self>, Box<Error>> {
+        load_file(dir.as_ref(), PLAYER_FILE)
+    }
+
+    /// Writes the player into `dir`, creating the directory if needed.
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> Result<(), Box<Error>> {
+        save_file(dir.as_ref(), PLAYER_FILE, self)
+    }
+}
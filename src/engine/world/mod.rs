@@ -12,6 +12,9 @@ pub use self::chunk::Chunk;
 pub mod block;
 pub mod chunk;
 pub mod gen;
+pub mod info;
+pub mod path;
+pub mod spatial;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct ChunkPos(pub Point3<i32>);
@@ -107,6 +110,8 @@ fn chunk_id(pos: ChunkPos) -> u64 {
 pub struct VoxelWorld {
     chunks: HashMap<ChunkPos, ChunkType>,
     dirty_mesh: HashSet<ChunkPos>,
+    /// Blocks that were replaced since the last `drain_changed_blocks`.
+    changed_blocks: Vec<BlockPos>,
     registry: BlockRegistry,
 }
 
@@ -151,6 +156,7 @@ impl VoxelWorld {
         VoxelWorld {
             chunks: Default::default(),
             dirty_mesh: Default::default(),
+            changed_blocks: Default::default(),
             registry,
         }
     }
@@ -159,6 +165,13 @@ impl VoxelWorld {
         &self.registry
     }
 
+    /// Swaps out the block registry and remeshes every chunk. Blocks keep
+    /// their IDs, so the new registry should only change or add blocks.
+    pub fn set_registry(&mut self, registry: BlockRegistry) {
+        self.registry = registry;
+        self.dirty_mesh.extend(self.chunks.keys().cloned());
+    }
+
     pub fn unload_chunk(&mut self, pos: ChunkPos) {
         self.chunks.remove(&pos);
     }
@@ -210,6 +223,7 @@ impl VoxelWorld {
         }
 
         self.mark_neighborhood_dirty(pos);
+        self.changed_blocks.push(pos);
         self.chunks.get_mut(&chunk_pos).map(|chunk| match chunk {
             ChunkType::Array(chunk) => mem::replace(&mut chunk[block_pos], block),
             // We always expand the chunk or exit early by this point
@@ -217,6 +231,12 @@ impl VoxelWorld {
         })
     }
 
+    /// Every block that was replaced with `set_block_id` since this was last
+    /// called, so that anything that depends on the blocks can be updated.
+    pub fn drain_changed_blocks(&mut self) -> impl Iterator<Item = BlockPos> + '_ {
+        self.changed_blocks.drain(..)
+    }
+
     pub fn get_block_id(&self, pos: BlockPos) -> Option<BlockId> {
         let (chunk_pos, block_pos) = pos.chunk_pos_offset();
         self.chunks.get(&chunk_pos).map(|chunk| match chunk {
@@ -229,6 +249,26 @@ impl VoxelWorld {
         self.get_block_id(pos).map(|id| self.registry.get_ref(id))
     }
 
+    /// The value of a property of the block at `pos`, if the block is loaded
+    /// and has that property.
+    pub fn get_block_property(&self, pos: BlockPos, name: &str) -> Option<&str> {
+        self.get_block_id(pos).and_then(|id| self.registry.property(id, name))
+    }
+
+    /// Changes a single property of the block at `pos`, returning the new
+    /// state if the block is loaded and has that property value.
+    pub fn set_block_property(
+        &mut self,
+        pos: BlockPos,
+        name: &str,
+        value: &str,
+    ) -> Option<BlockId> {
+        let id = self.get_block_id(pos)?;
+        let new_id = self.registry.with_property(id, name, value)?;
+        self.set_block_id(pos, new_id);
+        Some(new_id)
+    }
+
     pub fn get_dirty_chunk(&mut self) -> Option<ChunkPos> {
         self.dirty_mesh
             .iter()
@@ -247,6 +287,14 @@ impl VoxelWorld {
             .cloned()
     }
 
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn dirty_chunk_count(&self) -> usize {
+        self.dirty_mesh.len()
+    }
+
     pub fn clean_chunk(&mut self, pos: ChunkPos) {
         self.dirty_mesh.remove(&pos);
     }
@@ -265,7 +313,7 @@ impl VoxelWorld {
             ret_norm = norm;
             debug.draw(Shape::Block(1.0, pos, Vector4::new(1.0, 0.0, 1.0, 1.0)));
             self.registry(pos)
-                .map(|props| props.opaque())
+                .map(|props| props.targetable())
                 .unwrap_or(false)
         }) {
             Some((ret_pos, ret_norm))
@@ -281,11 +329,8 @@ mod benches {
 
     #[bench]
     fn bench_world_get(b: &mut Bencher) {
-        let mut world = VoxelWorld::new(
-            BlockRegistry::load_from_file("resources/blocks.json")
-                .unwrap()
-                .0,
-        );
+        let mut world =
+            VoxelWorld::new(BlockRegistry::load_from_file("resources/blocks.json").unwrap());
         world.set_chunk(ChunkPos(Point3::new(0, 0, 0)), super::gen::get_test_chunk());
         b.iter(|| {
             for x in -2..34 {
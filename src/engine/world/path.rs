@@ -0,0 +1,390 @@
+use cgmath::{Point3, Vector3};
+use collision::Aabb3;
+use engine::{
+    job::Worker,
+    world::{BlockPos, VoxelWorld},
+};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// The most cells a single search looks at before giving up, so that goals
+/// that can't be reached don't take forever.
+const MAX_SEARCHED_CELLS: usize = 8192;
+/// How far below the goal the search looks for ground to stand on, for goals
+/// that are in the air, like a player that is jumping.
+const MAX_GOAL_DROP: i32 = 8;
+
+// costs are in quarter blocks, so that they can be compared exactly
+const WALK_COST: u32 = 4;
+/// Extra cost for jumping up a block, since it is slower than walking.
+const JUMP_COST: u32 = 2;
+/// Extra cost for each block fallen.
+const FALL_COST: u32 = 1;
+
+/// What a body can get over while following a path.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PathLimits {
+    /// How many blocks tall the body is, rounded up.
+    pub height: i32,
+    /// How many blocks high the body can jump up.
+    pub step_height: i32,
+    /// How many blocks the body can drop down.
+    pub max_fall: i32,
+}
+
+impl PathLimits {
+    pub fn for_body(aabb: &Aabb3<f64>, step_height: i32, max_fall: i32) -> Self {
+        PathLimits {
+            height: (aabb.max.y - aabb.min.y).ceil() as i32,
+            step_height,
+            max_fall,
+        }
+    }
+}
+
+/// Which blocks in a box of the world can't be walked through, copied out of
+/// the world so that it can be searched on another thread.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavGrid {
+    min: BlockPos,
+    size: Vector3<i32>,
+    solid: Vec<bool>,
+}
+
+impl NavGrid {
+    /// Copies the box from `min` to `max`, including both. Blocks that
+    /// aren't loaded count as solid, so that paths don't go through them.
+    pub fn capture(world: &VoxelWorld, min: BlockPos, max: BlockPos) -> Self {
+        let size = max.0 - min.0 + Vector3::new(1, 1, 1);
+        let mut solid = Vec::with_capacity((size.x * size.y * size.z) as usize);
+        for x in min.0.x..=max.0.x {
+            for y in min.0.y..=max.0.y {
+                for z in min.0.z..=max.0.z {
+                    let props = world.registry(BlockPos(Point3::new(x, y, z)));
+                    solid.push(props.map_or(true, |props| props.collidable()));
+                }
+            }
+        }
+
+        NavGrid { min, size, solid }
+    }
+
+    pub fn contains(&self, pos: BlockPos) -> bool {
+        let rel = pos.0 - self.min.0;
+        rel.x >= 0
+            && rel.y >= 0
+            && rel.z >= 0
+            && rel.x < self.size.x
+            && rel.y < self.size.y
+            && rel.z < self.size.z
+    }
+
+    /// Whether a block can't be walked through. Everything outside of the
+    /// grid is solid.
+    pub fn is_solid(&self, pos: BlockPos) -> bool {
+        if !self.contains(pos) {
+            return true;
+        }
+        let rel = pos.0 - self.min.0;
+        self.solid[((rel.x * self.size.y + rel.y) * self.size.z + rel.z) as usize]
+    }
+
+    /// Whether there is room for a body `height` blocks tall with its feet in
+    /// `pos`.
+    pub fn is_clear(&self, pos: BlockPos, height: i32) -> bool {
+        (0..height).all(|dy| !self.is_solid(pos.offset((0, dy, 0))))
+    }
+
+    /// Whether a body `height` blocks tall can stand with its feet in `pos`.
+    pub fn can_stand(&self, pos: BlockPos, height: i32) -> bool {
+        self.is_solid(pos.offset((0, -1, 0))) && self.is_clear(pos, height)
+    }
+}
+
+/// A path to find between two blocks, which are where the body's feet are.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathRequest {
+    pub start: BlockPos,
+    pub goal: BlockPos,
+    pub limits: PathLimits,
+    pub grid: NavGrid,
+}
+
+/// The blocks a body's feet go through to get from the start of a path to the
+/// goal, including both.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Path {
+    pub cells: Vec<BlockPos>,
+    /// The limits the path was found with.
+    pub limits: PathLimits,
+}
+
+impl Path {
+    /// Whether changing the block at `pos` could stop the path from working,
+    /// because it is somewhere the body walks through, jumps through or
+    /// stands on.
+    pub fn is_affected_by(&self, pos: BlockPos) -> bool {
+        let limits = self.limits;
+        let above = limits.height + limits.step_height.max(limits.max_fall);
+        self.cells.iter().any(|cell| {
+            cell.0.x == pos.0.x
+                && cell.0.z == pos.0.z
+                && pos.0.y >= cell.0.y - 1
+                && pos.0.y < cell.0.y + above
+        })
+    }
+}
+
+/// The cells that can be reached in one move from `pos`, and what it costs to
+/// get to them.
+fn moves(grid: &NavGrid, pos: BlockPos, limits: PathLimits, out: &mut Vec<(BlockPos, u32)>) {
+    let height = limits.height;
+    for &(dx, dz) in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
+        let side = pos.offset((dx, 0, dz));
+        if grid.can_stand(side, height) {
+            out.push((side, WALK_COST));
+            continue;
+        }
+
+        if !grid.is_clear(side, height) {
+            for dy in 1..=limits.step_height {
+                // there has to be room to jump straight up first
+                if !grid.is_clear(pos.offset((0, dy, 0)), height) {
+                    break;
+                }
+                let up = side.offset((0, dy, 0));
+                if grid.can_stand(up, height) {
+                    out.push((up, WALK_COST + JUMP_COST));
+                    break;
+                }
+            }
+            continue;
+        }
+
+        // walking off an edge
+        for dy in 1..=limits.max_fall {
+            let down = side.offset((0, -dy, 0));
+            if grid.is_solid(down) {
+                break;
+            }
+            if grid.can_stand(down, height) {
+                out.push((down, WALK_COST + FALL_COST * dy as u32));
+                break;
+            }
+        }
+    }
+}
+
+/// A guess at the cost from `from` to `to` that is never too high, which keeps
+/// A* finding the cheapest path.
+fn estimate(from: BlockPos, to: BlockPos) -> u32 {
+    let diff = to.0 - from.0;
+    WALK_COST * (diff.x.abs() + diff.z.abs()) as u32
+}
+
+/// Finds the cheapest path with A*. The goal is moved down onto the ground if
+/// it is in the air. Returns `None` if there is no way to get there inside the
+/// grid.
+pub fn find_path(request: &PathRequest) -> Option<Path> {
+    let PathRequest {
+        start,
+        goal,
+        limits,
+        ref grid,
+    } = *request;
+
+    let goal = (0..=MAX_GOAL_DROP)
+        .map(|dy| goal.offset((0, -dy, 0)))
+        .take_while(|&pos| !grid.is_solid(pos))
+        .find(|&pos| grid.can_stand(pos, limits.height))?;
+
+    // cells that have been reached, with the cheapest known cost to get there
+    // and the cell they were reached from
+    let mut reached: HashMap<BlockPos, (u32, BlockPos)> = HashMap::new();
+    let mut cells = vec![start];
+    let mut open = BinaryHeap::new();
+    let mut neighbours = Vec::new();
+
+    reached.insert(start, (0, start));
+    open.push(Reverse((estimate(start, goal), 0, 0)));
+
+    while let Some(Reverse((_, cost, idx))) = open.pop() {
+        let pos = cells[idx];
+        if pos == goal {
+            let mut path = vec![goal];
+            let mut pos = goal;
+            while pos != start {
+                pos = reached[&pos].1;
+                path.push(pos);
+            }
+            path.reverse();
+            return Some(Path {
+                cells: path,
+                limits,
+            });
+        }
+
+        // skip cells that were found again more cheaply after being queued
+        if cost > reached[&pos].0 {
+            continue;
+        }
+        if cells.len() > MAX_SEARCHED_CELLS {
+            return None;
+        }
+
+        neighbours.clear();
+        moves(grid, pos, limits, &mut neighbours);
+        for &(next, step) in &neighbours {
+            let next_cost = cost + step;
+            if reached
+                .get(&next)
+                .map_or(false, |&(known, _)| known <= next_cost)
+            {
+                continue;
+            }
+            reached.insert(next, (next_cost, pos));
+            cells.push(next);
+            open.push(Reverse((
+                next_cost + estimate(next, goal),
+                next_cost,
+                cells.len() - 1,
+            )));
+        }
+    }
+
+    None
+}
+
+/// Finds paths for a `job::Service`, so that searches don't hold up the
+/// game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct Pathfinder;
+
+impl Worker for Pathfinder {
+    type Input = PathRequest;
+    type Output = Option<Path>;
+
+    fn compute(&mut self, request: &PathRequest) -> Option<Path> {
+        find_path(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: PathLimits = PathLimits {
+        height: 2,
+        step_height: 1,
+        max_fall: 3,
+    };
+
+    fn pos(x: i32, y: i32, z: i32) -> BlockPos {
+        BlockPos(Point3::new(x, y, z))
+    }
+
+    /// A grid `size` blocks wide and 6 high, with a floor at y = 0.
+    fn flat_grid(size: i32) -> NavGrid {
+        let mut grid = NavGrid {
+            min: pos(0, 0, 0),
+            size: Vector3::new(size, 6, size),
+            solid: vec![false; (size * 6 * size) as usize],
+        };
+        for x in 0..size {
+            for z in 0..size {
+                set_solid(&mut grid, pos(x, 0, z));
+            }
+        }
+        grid
+    }
+
+    fn set_solid(grid: &mut NavGrid, pos: BlockPos) {
+        let rel = pos.0 - grid.min.0;
+        grid.solid[((rel.x * grid.size.y + rel.y) * grid.size.z + rel.z) as usize] = true;
+    }
+
+    /// A wall across the whole grid at `x`, `height` blocks high.
+    fn add_wall(grid: &mut NavGrid, x: i32, height: i32) {
+        for z in 0..grid.size.z {
+            for y in 1..=height {
+                set_solid(grid, pos(x, y, z));
+            }
+        }
+    }
+
+    fn request(grid: NavGrid, start: BlockPos, goal: BlockPos) -> PathRequest {
+        PathRequest {
+            start,
+            goal,
+            limits: LIMITS,
+            grid,
+        }
+    }
+
+    #[test]
+    fn outside_of_the_grid_is_solid() {
+        let grid = flat_grid(4);
+        assert!(grid.is_solid(pos(1, 0, 1)));
+        assert!(!grid.is_solid(pos(1, 1, 1)));
+        assert!(grid.is_solid(pos(-1, 1, 1)));
+        assert!(grid.is_solid(pos(1, 6, 1)));
+        assert!(grid.can_stand(pos(1, 1, 1), 2));
+        assert!(!grid.can_stand(pos(1, 2, 1), 2));
+    }
+
+    #[test]
+    fn straight_path_on_flat_ground() {
+        let path = find_path(&request(flat_grid(8), pos(1, 1, 1), pos(5, 1, 1))).unwrap();
+        let expected: Vec<_> = (1..=5).map(|x| pos(x, 1, 1)).collect();
+        assert_eq!(path.cells, expected);
+        assert_eq!(path.limits, LIMITS);
+    }
+
+    #[test]
+    fn goal_in_the_air_is_moved_to_the_ground() {
+        let path = find_path(&request(flat_grid(8), pos(1, 1, 1), pos(5, 4, 1))).unwrap();
+        assert_eq!(path.cells.last(), Some(&pos(5, 1, 1)));
+    }
+
+    #[test]
+    fn jumps_over_low_walls() {
+        let mut grid = flat_grid(8);
+        add_wall(&mut grid, 3, 1);
+        let path = find_path(&request(grid, pos(1, 1, 1), pos(5, 1, 1))).unwrap();
+        assert!(path.cells.contains(&pos(3, 2, 1)));
+        assert_eq!(path.cells.last(), Some(&pos(5, 1, 1)));
+    }
+
+    #[test]
+    fn no_path_over_high_walls() {
+        let mut grid = flat_grid(8);
+        add_wall(&mut grid, 3, 2);
+        assert_eq!(find_path(&request(grid, pos(1, 1, 1), pos(5, 1, 1))), None);
+    }
+
+    #[test]
+    fn walks_around_high_obstacles() {
+        let mut grid = flat_grid(8);
+        // a pillar that is too high to jump onto
+        for y in 1..=2 {
+            set_solid(&mut grid, pos(3, y, 1));
+        }
+        let path = find_path(&request(grid, pos(1, 1, 1), pos(5, 1, 1))).unwrap();
+        assert!(path.cells.iter().all(|cell| cell.0.y == 1));
+        assert_eq!(path.cells.len(), 7);
+    }
+
+    #[test]
+    fn affected_by_blocks_along_the_path() {
+        let path = find_path(&request(flat_grid(8), pos(1, 1, 1), pos(3, 1, 1))).unwrap();
+        // the ground under it, and the room above it to jump or fall through
+        assert!(path.is_affected_by(pos(2, 0, 1)));
+        assert!(path.is_affected_by(pos(2, 1, 1)));
+        assert!(path.is_affected_by(pos(2, 5, 1)));
+        assert!(!path.is_affected_by(pos(2, 6, 1)));
+        assert!(!path.is_affected_by(pos(2, 1, 2)));
+        assert!(!path.is_affected_by(pos(5, 1, 1)));
+    }
+}
@@ -0,0 +1,325 @@
+use cgmath::{prelude::*, Point3, Vector3};
+use collision::{prelude::*, Aabb3, Ray3};
+use engine::{
+    render::debug::DebugSection,
+    world::{block::BlockModel, BlockPos, VoxelWorld},
+};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// The width of a cell of the spatial hash, in blocks. Most entities fit in a
+/// single cell, and queries only have to look at a few.
+const CELL_SIZE: f64 = 4.0;
+
+fn cell_of(pos: Point3<f64>) -> Point3<i32> {
+    pos.map(|c| (c / CELL_SIZE).floor() as i32)
+}
+
+/// Every cell that `aabb` touches.
+fn cells_in(aabb: Aabb3<f64>) -> impl Iterator<Item = Point3<i32>> {
+    let (min, max) = (cell_of(aabb.min), cell_of(aabb.max));
+    (min.x..=max.x).flat_map(move |x| {
+        (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| Point3::new(x, y, z)))
+    })
+}
+
+/// How far along `ray` it first hits `aabb`, or `None` if it misses. Rays that
+/// start inside of the box hit it straight away.
+pub fn ray_hits_aabb(ray: &Ray3<f64>, aabb: &Aabb3<f64>) -> Option<f64> {
+    let (mut near, mut far) = (0.0f64, ::std::f64::INFINITY);
+    for axis in 0..3 {
+        let (origin, dir) = (ray.origin[axis], ray.direction[axis]);
+        if dir == 0.0 {
+            // parallel to the sides, so it has to be between them already
+            if origin < aabb.min[axis] || origin > aabb.max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let to_min = (aabb.min[axis] - origin) / dir;
+        let to_max = (aabb.max[axis] - origin) / dir;
+        near = near.max(to_min.min(to_max));
+        far = far.min(to_min.max(to_max));
+        if near > far {
+            return None;
+        }
+    }
+
+    Some(near)
+}
+
+/// The collision box of every entity that can be touched, bucketed into the
+/// cells of a spatial hash, so that the entities around a point can be found
+/// without looking at all of them. Physics rebuilds it every tick after moving
+/// bodies, so it is up to date for any system that runs after physics.
+#[derive(Clone, Debug, Default)]
+pub struct EntityIndex {
+    entries: Vec<(Entity, Aabb3<f64>)>,
+    cells: HashMap<Point3<i32>, Vec<usize>>,
+}
+
+impl EntityIndex {
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.cells.clear();
+    }
+
+    /// Adds an entity with its collision box in world space.
+    pub fn insert(&mut self, entity: Entity, aabb: Aabb3<f64>) {
+        let idx = self.entries.len();
+        self.entries.push((entity, aabb));
+        for cell in cells_in(aabb) {
+            self.cells.entry(cell).or_insert_with(Vec::new).push(idx);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entries in every cell that `aabb` touches, each only once.
+    fn candidates(&self, aabb: Aabb3<f64>) -> Vec<usize> {
+        let mut found: Vec<usize> = cells_in(aabb)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flat_map(|entries| entries.iter().cloned())
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// The entities whose boxes overlap `aabb`.
+    pub fn intersecting(&self, aabb: Aabb3<f64>) -> Vec<(Entity, Aabb3<f64>)> {
+        self.candidates(aabb)
+            .into_iter()
+            .map(|idx| self.entries[idx])
+            .filter(|(_, other)| other.intersects(&aabb))
+            .collect()
+    }
+
+    /// The entities with some part of their box within `radius` of `center`.
+    pub fn within(&self, center: Point3<f64>, radius: f64) -> Vec<(Entity, Aabb3<f64>)> {
+        let reach = Vector3::new(radius, radius, radius);
+        let bounds = Aabb3::new(center - reach, center + reach);
+        self.candidates(bounds)
+            .into_iter()
+            .map(|idx| self.entries[idx])
+            .filter(|(_, aabb)| {
+                // the closest point of the box to the center
+                let closest = Point3::new(
+                    ::util::clamp(center.x, aabb.min.x, aabb.max.x),
+                    ::util::clamp(center.y, aabb.min.y, aabb.max.y),
+                    ::util::clamp(center.z, aabb.min.z, aabb.max.z),
+                );
+                (closest - center).magnitude2() <= radius * radius
+            })
+            .collect()
+    }
+
+    /// Every pair of entities whose boxes overlap, each pair only once.
+    pub fn overlapping_pairs(&self) -> Vec<((Entity, Aabb3<f64>), (Entity, Aabb3<f64>))> {
+        let mut pairs = Vec::new();
+        for (idx, &(entity, aabb)) in self.entries.iter().enumerate() {
+            for other in self.candidates(aabb) {
+                // the other entity finds this pair when it is the lower one
+                if other <= idx {
+                    continue;
+                }
+                let (other_entity, other_aabb) = self.entries[other];
+                if aabb.intersects(&other_aabb) {
+                    pairs.push(((entity, aabb), (other_entity, other_aabb)));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// The closest entity that `ray` hits within `radius` blocks, along with
+    /// how far away it is. Only entities that `filter` accepts can be hit,
+    /// which is useful for leaving out entities that can't be targeted, or
+    /// the one a ray starts inside of, like a player's line of sight.
+    pub fn trace(
+        &self,
+        ray: Ray3<f64>,
+        radius: f64,
+        filter: impl Fn(Entity) -> bool,
+    ) -> Option<(Entity, f64)> {
+        let end = ray.origin + ray.direction * radius;
+        let bounds = Aabb3::new(ray.origin, end);
+        self.candidates(bounds)
+            .into_iter()
+            .map(|idx| self.entries[idx])
+            .filter(|&(entity, _)| filter(entity))
+            .filter_map(|(entity, aabb)| Some((entity, ray_hits_aabb(&ray, &aabb)?)))
+            .filter(|&(_, distance)| distance <= radius)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(::std::cmp::Ordering::Equal))
+    }
+}
+
+/// What a ray hit first.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RayHit {
+    /// A targetable block, and the normal of the face that was hit, which is
+    /// `None` when the ray starts inside of the block.
+    Block(BlockPos, Option<Vector3<i32>>),
+    Entity(Entity),
+}
+
+/// How far along `ray` it first hits the shape of the block at `pos`. Blocks
+/// are hit by their collision boxes, or by their model for blocks that can be
+/// walked through, and anything else is hit anywhere in its cell.
+fn ray_hits_block(world: &VoxelWorld, ray: &Ray3<f64>, pos: BlockPos) -> Option<f64> {
+    let id = world.get_block_id(pos)?;
+    let registry = world.get_registry();
+    let mut boxes = registry.collision_boxes(id).to_vec();
+    if boxes.is_empty() {
+        boxes = match registry.model(id) {
+            BlockModel::Boxes(boxes) => boxes.iter().map(|b| cast_aabb(*b)).collect(),
+            _ => vec![Aabb3::new(
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 1.0, 1.0),
+            )],
+        };
+    }
+
+    let offset = pos.base().0.to_vec();
+    boxes
+        .iter()
+        .filter_map(|b| ray_hits_aabb(ray, &b.add_v(offset)))
+        .min_by(|a, b| a.partial_cmp(b).unwrap_or(::std::cmp::Ordering::Equal))
+}
+
+fn cast_aabb(aabb: Aabb3<f32>) -> Aabb3<f64> {
+    Aabb3::new(aabb.min.cast().unwrap(), aabb.max.cast().unwrap())
+}
+
+/// Traces `ray` through both the blocks of the world and the entities in the
+/// index that `filter` accepts, returning whichever it hits first within
+/// `radius` blocks.
+pub fn trace(
+    world: &VoxelWorld,
+    index: &EntityIndex,
+    ray: Ray3<f64>,
+    radius: f64,
+    filter: impl Fn(Entity) -> bool,
+    debug: &mut DebugSection,
+) -> Option<RayHit> {
+    let block = world.trace_block(ray, radius, debug);
+    let entity = index.trace(ray, radius, filter);
+
+    match (block, entity) {
+        (Some((pos, normal)), Some((entity, distance))) => match ray_hits_block(world, &ray, pos) {
+            Some(block_distance) if block_distance <= distance => Some(RayHit::Block(pos, normal)),
+            _ => Some(RayHit::Entity(entity)),
+        },
+        (Some((pos, normal)), None) => Some(RayHit::Block(pos, normal)),
+        (None, Some((entity, _))) => Some(RayHit::Entity(entity)),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aabb(min: [f64; 3], max: [f64; 3]) -> Aabb3<f64> {
+        Aabb3::new(Point3::from(min), Point3::from(max))
+    }
+
+    fn entities(count: usize) -> Vec<Entity> {
+        let mut world = World::new();
+        (0..count).map(|_| world.create_entity().build()).collect()
+    }
+
+    fn found(mut hits: Vec<(Entity, Aabb3<f64>)>) -> Vec<Entity> {
+        hits.sort_by_key(|&(entity, _)| entity.id());
+        hits.into_iter().map(|(entity, _)| entity).collect()
+    }
+
+    #[test]
+    fn ray_hits_aabb_distance() {
+        let ray = Ray3::new(Point3::new(0.0, 0.5, 0.5), Vector3::new(1.0, 0.0, 0.0));
+        let target = aabb([2.0, 0.0, 0.0], [3.0, 1.0, 1.0]);
+        assert_eq!(ray_hits_aabb(&ray, &target), Some(2.0));
+
+        let behind = aabb([-3.0, 0.0, 0.0], [-2.0, 1.0, 1.0]);
+        assert_eq!(ray_hits_aabb(&ray, &behind), None);
+        let beside = aabb([2.0, 2.0, 0.0], [3.0, 3.0, 1.0]);
+        assert_eq!(ray_hits_aabb(&ray, &beside), None);
+        let around = aabb([-1.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+        assert_eq!(ray_hits_aabb(&ray, &around), Some(0.0));
+    }
+
+    #[test]
+    fn intersecting_across_cells() {
+        let ents = entities(3);
+        let mut index = EntityIndex::default();
+        // spans several cells, so it must only be found once
+        index.insert(ents[0], aabb([-5.0, 0.0, -5.0], [5.0, 2.0, 5.0]));
+        index.insert(ents[1], aabb([3.0, 0.0, 3.0], [4.0, 2.0, 4.0]));
+        index.insert(ents[2], aabb([20.0, 0.0, 20.0], [21.0, 2.0, 21.0]));
+        assert_eq!(index.len(), 3);
+
+        let query = aabb([3.5, 1.0, 3.5], [6.0, 3.0, 6.0]);
+        assert_eq!(found(index.intersecting(query)), [ents[0], ents[1]]);
+
+        index.clear();
+        assert!(index.is_empty());
+        assert!(index.intersecting(query).is_empty());
+    }
+
+    #[test]
+    fn within_uses_the_closest_point() {
+        let ents = entities(2);
+        let mut index = EntityIndex::default();
+        index.insert(ents[0], aabb([2.0, 0.0, 0.0], [3.0, 1.0, 1.0]));
+        index.insert(ents[1], aabb([2.0, 2.0, 2.0], [3.0, 3.0, 3.0]));
+
+        let center = Point3::new(0.0, 0.5, 0.5);
+        assert_eq!(found(index.within(center, 2.0)), [ents[0]]);
+        assert!(index.within(center, 1.5).is_empty());
+    }
+
+    #[test]
+    fn overlapping_pairs_once() {
+        let ents = entities(3);
+        let mut index = EntityIndex::default();
+        index.insert(ents[0], aabb([0.0, 0.0, 0.0], [5.0, 1.0, 1.0]));
+        index.insert(ents[1], aabb([4.5, 0.0, 0.0], [6.0, 1.0, 1.0]));
+        index.insert(ents[2], aabb([10.0, 0.0, 0.0], [11.0, 1.0, 1.0]));
+
+        let pairs: Vec<_> = index
+            .overlapping_pairs()
+            .into_iter()
+            .map(|((a, _), (b, _))| (a, b))
+            .collect();
+        assert_eq!(pairs, [(ents[0], ents[1])]);
+    }
+
+    #[test]
+    fn trace_finds_the_closest_accepted_entity() {
+        let ents = entities(3);
+        let mut index = EntityIndex::default();
+        index.insert(ents[0], aabb([-0.5, 0.0, -0.5], [0.5, 2.0, 0.5]));
+        index.insert(ents[1], aabb([3.0, 0.0, -0.5], [4.0, 2.0, 0.5]));
+        index.insert(ents[2], aabb([6.0, 0.0, -0.5], [7.0, 2.0, 0.5]));
+
+        // starts inside the first entity, like a player looking out
+        let ray = Ray3::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(index.trace(ray, 10.0, |_| true), Some((ents[0], 0.0)));
+        assert_eq!(
+            index.trace(ray, 10.0, |entity| entity != ents[0]),
+            Some((ents[1], 3.0))
+        );
+        assert_eq!(
+            index.trace(ray, 10.0, |entity| entity == ents[2]),
+            Some((ents[2], 6.0))
+        );
+        assert_eq!(index.trace(ray, 5.0, |entity| entity == ents[2]), None);
+    }
+}
@@ -4,7 +4,7 @@ use gl_api::{
     layout::Layout,
     limits::Limits,
     shader::program::Program,
-    vertex_array::VertexArray,
+    vertex_array::{InstancedVertexArray, VertexArray},
     BufferIndex, PrimitiveType,
 };
 use glutin::GlWindow;
@@ -23,15 +23,16 @@ lazy_static::lazy_static! {
     pub(crate) static ref TEXTURE_DROP_LIST: Mutex<Vec<u32>> = Mutex::new(vec![]);
     pub(crate) static ref PROGRAM_DROP_LIST: Mutex<Vec<u32>> = Mutex::new(vec![]);
     pub(crate) static ref SHADER_DROP_LIST: Mutex<Vec<u32>> = Mutex::new(vec![]);
+    pub(crate) static ref FRAMEBUFFER_DROP_LIST: Mutex<Vec<u32>> = Mutex::new(vec![]);
 }
 
-crate struct Entry<'v, V>(
+crate struct Entry<'v, A>(
     ::std::collections::hash_map::Entry<'v, TypeId, Box<dyn Any>>,
-    PhantomData<*const V>,
+    PhantomData<*const A>,
 );
 
-impl<'v, V: 'static> Entry<'v, V> {
-    fn or_insert_with<F: FnOnce() -> VertexArray<V>>(self, func: F) -> &'v mut VertexArray<V> {
+impl<'v, A: 'static> Entry<'v, A> {
+    fn or_insert_with<F: FnOnce() -> A>(self, func: F) -> &'v mut A {
         self.0
             .or_insert_with(|| Box::new(func()))
             .downcast_mut()
@@ -51,8 +52,9 @@ impl VaoMap {
         }
     }
 
-    crate fn entry<'v, V: Any>(&'v mut self) -> Entry<'v, V> {
-        Entry(self.table.entry(TypeId::of::<V>()), PhantomData)
+    /// The vertex array of type `A`, which describes a vertex format.
+    crate fn entry<'v, A: Any>(&'v mut self) -> Entry<'v, A> {
+        Entry(self.table.entry(TypeId::of::<A>()), PhantomData)
     }
 }
 
@@ -139,6 +141,9 @@ impl Context {
         for id in SHADER_DROP_LIST.lock().unwrap().drain(..) {
             gl_call!(debug DeleteShader(id));
         }
+        for id in FRAMEBUFFER_DROP_LIST.lock().unwrap().drain(..) {
+            gl_call!(debug DeleteFramebuffers(1, &id));
+        }
     }
 
     // self.ctx.draw_elements(gl::TRIANGLES, &self.vertices, &self.indices);
@@ -155,7 +160,7 @@ impl Context {
             // Find a VAO that describes our vertex format, creating one if it does not
             // exist.
             let mut map = self.0.format_cache.borrow_mut();
-            let vao = map.entry::<V>().or_insert_with(|| {
+            let vao = map.entry::<VertexArray<V>>().or_insert_with(|| {
                 VertexArray::<V>::for_vertex_type(self).with_buffer(self, vertices)
             });
 
@@ -186,7 +191,7 @@ impl Context {
             // Find a VAO that describes our vertex format, creating one if it does not
             // exist.
             let mut map = self.0.format_cache.borrow_mut();
-            let vao = map.entry::<V>().or_insert_with(|| {
+            let vao = map.entry::<VertexArray<V>>().or_insert_with(|| {
                 VertexArray::<V>::for_vertex_type(self).with_buffer(self, vertices)
             });
 
@@ -202,6 +207,34 @@ impl Context {
         }
     }
 
+    /// Draws `vertices` once for every element of `instances`.
+    pub fn draw_arrays_instanced<V: Layout, I: Layout>(
+        &mut self,
+        primitive: PrimitiveType,
+        program: &Program,
+        vertices: &Buffer<V>,
+        instances: &Buffer<I>,
+    ) {
+        if vertices.len() > 0 && instances.len() > 0 {
+            program.bind(self);
+
+            let mut map = self.0.format_cache.borrow_mut();
+            let vao = map
+                .entry::<InstancedVertexArray<V, I>>()
+                .or_insert_with(|| InstancedVertexArray::for_vertex_types(self));
+
+            vao.set_buffers(self, vertices, instances);
+            vao.bind(self);
+
+            gl_call!(assert DrawArraysInstanced(
+                primitive as u32,
+                0,
+                vertices.len() as i32,
+                instances.len() as i32
+            ));
+        }
+    }
+
     pub fn limits(&self) -> &Limits {
         &self.0.limits
     }
@@ -1,18 +1,78 @@
-trait GlObject {
-    unsafe fn create(ctx: &Context) -> Self;
+use gl;
+use gl_api::{
+    context::{Context, FRAMEBUFFER_DROP_LIST},
+    texture_array::DepthTextureArray2d,
+};
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct RawFramebuffer {
+    crate id: u32,
 }
 
-// ctx.create::<Framebuffer>()
+impl RawFramebuffer {
+    crate fn new(_ctx: &Context) -> Self {
+        let mut id = 0;
+        gl_call!(assert CreateFramebuffers(1, &mut id));
 
-pub struct RawFramebuffer {
-    id: u32,
+        RawFramebuffer { id }
+    }
 }
 
-impl RawFramebuffer {}
+impl Drop for RawFramebuffer {
+    fn drop(&mut self) {
+        FRAMEBUFFER_DROP_LIST.lock().unwrap().push(self.id);
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct IncompleteFramebuffer(pub u32);
 
 #[derive(Debug)]
 pub struct Framebuffer {
     crate raw: RawFramebuffer,
 }
 
-impl Framebuffer {}
+impl Framebuffer {
+    pub fn new(ctx: &Context) -> Self {
+        Framebuffer {
+            raw: RawFramebuffer::new(ctx),
+        }
+    }
+
+    /// Attaches a single layer of a depth texture array as the depth buffer of
+    /// this framebuffer.
+    pub fn attach_depth_layer(&self, _ctx: &Context, texture: &DepthTextureArray2d, layer: usize) {
+        gl_call!(assert NamedFramebufferTextureLayer(
+            self.raw.id,
+            gl::DEPTH_ATTACHMENT,
+            texture.raw.id,
+            0,
+            layer as i32
+        ));
+    }
+
+    /// Tells GL that this framebuffer has no color attachments, which is what
+    /// we want for depth-only passes.
+    pub fn disable_color(&self, _ctx: &Context) {
+        gl_call!(assert NamedFramebufferDrawBuffer(self.raw.id, gl::NONE));
+        gl_call!(assert NamedFramebufferReadBuffer(self.raw.id, gl::NONE));
+    }
+
+    pub fn check_complete(&self, _ctx: &Context) -> Result<(), IncompleteFramebuffer> {
+        let status = gl_call!(assert CheckNamedFramebufferStatus(self.raw.id, gl::FRAMEBUFFER));
+        if status == gl::FRAMEBUFFER_COMPLETE {
+            Ok(())
+        } else {
+            Err(IncompleteFramebuffer(status))
+        }
+    }
+
+    pub fn bind(&self, _ctx: &Context) {
+        gl_call!(assert BindFramebuffer(gl::FRAMEBUFFER, self.raw.id));
+    }
+
+    /// Binds the window's framebuffer again.
+    pub fn bind_default(_ctx: &Context) {
+        gl_call!(assert BindFramebuffer(gl::FRAMEBUFFER, 0));
+    }
+}
@@ -6,6 +6,7 @@ pub mod layout;
 pub mod buffer;
 pub mod context;
 mod draw;
+pub mod framebuffer;
 pub mod limits;
 pub mod misc;
 pub mod shader;
@@ -1,5 +1,9 @@
 use gl_api::context::Context;
-use std::{io, path::Path};
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
 
 pub mod program;
 pub mod shader;
@@ -12,6 +16,7 @@ use shader::*;
 pub enum PipelineError {
     Shader(ShaderError),
     Io(io::Error),
+    Link(LinkError),
     ProgramCreation,
 }
 
@@ -21,24 +26,15 @@ impl From<ShaderError> for PipelineError {
     }
 }
 
-impl From<io::Error> for PipelineError {
-    fn from(err: io::Error) -> Self {
-        PipelineError::Io(err)
+impl From<LinkError> for PipelineError {
+    fn from(err: LinkError) -> Self {
+        PipelineError::Link(err)
     }
 }
 
-pub fn load_shader<P1: AsRef<Path>, P2: AsRef<Path>>(ctx: &Context, vert: P1, frag: P2) -> Program {
-    match simple_pipeline(ctx, vert, frag) {
-        Ok(program) => program,
-        Err(PipelineError::Shader(ShaderError::Shader(msg))) => {
-            println!("Shader compilation error: {}", msg);
-            panic!();
-        }
-        Err(PipelineError::Io(err)) | Err(PipelineError::Shader(ShaderError::Io(err))) => {
-            println!("Shader compilation error: I/O {:?}", err);
-            panic!();
-        }
-        Err(other) => panic!(other),
+impl From<io::Error> for PipelineError {
+    fn from(err: io::Error) -> Self {
+        PipelineError::Io(err)
     }
 }
 
@@ -46,16 +42,29 @@ pub fn simple_pipeline<P1: AsRef<Path>, P2: AsRef<Path>>(
     ctx: &Context,
     vert: P1,
     frag: P2,
+) -> Result<Program, PipelineError> {
+    let mut vert_source = String::new();
+    let mut frag_source = String::new();
+    File::open(vert)?.read_to_string(&mut vert_source)?;
+    File::open(frag)?.read_to_string(&mut frag_source)?;
+
+    pipeline_from_source(ctx, &vert_source, &frag_source)
+}
+
+pub fn pipeline_from_source(
+    ctx: &Context,
+    vert: &str,
+    frag: &str,
 ) -> Result<Program, PipelineError> {
     let program = ProgramBuilder::new(ctx);
     let vert_shader = Shader::new(ShaderType::Vertex)?;
     let frag_shader = Shader::new(ShaderType::Fragment)?;
 
-    vert_shader.source_from_file(vert)?;
-    frag_shader.source_from_file(frag)?;
+    vert_shader.shader_source(vert);
+    frag_shader.shader_source(frag);
 
     program.attach_shader(vert_shader.compile()?);
     program.attach_shader(frag_shader.compile()?);
 
-    Ok(program.link().unwrap())
+    Ok(program.link()?)
 }
@@ -48,7 +48,7 @@ pub struct Program {
 }
 
 impl Program {
-    pub fn set_uniform<U: Uniform>(&mut self, ctx: &Context, name: &str, uniform: &U) {
+    pub fn set_uniform<U: Uniform + ?Sized>(&mut self, ctx: &Context, name: &str, uniform: &U) {
         self.bind(ctx);
         if let Some(&location) = self.uniform_cache.get(name) {
             uniform.set_uniform(ctx, location);
@@ -98,6 +98,20 @@ crate fn gl_format<P: Pixel>() -> Option<(u32, u32)> {
     Some((format, ty))
 }
 
+/// Parameters for depth textures that are meant to be sampled with a shadow
+/// sampler. Linear filtering combined with the comparison mode gives us
+/// hardware 2x2 PCF for free, and anything outside the map is treated as lit.
+crate fn load_depth_texture_defaults(tex: &RawTexture) {
+    let border = [1.0f32, 1.0, 1.0, 1.0];
+    gl_call!(assert TextureParameteri(tex.id, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32));
+    gl_call!(assert TextureParameteri(tex.id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32));
+    gl_call!(assert TextureParameteri(tex.id, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as i32));
+    gl_call!(assert TextureParameteri(tex.id, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as i32));
+    gl_call!(assert TextureParameterfv(tex.id, gl::TEXTURE_BORDER_COLOR, border.as_ptr()));
+    gl_call!(assert TextureParameteri(tex.id, gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE as i32));
+    gl_call!(assert TextureParameteri(tex.id, gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as i32));
+}
+
 crate fn load_texture_defaults(tex: &RawTexture) {
     gl_call!(assert TextureParameteri(tex.id, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32));
     gl_call!(assert TextureParameteri(tex.id, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32));
@@ -131,6 +145,14 @@ impl Texture2d {
         Texture2d { raw }
     }
 
+    pub fn set_min_filter(&self, _ctx: &Context, filter: MinFilter) {
+        gl_call!(assert TextureParameteri(self.raw.id, gl::TEXTURE_MIN_FILTER, filter as i32));
+    }
+
+    pub fn set_mag_filter(&self, _ctx: &Context, filter: MagFilter) {
+        gl_call!(assert TextureParameteri(self.raw.id, gl::TEXTURE_MAG_FILTER, filter as i32));
+    }
+
     pub fn upload_texture<P, C>(&self, ctx: &Context, image: &ImageBuffer<P, C>)
     where
         P: Pixel + 'static,
@@ -158,8 +180,36 @@ impl Texture2d {
 impl Uniform for Texture2d {
     #[inline(always)]
     fn set_uniform(&self, ctx: &Context, location: UniformLocation) {
-        gl_call!(assert BindTexture(gl::TEXTURE_2D, self.raw.id));
         gl_call!(assert ActiveTexture(gl::TEXTURE0));
+        gl_call!(assert BindTexture(gl::TEXTURE_2D, self.raw.id));
         gl_call!(assert Uniform1i(location, 0));
     }
 }
+
+impl AsRawTexture for Texture2d {
+    fn raw_texture(&self) -> &RawTexture {
+        &self.raw
+    }
+}
+
+/// Anything that is backed by a texture object and can be bound to a texture
+/// unit.
+pub trait AsRawTexture {
+    fn raw_texture(&self) -> &RawTexture;
+}
+
+/// A texture paired with the texture unit it should be bound to. Setting this
+/// as a uniform binds the texture to that unit and points the sampler at it,
+/// which is needed when a program samples from more than one texture.
+pub struct TextureUnit<'t, T: 't>(pub u32, pub &'t T);
+
+impl<'t, T: AsRawTexture> Uniform for TextureUnit<'t, T> {
+    #[inline(always)]
+    fn set_uniform(&self, _ctx: &Context, location: UniformLocation) {
+        let raw = self.1.raw_texture();
+        gl_call!(assert ActiveTexture(gl::TEXTURE0 + self.0));
+        gl_call!(assert BindTexture(raw.texture_type as u32, raw.id));
+        gl_call!(assert Uniform1i(location, self.0 as i32));
+        gl_call!(assert ActiveTexture(gl::TEXTURE0));
+    }
+}
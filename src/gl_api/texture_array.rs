@@ -1,7 +1,9 @@
 use gl;
 use gl_api::{
     shader::uniform::{Uniform, UniformLocation},
-    texture::{load_texture_defaults, RawTexture, TextureType},
+    texture::{
+        load_depth_texture_defaults, load_texture_defaults, AsRawTexture, RawTexture, TextureType,
+    },
     Context,
 };
 use std::ops::Deref;
@@ -120,8 +122,57 @@ impl TextureArray2d {
 impl Uniform for TextureArray2d {
     #[inline(always)]
     fn set_uniform(&self, ctx: &Context, location: UniformLocation) {
-        gl_call!(assert BindTexture(gl::TEXTURE_2D_ARRAY, self.raw.id));
         gl_call!(assert ActiveTexture(gl::TEXTURE0));
+        gl_call!(assert BindTexture(gl::TEXTURE_2D_ARRAY, self.raw.id));
         gl_call!(assert Uniform1i(location, 0));
     }
 }
+
+impl AsRawTexture for TextureArray2d {
+    fn raw_texture(&self) -> &RawTexture {
+        &self.raw
+    }
+}
+
+/// An array of depth textures, meant to be rendered into through a
+/// `Framebuffer` one layer at a time and sampled with `sampler2DArrayShadow`.
+pub struct DepthTextureArray2d {
+    crate raw: RawTexture,
+    width: usize,
+    height: usize,
+    layers: usize,
+}
+
+impl DepthTextureArray2d {
+    pub fn new(ctx: &Context, width: usize, height: usize, layers: usize) -> Self {
+        let raw = RawTexture::new(ctx, TextureType::Texture2DArray);
+
+        gl_call!(assert TextureStorage3D(raw.id, 1, gl::DEPTH_COMPONENT24, width as i32, height as i32, layers as i32));
+        load_depth_texture_defaults(&raw);
+
+        DepthTextureArray2d {
+            raw,
+            width,
+            height,
+            layers,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn layers(&self) -> usize {
+        self.layers
+    }
+}
+
+impl AsRawTexture for DepthTextureArray2d {
+    fn raw_texture(&self) -> &RawTexture {
+        &self.raw
+    }
+}
@@ -16,6 +16,36 @@ impl RawVertexArray {
 
         RawVertexArray { id }
     }
+
+    /// Sets up the attributes of one vertex layout, numbered from `first`,
+    /// to be read from the buffer at `binding`.
+    fn set_up_attributes(&self, attribs: &[AttributeFormat], first: usize, binding: usize) {
+        // TODO: is this actually correct?
+        let mut byte_offset = 0;
+        for (idx, fmt) in attribs.iter().enumerate() {
+            let attr = first + idx;
+            self.enable_attribute(attr);
+            self.set_attribute_format(attr, *fmt, byte_offset);
+            self.set_attribute_binding(attr, binding);
+            byte_offset += fmt.size();
+        }
+    }
+
+    fn enable_attribute(&self, attr: usize) {
+        gl_call!(assert EnableVertexArrayAttrib(self.id, attr as u32));
+    }
+
+    fn set_attribute_binding(&self, attr: usize, binding: usize) {
+        gl_call!(assert VertexArrayAttribBinding(self.id, attr as u32, binding as u32));
+    }
+
+    fn set_attribute_format(&self, attr: usize, fmt: AttributeFormat, offset: usize) {
+        if fmt.ty.is_integer() {
+            gl_call!(assert VertexArrayAttribIFormat(self.id, attr as u32, fmt.dim as i32, fmt.ty as u32, offset as u32));
+        } else {
+            gl_call!(assert VertexArrayAttribFormat(self.id, attr as u32, fmt.dim as i32, fmt.ty as u32, gl::FALSE, offset as u32));
+        }
+    }
 }
 
 impl Drop for RawVertexArray {
@@ -42,35 +72,11 @@ impl<V: Layout> VertexArray<V> {
             attribs: V::layout(),
             _marker: PhantomData,
         };
-
-        // TODO: is this actually correct?
-        let mut byte_offset = 0;
-        for (attr, fmt) in vao.attribs.iter().enumerate() {
-            vao.enable_attribute(attr);
-            vao.set_attribute_format(attr, *fmt, byte_offset);
-            vao.set_attribute_binding(attr, 0);
-            byte_offset += fmt.size();
-        }
+        vao.raw.set_up_attributes(&vao.attribs, 0, 0);
 
         vao
     }
 
-    fn enable_attribute(&self, attr: usize) {
-        gl_call!(assert EnableVertexArrayAttrib(self.raw.id, attr as u32));
-    }
-
-    fn set_attribute_binding(&self, attr: usize, binding: usize) {
-        gl_call!(assert VertexArrayAttribBinding(self.raw.id, attr as u32, binding as u32));
-    }
-
-    fn set_attribute_format(&self, attr: usize, fmt: AttributeFormat, offset: usize) {
-        if fmt.ty.is_integer() {
-            gl_call!(assert VertexArrayAttribIFormat(self.raw.id, attr as u32, fmt.dim as i32, fmt.ty as u32, offset as u32));
-        } else {
-            gl_call!(assert VertexArrayAttribFormat(self.raw.id, attr as u32, fmt.dim as i32, fmt.ty as u32, gl::FALSE, offset as u32));
-        }
-    }
-
     pub fn bind(&self, _ctx: &Context) {
         // UNWRAP: our ID should always be valid
         gl_call!(debug BindVertexArray(self.raw.id));
@@ -87,3 +93,41 @@ impl<V: Layout> VertexArray<V> {
         self
     }
 }
+
+/// A vertex array that reads `V` once per vertex from binding index 0, and `I`
+/// once per instance from binding index 1. The attributes of `I` are numbered
+/// after the attributes of `V`.
+#[derive(Debug, Eq, PartialEq)]
+pub struct InstancedVertexArray<V, I> {
+    crate raw: RawVertexArray,
+    _marker: PhantomData<*const (V, I)>,
+}
+
+unsafe impl<V, I> Send for InstancedVertexArray<V, I> {}
+unsafe impl<V, I> Sync for InstancedVertexArray<V, I> {}
+
+impl<V: Layout, I: Layout> InstancedVertexArray<V, I> {
+    pub fn for_vertex_types(ctx: &Context) -> Self {
+        let vao = InstancedVertexArray {
+            raw: RawVertexArray::new(ctx),
+            _marker: PhantomData,
+        };
+
+        let vertex_attribs = V::layout();
+        vao.raw.set_up_attributes(&vertex_attribs, 0, 0);
+        vao.raw.set_up_attributes(&I::layout(), vertex_attribs.len(), 1);
+        // move on to the next instance after every vertex has been drawn
+        gl_call!(assert VertexArrayBindingDivisor(vao.raw.id, 1, 1));
+
+        vao
+    }
+
+    pub fn bind(&self, _ctx: &Context) {
+        gl_call!(debug BindVertexArray(self.raw.id));
+    }
+
+    pub fn set_buffers(&mut self, _ctx: &Context, vertices: &Buffer<V>, instances: &Buffer<I>) {
+        gl_call!(assert VertexArrayVertexBuffer(self.raw.id, 0, vertices.raw.id, 0, ::std::mem::size_of::<V>() as i32));
+        gl_call!(assert VertexArrayVertexBuffer(self.raw.id, 1, instances.raw.id, 0, ::std::mem::size_of::<I>() as i32));
+    }
+}
@@ -36,6 +36,7 @@ extern crate rodio;
 extern crate serde_json;
 extern crate simple_logger;
 extern crate test;
+extern crate zip;
 
 // need this due to weird quirk of shred_derive
 use engine::world::ChunkPos;
@@ -43,37 +44,54 @@ pub use specs::shred;
 
 #[macro_use]
 pub mod gl_api;
+mod cli;
 pub mod engine;
 pub mod handle;
 pub mod util;
 
-use cgmath::{Deg, Point3, Vector3};
+use cgmath::{Point3, Vector3};
+use cli::{CliError, Command, Options};
 use collision::Aabb3;
 use engine::{
+    assets::{AssetChanged, AssetManager},
     audio::AudioManager,
     camera::Camera,
     components as comp,
+    console::{CommandRegistry, Console, ConsoleCommand},
+    item::ItemRegistry,
     job::Worker,
+    keybinds::Keybinds,
+    mob::MobRegistry,
+    model::ModelRegistry,
+    replay::Replay,
     render::{
+        breaking::DrawBlockBreaking,
+        console::DrawConsole,
+        entity::DrawEntities,
+        item::DrawDroppedItems,
         mesher::{ChunkMesher, CullMesher},
-        ui::DrawCrosshair,
+        overlay::DebugOverlay,
+        problems::DrawAssetProblems,
+        textures::{SharedTextures, UpdateTextures},
+        ui::{DrawCrosshair, DrawHotbar, DrawStatusBars},
     },
     resources as res,
+    settings::Settings,
     world::{
         block::{BlockRegistry, Faces},
-        gen::NoiseGenerator,
+        gen::{NoiseGenerator, WorldGenerator},
+        info::{PlayerInfo, WorldInfo},
+        spatial::EntityIndex,
         VoxelWorld,
     },
 };
-use gl_api::{
-    context::Context,
-    misc,
-    shader::{shader::ShaderError, *},
-};
+use gl_api::{context::Context, misc};
 use glutin::{dpi::*, GlContext, GlWindow};
 use shrev::EventChannel;
 use specs::prelude::*;
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
+
+const SETTINGS_PATH: &str = "settings.json";
 
 mod benches {
     use super::*;
@@ -81,7 +99,7 @@ mod benches {
 
     #[bench]
     fn bench_mesher(bencher: &mut Bencher) {
-        let (registry, _) = BlockRegistry::load_from_file("resources/blocks.json").unwrap();
+        let registry = BlockRegistry::load_from_file("resources/blocks.json").unwrap();
         let mut world = VoxelWorld::new(registry);
         let mut gen = NoiseGenerator::new_default();
 
@@ -102,13 +120,97 @@ mod benches {
 
 }
 
+/// Checks the resources without opening a window, returning the exit code.
+fn validate_resources(assets: &AssetManager) -> i32 {
+    use engine::render::terrain::TEXTURE_SIZE;
+
+    let registry = match BlockRegistry::load_validated(assets, TEXTURE_SIZE) {
+        Ok(registry) => registry,
+        Err(err) => {
+            println!("{}", err);
+            return 1;
+        }
+    };
+
+    let items = match ItemRegistry::load_validated(assets, &registry, TEXTURE_SIZE) {
+        Ok(items) => items,
+        Err(err) => {
+            println!("{}", err);
+            return 1;
+        }
+    };
+
+    let models = match ModelRegistry::load_validated(assets) {
+        Ok(models) => models,
+        Err(err) => {
+            println!("{}", err);
+            return 1;
+        }
+    };
+
+    match MobRegistry::load_from_assets(assets, &registry, &models) {
+        Ok(mobs) => {
+            println!(
+                "Resources are valid: {} block states using {} textures, {} items, {} models \
+                 and {} mobs",
+                registry.state_count(),
+                registry.textures().len(),
+                items.item_count(),
+                models.model_count(),
+                mobs.mob_count()
+            );
+            0
+        }
+        Err(err) => {
+            println!("{}", err);
+            1
+        }
+    }
+}
+
 fn main() {
-    simple_logger::init_with_level(log::Level::Debug).unwrap();
+    let options = match Options::from_env() {
+        Ok(options) => options,
+        Err(CliError::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    simple_logger::init_with_level(options.log_level).unwrap();
+    let settings = match Settings::load_from_file(SETTINGS_PATH) {
+        Ok(settings) => settings,
+        Err(err) => {
+            warn!("Could not load settings, using the defaults: {}", err);
+            Settings::default()
+        }
+    };
+    // overrides from the command line only last for this launch, so they are
+    // kept out of the settings, which can be saved
+    let (window_width, window_height) = options
+        .window_size
+        .unwrap_or((settings.window_width, settings.window_height));
+    let assets = match AssetManager::new(options.resource_packs()) {
+        Ok(assets) => Arc::new(assets),
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if options.command == Command::ValidateResources {
+        std::process::exit(validate_resources(&assets));
+    }
+
     let mut events_loop = glutin::EventsLoop::new();
     let window = glutin::WindowBuilder::new()
         .with_title("Hello, world!")
-        .with_dimensions(LogicalSize::new(1024.0, 768.0));
-    let context = glutin::ContextBuilder::new().with_vsync(true);
+        .with_dimensions(LogicalSize::new(window_width as f64, window_height as f64));
+    let context = glutin::ContextBuilder::new().with_vsync(settings.vsync);
     let gl_window = glutin::GlWindow::new(window, context, &events_loop).unwrap();
 
     gl_window.grab_cursor(true).unwrap();
@@ -122,21 +224,6 @@ fn main() {
     let mut ctx = Context::load(|symbol| gl_window.get_proc_address(symbol));
     println!("Context created!");
 
-    let mut debug_program = match simple_pipeline(
-        &mut ctx,
-        "resources/shaders/debug.vs",
-        "resources/shaders/debug.fs",
-    ) {
-        Ok(prog) => prog,
-        Err(msg) => match msg {
-            PipelineError::Shader(ShaderError::Shader(msg)) => {
-                println!("{}", msg);
-                panic!()
-            }
-            _ => panic!("Other error"),
-        },
-    };
-
     gl_call!(assert Disable(gl::MULTISAMPLE));
     gl_call!(assert Enable(gl::DEPTH_TEST));
     gl_call!(assert DepthFunc(gl::LESS));
@@ -149,9 +236,6 @@ fn main() {
 
     let mut window_events = shrev::EventChannel::new();
 
-    let projection = ::cgmath::perspective(Deg(70.0), 600.0 / 600.0, 0.1, 1000.0f32);
-    debug_program.set_uniform(&mut ctx, "projection", &projection);
-
     let mut world = World::default();
 
     // world.register::<Handle<::engine::render::terrain::GpuChunkMesh>>();
@@ -163,26 +247,156 @@ fn main() {
     world.register::<comp::ChunkId>();
     world.register::<comp::DirtyMesh>();
     world.register::<comp::Collidable>();
-
-    let (registry, tex_names) = BlockRegistry::load_from_file("resources/blocks.json").unwrap();
+    world.register::<comp::Inventory>();
+    world.register::<comp::GameMode>();
+    world.register::<comp::DroppedItem>();
+    world.register::<comp::Flying>();
+    world.register::<comp::Grounded>();
+    world.register::<comp::Airborne>();
+    world.register::<comp::Health>();
+    world.register::<comp::Breath>();
+    world.register::<comp::Mob>();
+    world.register::<comp::Navigation>();
+    world.register::<comp::EntityModel>();
+
+    let registry = match BlockRegistry::load_from_assets(&assets) {
+        Ok(registry) => registry,
+        Err(err) => {
+            error!("Could not load the blocks: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let items = match ItemRegistry::load_from_assets(&assets, &registry) {
+        Ok(items) => items,
+        Err(err) => {
+            error!("Could not load the items: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let models = match ModelRegistry::load_from_assets(&assets) {
+        Ok(models) => models,
+        Err(err) => {
+            error!("Could not load the models: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let mobs = match MobRegistry::load_from_assets(&assets, &registry, &models) {
+        Ok(mobs) => mobs,
+        Err(err) => {
+            error!("Could not load the mobs: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let textures = SharedTextures::load(&ctx, &assets, registry.textures(), items.textures());
+    let terrain_blocks = TerrainBlocks::from_registry(&registry);
     let voxel_world = VoxelWorld::new(registry);
 
-    let player_tfm = comp::Transform::default();
-    world
+    let mut world_info = None;
+    let mut replay = Replay::default();
+    if let Some(path) = &options.replay {
+        match Replay::load(path) {
+            Ok((loaded, recorded_world)) => {
+                info!("Replaying input from `{}`", path.display());
+                replay = loaded;
+                world_info = Some(recorded_world);
+            }
+            Err(err) => warn!("Could not load replay `{}`: {}", path.display(), err),
+        }
+    }
+
+    if let (None, Some(dir)) = (world_info, &options.world) {
+        match WorldInfo::load(dir) {
+            Ok(loaded) => world_info = loaded,
+            Err(err) => warn!("Could not load the world in `{}`: {}", dir.display(), err),
+        }
+    }
+
+    let world_info = match world_info {
+        Some(info) => {
+            if options.seed.is_some() || options.generator.is_some() {
+                warn!("Ignoring `--seed` and `--generator` for an existing world");
+            }
+            info
+        }
+        None => {
+            let info = WorldInfo {
+                seed: options.seed.unwrap_or_else(rand::random),
+                generator: options.generator.unwrap_or_default(),
+            };
+            if let Some(dir) = &options.world {
+                if let Err(err) = info.save(dir) {
+                    warn!("Could not save the world to `{}`: {}", dir.display(), err);
+                }
+            }
+            info
+        }
+    };
+
+    if let Some(path) = &options.record {
+        match Replay::record(path, world_info) {
+            Ok(recorder) => {
+                info!("Recording input to `{}`", path.display());
+                replay = recorder;
+            }
+            Err(err) => warn!("Could not start recording to `{}`: {}", path.display(), err),
+        }
+    }
+    info!(
+        "World seed: {} ({:?} generator)",
+        world_info.seed, world_info.generator
+    );
+
+    let generator = WorldGenerator::new(world_info.generator, world_info.seed, terrain_blocks);
+    let player_box = Aabb3::new(Point3::new(-0.4, -1.6, -0.4), Point3::new(0.4, 0.2, 0.4));
+    // the player's position is at their eyes, above the bottom of their box
+    let spawn_point =
+        generator.spawn_block().base().0 + Vector3::new(0.5, -player_box.min.y, 0.5);
+
+    // the player is only kept between runs when input isn't being recorded or
+    // replayed, since a replay has to start from the same place as its recording
+    let player_dir = match (&options.world, &options.record, &options.replay) {
+        (Some(dir), None, None) => Some(dir.clone()),
+        _ => None,
+    };
+    let player_info = match player_dir.as_ref().map(PlayerInfo::load) {
+        Some(Ok(info)) => info,
+        Some(Err(err)) => {
+            warn!("Could not load the player, starting from the spawn: {}", err);
+            None
+        }
+        _ => None,
+    };
+    let player_info = player_info.unwrap_or_else(|| PlayerInfo {
+        position: spawn_point.into(),
+        ..PlayerInfo::default()
+    });
+
+    let player_tfm = comp::Transform::default().with_position(player_info.position.into());
+    let player = world
         .create_entity()
         .with(comp::ClientControlled)
         .with(comp::Player)
         .with(player_tfm)
-        .with(comp::Collidable {
-            aabb: Aabb3::new(Point3::new(-0.4, -1.6, -0.4), Point3::new(0.4, 0.2, 0.4)),
-        })
+        .with(comp::Collidable { aabb: player_box })
         .with(comp::RigidBody {
             mass: 100.0,
             drag: Vector3::new(3.0, 6.0, 3.0),
             velocity: Vector3::new(0.0, 0.0, 0.0),
         })
         .with(comp::LookTarget::default())
+        .with(comp::Inventory::new(comp::HOTBAR_SIZE * 4))
+        .with(comp::Health::new(20))
+        .with(comp::Breath::new(15.0))
+        .with(player_info.game_mode)
         .build();
+    if player_info.flying {
+        let _ = world.write_storage::<comp::Flying>().insert(player, comp::Flying);
+    }
+    if let Some(model) = models.get_id("player") {
+        let _ = world
+            .write_storage::<comp::EntityModel>()
+            .insert(player, comp::EntityModel::new(model));
+    }
 
     use engine::{
         render::{debug::*, terrain::*},
@@ -207,14 +421,16 @@ fn main() {
         inner: S,
         name: &'static str,
         samples: Vec<Duration>,
+        timings: res::SystemTimings,
     }
 
     impl<S> TraceSystem<S> {
-        fn new(inner: S, name: &'static str) -> Self {
+        fn new(inner: S, name: &'static str, timings: &res::SystemTimings) -> Self {
             TraceSystem {
                 inner,
                 name,
                 samples: Vec::new(),
+                timings: timings.clone(),
             }
         }
     }
@@ -235,6 +451,7 @@ fn main() {
                     sum / len,
                     self.name,
                 );
+                self.timings.record(self.name, sum / len);
             }
 
             let before = Instant::now();
@@ -245,12 +462,16 @@ fn main() {
         }
     }
 
-    let terrain_renderer = TerrainRenderer::new(&mut ctx, tex_names);
+    let mut asset_changes = EventChannel::<AssetChanged>::new();
+    let terrain_renderer =
+        TerrainRenderer::new(&mut ctx, assets.clone(), &mut asset_changes, &textures);
 
-    let (debug_rendering_system, debug_accumulator) = DebugRenderer::new(&mut ctx);
+    let (debug_rendering_system, debug_accumulator) =
+        DebugRenderer::new(&mut ctx, assets.clone(), &mut asset_changes);
 
     fn attach_system<'a, 'b, T>(
         builder: DispatcherBuilder<'a, 'b>,
+        timings: &res::SystemTimings,
         sys: T,
         name: &'static str,
         deps: &[&str],
@@ -258,54 +479,245 @@ fn main() {
     where
         T: for<'c> System<'c> + Send + 'a,
     {
-        builder.with(TraceSystem::new(sys, name), name, deps)
+        builder.with(TraceSystem::new(sys, name, timings), name, deps)
     }
 
     fn attach_system_sync<'a, 'b, T>(
         builder: DispatcherBuilder<'a, 'b>,
+        timings: &res::SystemTimings,
         sys: T,
         name: &'static str,
     ) -> DispatcherBuilder<'a, 'b>
     where
         T: for<'c> System<'c> + 'b,
     {
-        builder.with_thread_local(TraceSystem::new(sys, name))
+        builder.with_thread_local(TraceSystem::new(sys, name, timings))
     }
 
+    let mut command_channel = EventChannel::<ConsoleCommand>::new();
+    let mut command_registry = CommandRegistry::default();
+    let mut landings = EventChannel::<comp::Landed>::new();
+    let mut damage = EventChannel::<comp::Damage>::new();
+
+    let timings = res::SystemTimings::default();
     let mut builder = DispatcherBuilder::new();
-    builder = attach_system(builder, CameraUpdater::default(), "camera updater", &[]);
-    builder = attach_system(builder, ChunkUnloader::default(), "chunk unloader", &[]);
-    builder = attach_system(builder, AudioManager::new(), "audio manager", &[]);
     builder = attach_system(
         builder,
-        CameraRotationUpdater::new(&mut window_events),
+        &timings,
+        ActionMapper::new(&mut window_events),
+        "action mapper",
+        &[],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        AssetWatcher::new(assets.clone()),
+        "asset watcher",
+        &[],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        BlockReloader::new(assets.clone(), &mut asset_changes),
+        "block reloader",
+        &["asset watcher"],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        CameraUpdater::default(),
+        "camera updater",
+        &["action mapper"],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        ChunkUnloader::default(),
+        "chunk unloader",
+        &[],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        AudioManager::new(assets.clone()),
+        "audio manager",
+        &[],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        CameraRotationUpdater,
         "cursor input handler",
+        &["action mapper"],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        PlayerController,
+        "player controller",
         &[],
     );
-    builder = attach_system(builder, PlayerController, "player controller", &[]);
-    builder = attach_system(builder, Physics::new(), "physics", &[]);
+    builder = attach_system(builder, &timings, MobAi, "mob ai", &[]);
+    builder = attach_system(
+        builder,
+        &timings,
+        MobSpawner::default(),
+        "mob spawner",
+        &["mob ai"],
+    );
     builder = attach_system(
         builder,
-        BlockInteraction::new(&mut window_events),
+        &timings,
+        Pathfinding::new(),
+        "pathfinding",
+        &["mob ai"],
+    );
+    builder = attach_system(builder, &timings, Physics::new(), "physics", &[]);
+    builder = attach_system(
+        builder,
+        &timings,
+        BlockInteraction,
         "block interactions",
+        &["physics", "action mapper"],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        DroppedItems,
+        "dropped items",
+        &["block interactions"],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        DamageSources::new(&mut landings),
+        "damage sources",
         &["physics"],
     );
-    builder = attach_system(builder, TerrainGenerator::new(), "terrain generator", &[]);
     builder = attach_system(
         builder,
+        &timings,
+        ApplyDamage::new(&mut damage),
+        "apply damage",
+        &["damage sources"],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        TerrainGenerator::new(generator),
+        "terrain generator",
+        &[],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
         ChunkMesher::new(),
         "chunk mesher",
         &["terrain generator"],
     );
+    builder = attach_system(
+        builder,
+        &timings,
+        PlayerCommands::new(&mut command_channel, &mut command_registry),
+        "player commands",
+        &[],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        WorldCommands::new(&mut command_channel, &mut command_registry),
+        "world commands",
+        &[],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        GameCommands::new(&mut command_channel, &mut command_registry),
+        "game commands",
+        &[],
+    );
+    builder = attach_system(
+        builder,
+        &timings,
+        SettingsCommands::new(&mut command_channel, &mut command_registry, SETTINGS_PATH),
+        "settings commands",
+        &[],
+    );
 
     builder = attach_system_sync(
         builder,
+        &timings,
         InputHandler::new(&mut window_events),
         "input handler",
     );
-    builder = attach_system_sync(builder, terrain_renderer, "terrain renderer");
-    builder = attach_system_sync(builder, debug_rendering_system, "debug renderer");
-    builder = attach_system_sync(builder, DrawCrosshair::new(&ctx), "crosshair renderer");
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        ConsoleSystem::new(&mut window_events),
+        "console",
+    );
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        UpdateTextures::new(&ctx, assets.clone(), &mut asset_changes),
+        "texture updater",
+    );
+    builder = attach_system_sync(builder, &timings, terrain_renderer, "terrain renderer");
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        DrawBlockBreaking::new(&ctx, assets.clone(), &mut asset_changes),
+        "block breaking renderer",
+    );
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        DrawDroppedItems::new(&ctx, assets.clone(), &mut asset_changes),
+        "dropped item renderer",
+    );
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        DrawEntities::new(&ctx, assets.clone(), &mut asset_changes, &models),
+        "entity renderer",
+    );
+    builder = attach_system_sync(builder, &timings, debug_rendering_system, "debug renderer");
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        DrawCrosshair::new(&ctx, assets.clone(), &mut asset_changes),
+        "crosshair renderer",
+    );
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        DrawHotbar::new(&ctx, assets.clone(), &mut asset_changes),
+        "hotbar renderer",
+    );
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        DrawStatusBars::new(&ctx, assets.clone(), &mut asset_changes),
+        "status bar renderer",
+    );
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        DebugOverlay::new(&ctx, assets.clone(), &mut asset_changes),
+        "debug overlay",
+    );
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        DrawConsole::new(&ctx, assets.clone(), &mut asset_changes),
+        "console renderer",
+    );
+    builder = attach_system_sync(
+        builder,
+        &timings,
+        DrawAssetProblems::new(&ctx, assets.clone(), &mut asset_changes),
+        "asset problem renderer",
+    );
 
     let mut dispatcher = builder.build();
 
@@ -318,8 +730,47 @@ fn main() {
     world.add_resource(window_events);
     world.add_resource(res::Dt(Duration::from_secs(1)));
     world.add_resource(Camera::default());
+    world.add_resource(res::SunDirection::default());
+    world.add_resource(timings);
+    world.add_resource(res::WorldSeed(world_info.seed));
+    world.add_resource(res::SimulationRng::new(res::WorldSeed(world_info.seed)));
+    world.add_resource(replay);
+    world.add_resource(settings);
+
+    let keybinds = match assets
+        .read_to_string("keybinds.json")
+        .map_err(Into::into)
+        .and_then(|text| Keybinds::load_from_str(&text))
+    {
+        Ok(keybinds) => keybinds,
+        Err(err) => {
+            warn!("Could not load keybinds, using the defaults: {}", err);
+            Keybinds::default()
+        }
+    };
+    world.add_resource(keybinds);
+
+    let mut console = Console::default();
+    // commands to run at startup, mostly so that tests can be scripted
+    if let Some(path) = &options.script {
+        if let Err(err) = console.run_script(path) {
+            warn!("Could not load script `{}`: {}", path.display(), err);
+        }
+    }
+    world.add_resource(console);
+    world.add_resource(command_channel);
+    world.add_resource(command_registry);
+    world.add_resource(asset_changes);
+    world.add_resource(landings);
+    world.add_resource(damage);
+    world.add_resource(res::SpawnPoint(spawn_point));
+    world.add_resource(EntityIndex::default());
 
     world.add_resource(voxel_world);
+    world.add_resource(items);
+    world.add_resource(textures);
+    world.add_resource(models);
+    world.add_resource(mobs);
     world.add_resource(gl_window);
 
     println!("World set up");
@@ -347,9 +798,7 @@ fn main() {
             let size = window.get_inner_size().unwrap();
             if size != window_size {
                 window_size = size;
-                let size: (u32, u32) = size.to_physical(window.get_hidpi_factor()).into();
-                gl_call!(Viewport(0, 0, size.0 as i32, size.1 as i32))
-                    .expect("Failed to set viewport size");
+                ctx.set_viewport(&*window);
             }
         });
 
@@ -368,7 +817,11 @@ fn main() {
         // Update systems and the world.
         world.maintain();
         world.res.insert(res::StopGameLoop(false));
+        world.exec(|(mut replay, mut dt): (Write<'_, Replay>, Write<'_, res::Dt>)| {
+            dt.0 = replay.begin_tick(dt.0);
+        });
         dispatcher.dispatch(&world.res);
+        world.write_resource::<Replay>().end_tick();
         let processing_end = Instant::now();
 
         // Swap the backbuffer
@@ -390,4 +843,22 @@ fn main() {
             );
         }
     }
+
+    if let Some(dir) = &player_dir {
+        let info = PlayerInfo {
+            position: world
+                .read_storage::<comp::Transform>()
+                .get(player)
+                .map_or(player_info.position, |tfm| tfm.position.into()),
+            game_mode: world
+                .read_storage::<comp::GameMode>()
+                .get(player)
+                .cloned()
+                .unwrap_or_default(),
+            flying: world.read_storage::<comp::Flying>().get(player).is_some(),
+        };
+        if let Err(err) = info.save(dir) {
+            warn!("Could not save the player to `{}`: {}", dir.display(), err);
+        }
+    }
 }